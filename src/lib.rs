@@ -1,171 +1,476 @@
 #![deny(clippy::all)]
 use regex::Regex;
-use std::{collections::{HashMap, HashSet}, env::VarError, error::Error, fmt::format, io, os::windows::process::CommandExt, process::{Command, Output}};
-use napi::{Error as napiError, JsError};
-use structs::{Author, AuthorStatDailyContribute, Branch, BranchCreatedInfo, BranchStatDailyContribute, FileDiffContext, FileLineChangeStat, FileStatus, FileStatusReport, FileStatusType, Remote, RepoFileInfo, RepoStatus, RepositoryFull, RepositorySimple, StatDailyContribute};
-use util::{build_commit_range, get_basename, get_current_time};
+use std::{collections::{HashMap, HashSet}, env::VarError, fmt::format, io, os::windows::process::CommandExt, process::{Command, Output, Stdio}, io::{BufRead, BufReader, Read, Write}, sync::{atomic::{AtomicU64, Ordering}, RwLock}};
+use napi::{bindgen_prelude::Buffer, threadsafe_function::{ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode}, Error as napiError, JsError, JsFunction, Status};
+use structs::{AheadBehind, Author, AuthorStatDailyContribute, AuthorTotals, BlameLine, Branch, BranchCreatedInfo, BranchDetail, BranchRef, BranchUpdates, CherryPickResult, CherryPickStatus, BranchStatDailyContribute, CommitDecorations, CommitDetail, CommitGraphNode, DiffHunk, DiffLine, DiffLineOrigin, CommitLogFilter, CommitLogPage, CommitOptions, CommitPatch, CloneOptions, CommitSignature, CommitSummary, ConflictVersions, ContentKind, DiffProgress, ExtensionChangeStat, FileBusFactor, FileDiffContext, FileLineChangeStat, FileNumstat, FileStatus, FileStatusReport, FileStatusType, GitOutput, LfsPointer, MergeOptions, MergeResult, MergeStatus, ObjectInfo, ObjectKind, ObjectType, PathClassification, PathFailure, RangeSummary, ReflogEntry, Remote, RemoteDetail, RenameOptions, RepoFileInfo, RepoStats, RepoStatus, RepositoryFull, RepositorySimple, RepositoryValidation, SignatureStatus, StashApplyResult, StashApplyStatus, StashEntry, StatDailyContribute, SubmoduleInfo, SubmoduleStatus, TagInfo, TransferProgress, UncommittedFileStat, WorkingTreeFileStatus, WorkingTreeStat, WordDiffHunk, WordDiffSegment};
+use util::{build_commit_range, build_diff_range, EMPTY_TREE_HASH, decode_text, decode_with_fallback, detect_utf16, get_basename, get_current_time, is_generated_path, is_vendored_path, parse_linguist_attributes, unquote_git_path};
 
 
 mod structs;
+mod tasks;
 mod util;
 #[macro_use]
 extern crate napi_derive;
+// the unit and record separator control bytes, git prints them verbatim. They are unlikely in commit text but not forbidden,
+// so the fields which may hold free text, e.g. subjects, messages and paths, are terminated by `%x00` and split on NUL instead,
+// with `-z` when the paths are printed too. COMMIT_INETRVAL then only marks the first field of a commit
+static PARAM_INTERVAL: &str = "\x1f";
+static COMMIT_INETRVAL: &str = "\x1e";
 
-static PARAM_INTERVAL: &str = "<<PARAM_INTERVAL>>";
-static COMMIT_INETRVAL: &str = "<<COMMIT_INETRVAL>>";
+// the environment overrides of every spawned command, set by `set_git_env`
+static GIT_ENV: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+// the cap of the output read into the memory, 0 is unlimited, set by `set_max_output_bytes`
+static MAX_OUTPUT_BYTES: AtomicU64 = AtomicU64::new(0);
+// the encoding of the commit messages which are not UTF-8, set by `set_log_encoding`
+static LOG_ENCODING: RwLock<Option<&'static encoding_rs::Encoding>> = RwLock::new(None);
 
-fn get_command_output(prog: &str, path: &str, args: &[&str]) -> io::Result<Output> {
+fn build_command(prog: &str, path: &str, args: &[&str]) -> Command {
     let mut cmd = Command::new(prog);
     args.iter().for_each(|arg| {
         cmd.arg(arg);
     });
+    // a missing credential fails instead of waiting for a prompt nobody can answer
+    cmd.env("GIT_TERMINAL_PROMPT", "0");
+    // the messages of git are matched in English, e.g. "would be overwritten by" or "not fully merged"
+    cmd.env("LC_ALL", "C");
+    cmd.env("LANGUAGE", "C");
+    if let Ok(git_env) = GIT_ENV.read() {
+        for (key, value) in git_env.iter() {
+            if value.is_empty() {
+                cmd.env_remove(key);
+            } else {
+                cmd.env(key, value);
+            }
+        }
+    }
     // 创建进程时，设置创建进程的标志，以隐藏窗口
     cmd.creation_flags(0x08000000);
     if !path.is_empty(){
         cmd.current_dir(path);
     }
-    cmd.output()
+    cmd
+}
+
+/**
+ * Run the command and read its output, the stdout over `MAX_OUTPUT_BYTES` kills the command and fails
+ * with `io::ErrorKind::OutOfMemory` instead of growing the memory, the stderr over it is dropped
+ */
+fn get_command_output(prog: &str, path: &str, args: &[&str]) -> io::Result<Output> {
+    let max_bytes = MAX_OUTPUT_BYTES.load(Ordering::Relaxed);
+    if max_bytes == 0 {
+        return build_command(prog, path, args).output()
+    }
+    let mut child = build_command(prog, path, args).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stderr_reader = spawn_stderr_reader(&mut child, max_bytes);
+    let mut stdout = Vec::new();
+    let read = child.stdout.take().unwrap().take(max_bytes.saturating_add(1)).read_to_end(&mut stdout);
+    let exceeded = stdout.len() as u64 > max_bytes;
+    if read.is_err() || exceeded {
+        let _ = child.kill();
+    }
+    let status = child.wait()?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    read?;
+    if exceeded {
+        let command = format!("{} {}", prog, args.first().unwrap_or(&""));
+        return Err(io::Error::new(io::ErrorKind::OutOfMemory, format!("the output of `{}` exceeds the limit of {} bytes", command.trim(), max_bytes)))
+    }
+    Ok(Output { status, stdout, stderr })
+}
+
+/**
+ * Read the stderr of the child on another thread, git blocks on a full stderr pipe and never closes the stdout.
+ * The first `max_bytes` are kept, the rest is dropped
+ */
+fn spawn_stderr_reader(child: &mut std::process::Child, max_bytes: u64) -> std::thread::JoinHandle<Vec<u8>> {
+    let stderr_pipe = child.stderr.take();
+    std::thread::spawn(move || {
+        let mut stderr = Vec::new();
+        if let Some(mut stderr_pipe) = stderr_pipe {
+            let _ = (&mut stderr_pipe).take(max_bytes).read_to_end(&mut stderr);
+            let _ = io::copy(&mut stderr_pipe, &mut io::sink());
+        }
+        stderr
+    })
+}
+
+/**
+ * Run a git command and keep both the stdout and the stderr as text, git writes the warnings and the hints
+ * to the stderr even when it succeeds, the exit code is -1 when git is terminated by a signal
+ */
+fn run_git_command(path: &str, args: &[&str]) -> io::Result<GitOutput> {
+    let output = get_command_output("git", path, args)?;
+    Ok(GitOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: output.status.code().unwrap_or(-1),
+    })
+}
+
+/**
+ * Run the command like `get_command_output`, but treat a non-zero exit code as an error carrying the stderr
+ */
+fn get_checked_command_output(prog: &str, path: &str, args: &[&str]) -> io::Result<Output> {
+    let output = get_command_output(prog, path, args)?;
+    if output.status.success() {
+        return Ok(output)
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if is_dubious_ownership(&stderr) {
+        return Err(io::Error::new(io::ErrorKind::Other, dubious_ownership_message(path)))
+    }
+    let command = format!("{} {}", prog, args.first().unwrap_or(&""));
+    Err(io::Error::new(io::ErrorKind::Other, format!("`{}` exited with {}: {}", command.trim(), output.status, stderr.trim())))
+}
+
+/**
+ * Git refuses to work in a repository owned by another user unless it is listed in safe.directory
+ */
+fn is_dubious_ownership (stderr: &str) -> bool {
+    stderr.contains("detected dubious ownership")
+}
+
+fn dubious_ownership_message (path: &str) -> String {
+    format!("the repository at {} is owned by another user, add it to safe.directory (see `set_safe_directory`) to trust it", path)
 }
 
 // 使用Result来处理可能会抛出异常的函数
 
+#[napi]
+/**
+ * Set the environment variables of every git command spawned after it, e.g. `GIT_SSH_COMMAND` or `HOME`,
+ * they replace the variables set by the previous call. An empty value removes the variable from the environment.
+ * `GIT_TERMINAL_PROMPT` is "0" and `LC_ALL`, `LANGUAGE` are "C" unless they are overridden here
+ * @param env the map from the name of the variable to its value
+ */
+pub fn set_git_env (env: HashMap<String, String>) {
+    let mut git_env = env.into_iter().collect::<Vec<(String, String)>>();
+    git_env.sort();
+    if let Ok(mut current) = GIT_ENV.write() {
+        *current = git_env;
+    }
+}
+
+#[napi]
+/**
+ * Limit the output of every git command read into the memory after it, a command printing more fails
+ * instead of exhausting the memory of the process. The streamed commands like `get_commit_log_format_stream`
+ * are not limited, they never keep the whole output
+ * @param max_bytes the limit in bytes, 0 or a negative value removes the limit
+ */
+pub fn set_max_output_bytes (max_bytes: i64) {
+    MAX_OUTPUT_BYTES.store(max_bytes.max(0) as u64, Ordering::Relaxed);
+}
+
+#[napi]
+/**
+ * Set the encoding of the commit messages written without an encoding header by an old git in a non-UTF-8 locale,
+ * e.g. "GBK" or "Shift_JIS". Git converts the messages with an encoding header to UTF-8 by itself,
+ * the messages which are not valid UTF-8 are decoded with this encoding instead of being replaced by "\u{FFFD}"
+ * @param encoding the label of the encoding, "" restores the default of replacing the invalid bytes
+ */
+pub fn set_log_encoding (encoding: String) -> Result<(), JsError> {
+    let log_encoding = if encoding.is_empty() {
+        None
+    } else {
+        match encoding_rs::Encoding::for_label(encoding.trim().as_bytes()) {
+            Some(log_encoding) => Some(log_encoding),
+            None => return Err(JsError::from(CustomerGitError::UnknownEncoding(encoding))),
+        }
+    };
+    if let Ok(mut current) = LOG_ENCODING.write() {
+        *current = log_encoding;
+    }
+    Ok(())
+}
+
+/**
+ * Decode the output of a command printing the commit messages with the encoding of `set_log_encoding`
+ */
+fn decode_log_output (content: &[u8]) -> String {
+    let log_encoding = LOG_ENCODING.read().ok().and_then(|log_encoding| *log_encoding);
+    decode_with_fallback(content, log_encoding)
+}
+
 #[napi]
 /**
  * Check if git is installed
  */
 pub fn has_git () -> bool {
-    let output = get_command_output("git", "", &["--version"]).expect("failed to execute process");
-    if output.status.success() {
-        return true
+    has_program("git")
+}
+
+/**
+ * Check if a program can be spawned and reports its version successfully
+ */
+fn has_program (prog: &str) -> bool {
+    let output = get_command_output(prog, "", &["--version"]);
+    match output {
+        Ok(output) => output.status.success(),
+        // the executable is not found or can not be spawned
+        Err(_) => false,
     }
+}
 
-   return false
+/**
+ * The errors detected before running the git command of a function, or recognized in the output of a failed git command,
+ * they have their own code on the js side instead of the "GenericFailure" of a failed git command
+ */
+#[derive(Debug)]
+enum CustomerGitError {
+    /** the path doesn't exist or is not inside a git repository, the code is "InvalidArg" */
+    NotARepository(String),
+    /** the placeholder is not in `get_format_key_map`, the code is "InvalidArg" */
+    UnknownPlaceholder(String),
+    /** the checkout or the merge would overwrite the local changes or the untracked files, the code is "Cancelled" */
+    LocalChangesOverwritten(Vec<String>),
+    /** the branch name is rejected by `git check-ref-format --branch`, the code is "InvalidArg" */
+    InvalidBranchName(String),
+    /** the branch to delete is not fully merged, the code is "Cancelled", it can be deleted with force */
+    BranchNotMerged(String),
+    /** the remote refused the credential or git can't prompt for it, the code is "GenericFailure" and the message starts with "authentication failed" */
+    AuthenticationFailed(String),
+    /** the push is rejected because it is not a fast-forward, the code is "Cancelled", it can be pushed with force */
+    PushRejected(String),
+    /** the current branch has no upstream and no remote branch is given, the code is "InvalidArg" */
+    NoUpstream(String),
+    /** the repository is owned by another user and not listed in safe.directory, the code is "InvalidArg" */
+    DubiousOwnership(String),
+    /** the object name doesn't resolve to an object, the code is "InvalidArg" */
+    ObjectNotFound(String),
+    /** the file is larger than the limit of `set_max_output_bytes`, the code is "Cancelled" */
+    FileTooLarge(String),
+    /** HEAD is an unborn branch of a repository without commits, the code is "InvalidArg" */
+    NoCommits(String),
+    /** the label is not a known encoding, the code is "InvalidArg" */
+    UnknownEncoding(String),
+    /** the argument is out of its valid range, the code is "InvalidArg" */
+    InvalidArgument(String),
+}
+
+impl From<CustomerGitError> for JsError {
+    fn from(error: CustomerGitError) -> Self {
+        match error {
+            CustomerGitError::NotARepository(path) => {
+                JsError::from(napiError::new(Status::InvalidArg, format!("not a git repository: {}", path)))
+            }
+            CustomerGitError::UnknownPlaceholder(placeholder) => {
+                let mut valid = get_format_key_map().into_keys().collect::<Vec<String>>();
+                valid.sort();
+                JsError::from(napiError::new(Status::InvalidArg, format!("unknown placeholder `{}`, the valid placeholders are: {}", placeholder, valid.join(", "))))
+            }
+            CustomerGitError::LocalChangesOverwritten(files) => {
+                JsError::from(napiError::new(Status::Cancelled, format!("local changes would be overwritten: {}", files.join(", "))))
+            }
+            CustomerGitError::InvalidBranchName(name) => {
+                JsError::from(napiError::new(Status::InvalidArg, format!("not a valid branch name: {}", name)))
+            }
+            CustomerGitError::BranchNotMerged(name) => {
+                JsError::from(napiError::new(Status::Cancelled, format!("the branch {} is not fully merged", name)))
+            }
+            CustomerGitError::AuthenticationFailed(output) => {
+                JsError::from(napiError::new(Status::GenericFailure, format!("authentication failed: {}", output)))
+            }
+            CustomerGitError::PushRejected(output) => {
+                JsError::from(napiError::new(Status::Cancelled, format!("the push is rejected because it is not a fast-forward: {}", output)))
+            }
+            CustomerGitError::NoUpstream(output) => {
+                JsError::from(napiError::new(Status::InvalidArg, format!("no upstream branch: {}", output)))
+            }
+            CustomerGitError::DubiousOwnership(path) => {
+                JsError::from(napiError::new(Status::InvalidArg, dubious_ownership_message(&path)))
+            }
+            CustomerGitError::ObjectNotFound(object) => {
+                JsError::from(napiError::new(Status::InvalidArg, format!("object not found: {}", object)))
+            }
+            CustomerGitError::FileTooLarge(file_path) => {
+                let max_bytes = MAX_OUTPUT_BYTES.load(Ordering::Relaxed);
+                JsError::from(napiError::new(Status::Cancelled, format!("file exceeds the limit of {} bytes: {}", max_bytes, file_path)))
+            }
+            CustomerGitError::NoCommits(path) => {
+                JsError::from(napiError::new(Status::InvalidArg, format!("no commits yet: {}", path)))
+            }
+            CustomerGitError::UnknownEncoding(label) => {
+                JsError::from(napiError::new(Status::InvalidArg, format!("unknown encoding: {}", label)))
+            }
+            CustomerGitError::InvalidArgument(message) => {
+                JsError::from(napiError::new(Status::InvalidArg, format!("invalid argument: {}", message)))
+            }
+        }
+    }
 }
 
-#[napi]
 /**
- * Check if a path is a git repository
- * @param path path to the repository
+ * Fail fast if the path is not a git repository, so the caller gets a `CustomerGitError::NotARepository`
+ * instead of the error of the git command
  */
-fn is_git_repository(path: String) -> bool {
-    let output = get_command_output("git", &path, &["rev-parse", "--is-inside-work-tree"]);
+fn ensure_git_repository (path: &str) -> Result<(), JsError> {
+    if !std::path::Path::new(path).is_dir() {
+        return Err(JsError::from(CustomerGitError::NotARepository(path.to_string())))
+    }
+    let output = get_command_output("git", path, &["rev-parse", "--git-dir"]);
     match output {
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            stdout.trim() == "true"
+            if output.status.success() {
+                Ok(())
+            } else if is_dubious_ownership(&String::from_utf8_lossy(&output.stderr)) {
+                Err(JsError::from(CustomerGitError::DubiousOwnership(path.to_string())))
+            } else {
+                Err(JsError::from(CustomerGitError::NotARepository(path.to_string())))
+            }
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
         }
-        Err(_) => false,
     }
 }
 
 #[napi]
 /**
- * Get all branches in a git repository
+ * Trust a repository owned by another user by adding it to safe.directory in the global git config,
+ * nothing is changed if it is already listed
  * @param path path to the repository
  */
-fn get_branches(path: String) -> Result<Vec<String>, JsError> {
-    let output = get_command_output("git", &path, &["branch", "--all"]);
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let branches = stdout
-                .lines()
-                .map(|line| {
-                    let tmp = line.trim_start_matches('*').trim().split(" ").into_iter().next().unwrap();
-                    return tmp.to_string();
-                }).collect();
-            Ok(branches)
+fn set_safe_directory (path: String) -> Result<(), JsError> {
+    // git compares safe.directory with the path using forward slashes, also on windows
+    let directory = path.replace('\\', "/");
+    let listed = get_command_output("git", "", &["config", "--global", "--get-all", "safe.directory"]);
+    if let Ok(listed) = listed {
+        if String::from_utf8_lossy(&listed.stdout).lines().any(|line| line.trim() == directory) {
+            return Ok(())
         }
+    }
+    let output = get_checked_command_output("git", "", &["config", "--global", "--add", "safe.directory", &directory]);
+    match output {
+        Ok(_) => Ok(()),
         Err(e) => {
             let err = napiError::from(e);
             Err(JsError::from(err))
-        },
+        }
     }
 }
 
 #[napi]
 /**
- * Check if a git repository is commited
+ * Check if a path is a git repository, a bare repository is a git repository too
  * @param path path to the repository
  */
-fn is_commited (path: String) -> Result<bool, JsError> {
-    let output = get_command_output("git", &path, &["status", "--porcelain"]);
+fn is_git_repository(path: String) -> bool {
+    let output = get_command_output("git", &path, &["rev-parse", "--is-inside-work-tree", "--is-bare-repository"]);
     match output {
         Ok(output) => {
+            // one line for each flag, "false" for the work tree of a bare repository
             let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(stdout.is_empty())
+            output.status.success() && stdout.lines().any(|line| line.trim() == "true")
         }
-       Err(e) => {
-          let err = napiError::from(e);
-          Err(JsError::from(err))
-       }
+        Err(_) => false,
     }
 }
 
 #[napi]
 /**
- * Check if a branch has been pushed
+ * Check if a path is a usable git repository, and tell why it is not, e.g. to show an actionable message.
+ * The path is checked on the filesystem first, then whether git is installed, then `git rev-parse`
  * @param path path to the repository
- * @param branch branch name
  */
-fn is_pushed (path: String, branch: String) -> Result<bool, JsError> {
-    let remote_output = get_branch_in_remote(path.to_string(), branch.to_string());
-    match remote_output {
-        Ok(remote) => {
-            if remote == "" {
-                return Ok(false);
-            }
-            else {
-                let has_pushed = get_command_output("git", &path, &["cherry", &format!("{}/{}", remote.trim(), branch)]);
-                match has_pushed {
-                    Ok(output) => {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        Ok(stdout.trim().is_empty())
-                    }
-                    Err(e) => {
-                        let err = napiError::from(e);
-                        Err(JsError::from(err))
-                    }
-                }
+fn validate_repository(path: String) -> RepositoryValidation {
+    let dir = std::path::Path::new(&path);
+    match std::fs::metadata(dir) {
+        Ok(metadata) if !metadata.is_dir() => return RepositoryValidation::NotAGitRepository,
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return RepositoryValidation::PermissionDenied,
+        Err(_) => return RepositoryValidation::PathDoesNotExist,
+    }
+    if let Err(e) = std::fs::read_dir(dir) {
+        if e.kind() == io::ErrorKind::PermissionDenied {
+            return RepositoryValidation::PermissionDenied
+        }
+    }
+    if !has_git() {
+        return RepositoryValidation::GitNotInstalled
+    }
+    let output = get_command_output("git", &path, &["rev-parse", "--git-dir"]);
+    match output {
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if output.status.success() {
+                RepositoryValidation::Ok
+            } else if is_dubious_ownership(&stderr) {
+                RepositoryValidation::DubiousOwnership
+            } else if stderr.contains("Permission denied") {
+                RepositoryValidation::PermissionDenied
+            } else {
+                RepositoryValidation::NotAGitRepository
             }
         }
-        Err(e) => {
-            Err(e)
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => RepositoryValidation::PermissionDenied,
+        Err(_) => RepositoryValidation::GitNotInstalled,
+    }
+}
+
+#[napi]
+/**
+ * Check if a path is a bare repository, i.e. a repository without a work tree
+ * @param path path to the repository
+ */
+fn is_bare_repository(path: String) -> bool {
+    let output = get_command_output("git", &path, &["rev-parse", "--is-bare-repository"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            output.status.success() && stdout.trim() == "true"
         }
+        Err(_) => false,
     }
 }
 
 #[napi]
 /**
- * Get the status of a repository
+ * Check if a repository is a shallow clone, i.e. its history is truncated
  * @param path path to the repository
  */
-fn get_status (path: String) -> Result<RepoStatus, JsError> {
-    let commit = is_commited(path.to_string())?;
-    if !commit {
-        return Ok(RepoStatus::UnCommit);
-    } else {
-        let current_branch = get_current_branch(path.to_string())?;
-        let pushed = is_pushed(path.to_string(), current_branch)?;
-        if !pushed {
-            return Ok(RepoStatus::UnPush)
-        } else {
-            return Ok(RepoStatus::Ok)
+fn is_shallow(path: String) -> bool {
+    let output = get_command_output("git", &path, &["rev-parse", "--is-shallow-repository"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            output.status.success() && stdout.trim() == "true"
         }
+        Err(_) => false,
     }
 }
 
 #[napi]
 /**
- * Get the current branch name
+ * Get the number and the size of the loose and packed objects, to tell when a repository needs `git gc`
  * @param path path to the repository
  */
-fn get_current_branch(path: String) -> Result<String, JsError> {
-    let output = get_command_output("git", &path, &["rev-parse", "--abbrev-ref", "HEAD"]);
+fn get_repo_stats(path: String) -> Result<RepoStats, JsError> {
+    // `-H` would print the sizes in a human readable form, the plain sizes are in KiB
+    let output = get_checked_command_output("git", &path, &["count-objects", "-v"]);
     match output {
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            Ok(stdout.to_string())
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut stats = RepoStats::default();
+            for line in stdout.lines() {
+                let Some((key, value)) = line.split_once(": ") else {
+                    continue
+                };
+                let value = value.trim().parse::<i32>().unwrap_or(0);
+                match key {
+                    "count" => stats.count = value,
+                    "size" => stats.size_kb = value,
+                    "in-pack" => stats.in_pack = value,
+                    "packs" => stats.packs = value,
+                    "size-pack" => stats.size_pack_kb = value,
+                    "prune-packable" => stats.prune_packable = value,
+                    "garbage" => stats.garbage = value,
+                    "size-garbage" => stats.size_garbage_kb = value,
+                    _ => {}
+                }
+            }
+            Ok(stats)
         }
         Err(e) => {
             let err = napiError::from(e);
@@ -176,73 +481,108 @@ fn get_current_branch(path: String) -> Result<String, JsError> {
 
 #[napi]
 /**
- * Get the remote of a branch
+ * Get all branches in a git repository
  * @param path path to the repository
- * @param branch branch name
  */
-fn get_branch_in_remote (path: String, branch: String) -> Result<String, JsError> {
-    let arg = "branch.".to_string() + &branch + ".remote";
-    let output = get_command_output("git", &path, &["config", "--get", &arg]);
+fn get_branches(path: String) -> Result<Vec<String>, JsError> {
+    let (branches, _) = get_branches_and_current(&path)?;
+    Ok(branches)
+}
+
+#[napi]
+/**
+ * Get all branches in a git repository, telling local branches from remote-tracking branches
+ * @param path path to the repository
+ */
+fn get_branch_refs(path: String) -> Result<Vec<BranchRef>, JsError> {
+    let (branches, _) = get_branches_and_current(&path)?;
+    let output = get_checked_command_output("git", &path, &["remote"]);
+    let remotes = match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).lines().map(|s| s.to_string()).collect::<Vec<String>>(),
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    Ok(branches.into_iter().map(|branch| {
+        match branch.strip_prefix("remotes/") {
+            Some(name) => {
+                // remote names may contain '/', take the longest remote that prefixes the branch
+                let remote_name = remotes.iter()
+                    .filter(|remote| name.starts_with(&format!("{}/", remote)))
+                    .max_by_key(|remote| remote.len())
+                    .cloned()
+                    .unwrap_or_else(|| name.split('/').next().unwrap_or_default().to_string());
+                BranchRef { name: name.to_string(), is_remote: true, remote_name }
+            }
+            None => BranchRef { name: branch, is_remote: false, remote_name: "".to_string() },
+        }
+    }).collect())
+}
+
+/**
+ * Get all branches and the current branch with one `git branch --all`
+ */
+fn get_branches_and_current(path: &str) -> Result<(Vec<String>, String), JsError> {
+    ensure_git_repository(path)?;
+    let output = get_checked_command_output("git", path, &["branch", "--all"]);
     match output {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(stdout.trim().to_string())
+            let branches = stdout
+                .lines()
+                .filter_map(|line| {
+                    let tmp = line.trim_start_matches('*').trim().split(" ").into_iter().next().unwrap();
+                    // the detached HEAD is not a branch
+                    if tmp.starts_with('(') {
+                        return None;
+                    }
+                    return Some(tmp.to_string());
+                }).collect();
+            // the same name as `get_current_branch`, the short hash of a detached HEAD, an unborn branch is not listed either
+            let current_branch = get_current_branch(path.to_string())?;
+            Ok((branches, current_branch))
         }
         Err(e) => {
             let err = napiError::from(e);
             Err(JsError::from(err))
-        }
+        },
     }
 }
 
 #[napi]
 /**
- * Check this repository has remote
+ * Check if a git repository is commited
+ * Requires a work tree, fails on a bare repository
  * @param path path to the repository
-*/
-fn has_remote (path: String) -> Result<bool, JsError> {
-    let output = get_command_output("git", &path, &["remote", "show"]);
+ */
+fn is_commited (path: String) -> Result<bool, JsError> {
+    ensure_git_repository(&path)?;
+    let output = get_checked_command_output("git", &path, &["status", "--porcelain"]);
     match output {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(!stdout.trim().is_empty())
-        }
-        Err(e) => {
-           let err = napiError::from(e);
-           Err(JsError::from(err))
+            Ok(stdout.is_empty())
         }
+       Err(e) => {
+          let err = napiError::from(e);
+          Err(JsError::from(err))
+       }
     }
 }
 
 #[napi]
 /**
- * Get all remotes of a repository
+ * Get the status of the changed files in the working tree and the index
+ * Requires a work tree, fails on a bare repository
  * @param path path to the repository
-*/
-fn get_remote (path: String) -> Result<Vec<Remote>, JsError> {
-    let output = get_command_output("git", &path, &["remote", "-v"]);
+ */
+fn get_working_tree_status (path: String) -> Result<Vec<WorkingTreeFileStatus>, JsError> {
+    let output = get_checked_command_output("git", &path, &["status", "--porcelain=v2", "-z", "--untracked-files=all"]);
     match output {
         Ok(output) => {
-            let mut remotes = HashMap::<String, Remote>::new();
             let stdout = String::from_utf8_lossy(&output.stdout);
-            let lines = stdout.trim().split("\n").collect::<Vec<&str>>();
-            for line in lines {
-                let parts = line.trim().split_whitespace().collect::<Vec<&str>>();
-                let name = parts[0].to_string();
-                let url = parts[1].to_string();
-                let operate = parts[2].trim_start_matches("(").trim_end_matches(")").to_string();
-                let remote = remotes.get_mut(&name);
-                if let Some(remote) = remote {
-                    remote.operate.push(operate);
-                } else {
-                    remotes.insert(name.to_string(), Remote {
-                        name: name.to_string(),
-                        url,
-                        operate: vec![operate],
-                    });
-                }
-            }
-            Ok(remotes.into_values().collect())
+            Ok(parse_status_porcelain_v2(&stdout))
         }
         Err(e) => {
             let err = napiError::from(e);
@@ -251,161 +591,194 @@ fn get_remote (path: String) -> Result<Vec<Remote>, JsError> {
     }
 }
 
-#[napi]
 /**
- * Get all tags of a repository
+ * Parse the output of `git status --porcelain=v2 -z`, the ignored files are skipped
+ */
+fn parse_status_porcelain_v2 (stdout: &str) -> Vec<WorkingTreeFileStatus> {
+    let mut result = Vec::<WorkingTreeFileStatus>::new();
+    let mut entries = stdout.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let (kind, rest) = entry.split_at(1);
+        let rest = rest.trim_start();
+        let (xy, path, message) = match kind {
+            "1" => {
+                let fields = rest.splitn(8, ' ').collect::<Vec<&str>>();
+                (fields[0], fields[7].to_string(), "".to_string())
+            }
+            "2" => {
+                // the original path of the rename or copy is the next entry
+                let fields = rest.splitn(9, ' ').collect::<Vec<&str>>();
+                let orig_path = entries.next().unwrap_or("");
+                (fields[0], fields[8].to_string(), orig_path.to_string() + " => " + fields[8])
+            }
+            "u" => {
+                let fields = rest.splitn(10, ' ').collect::<Vec<&str>>();
+                (fields[0], fields[9].to_string(), "".to_string())
+            }
+            "?" => {
+                result.push(WorkingTreeFileStatus {
+                    path: rest.to_string(),
+                    status: FileStatusType::Added,
+                    message: "".to_string(),
+                    staged: false,
+                    unstaged: false,
+                    untracked: true,
+                });
+                continue;
+            }
+            _ => continue,
+        };
+        let (x, y) = xy.split_at(1);
+        let status = if kind == "u" {
+            FileStatusType::Updated
+        } else if x != "." {
+            parse_file_status(x)
+        } else {
+            parse_file_status(y)
+        };
+        result.push(WorkingTreeFileStatus {
+            path,
+            status,
+            message,
+            staged: kind != "u" && x != ".",
+            unstaged: kind == "u" || y != ".",
+            untracked: false,
+        });
+    }
+    result
+}
+
+#[napi]
+/**
+ * Get the line changes of the staged and the unstaged files
+ * Requires a work tree, fails on a bare repository
  * @param path path to the repository
-*/
-fn get_tags (path: String) -> Result<Vec<String>, JsError> {
-    let output = get_command_output("git", &path, &["tag"]);
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(stdout.trim().split("\n").map(|s| s.to_string()).collect())
+ */
+fn get_working_tree_stat (path: String) -> Result<WorkingTreeStat, JsError> {
+    let staged = get_checked_command_output("git", &path, &["diff", "--cached", "--shortstat"]);
+    let unstaged = get_checked_command_output("git", &path, &["diff", "--shortstat"]);
+    let (staged, unstaged) = match (staged, unstaged) {
+        (Ok(staged), Ok(unstaged)) => (staged, unstaged),
+        (Err(e), _) | (_, Err(e)) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    let parse = |stdout: &[u8]| -> Result<(i32, i32, i32), JsError> {
+        let stdout = String::from_utf8_lossy(stdout);
+        if stdout.trim().is_empty() {
+            return Ok((0, 0, 0))
         }
+        log_shortstat_parse(stdout.trim()).map_err(|e| {
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to parse shortstat `{}`: {}", stdout.trim(), e)));
+            JsError::from(err)
+        })
+    };
+    let (staged_files, staged_insertions, staged_deletions) = parse(&staged.stdout)?;
+    let (unstaged_files, unstaged_insertions, unstaged_deletions) = parse(&unstaged.stdout)?;
+    Ok(WorkingTreeStat {
+        staged_files,
+        staged_insertions,
+        staged_deletions,
+        unstaged_files,
+        unstaged_insertions,
+        unstaged_deletions,
+    })
+}
+
+#[napi]
+/**
+ * Get the line changes of every file with uncommitted changes, including the untracked files, sorted by the path
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ */
+fn get_uncommitted_file_stats (path: String) -> Result<Vec<UncommittedFileStat>, JsError> {
+    let staged = get_diff_numstat(&path, &["--cached", "--no-renames"])?;
+    let unstaged = get_diff_numstat(&path, &["--no-renames"])?;
+    let untracked = match get_checked_command_output("git", &path, &["ls-files", "--others", "--exclude-standard", "-z"]) {
+        Ok(output) => output.stdout,
         Err(e) => {
             let err = napiError::from(e);
-            Err(JsError::from(err))
+            return Err(JsError::from(err))
         }
+    };
+    let mut stats = HashMap::<String, UncommittedFileStat>::new();
+    fn entry<'a>(stats: &'a mut HashMap<String, UncommittedFileStat>, file_path: &str) -> &'a mut UncommittedFileStat {
+        stats.entry(file_path.to_string()).or_insert_with(|| UncommittedFileStat {
+            path: file_path.to_string(),
+            ..Default::default()
+        })
     }
-}
-
-fn get_format_key_map() -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    map.insert("%H".to_string(), "hashL".to_string());
-    map.insert("%h".to_string(), "hashS".to_string());
-    map.insert("%T".to_string(), "treeL".to_string());
-    map.insert("%t".to_string(), "treeS".to_string());
-    map.insert("%P".to_string(), "parentHashL".to_string());
-    map.insert("%p".to_string(), "parentHashS".to_string());
-    map.insert("%an".to_string(), "authorName".to_string());
-    map.insert("%ae".to_string(), "authorEmail".to_string());
-    map.insert("%ad".to_string(), "date".to_string());
-    map.insert("%ar".to_string(), "dateRelative".to_string());
-    map.insert("%at".to_string(), "dateTimeStamp".to_string());
-    map.insert("%cn".to_string(), "committerName".to_string());
-    map.insert("%ce".to_string(), "committerEmail".to_string());
-    map.insert("%cd".to_string(), "committerDate".to_string());
-    map.insert("%cr".to_string(), "committerDateRelative".to_string());
-    map.insert("%ct".to_string(), "committerDateTimeStamp".to_string());
-    map.insert("%cs".to_string(), "committerDateYMD".to_string());
-    map.insert("%d".to_string(), "refs".to_string());
-    map.insert("%D".to_string(), "refsComma".to_string());
-    map.insert("%s".to_string(), "message".to_string());
-    map.insert("%b".to_string(), "body".to_string());
-    map.insert("%B".to_string(), "bodyNoTrailingSlash".to_string());
-    map.insert("%N".to_string(), "notes".to_string());
-
-    return map;
+    for file in staged.iter() {
+        let stat = entry(&mut stats, &file.path);
+        stat.staged_additions = file.additions;
+        stat.staged_deletions = file.deletions;
+    }
+    for file in unstaged.iter() {
+        let stat = entry(&mut stats, &file.path);
+        stat.unstaged_additions = file.additions;
+        stat.unstaged_deletions = file.deletions;
+    }
+    for file_path in String::from_utf8_lossy(&untracked).split('\0').filter(|file_path| !file_path.is_empty()) {
+        entry(&mut stats, file_path).untracked = true;
+    }
+    let mut result = stats.into_values().collect::<Vec<UncommittedFileStat>>();
+    result.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(result)
 }
 
 #[napi]
 /**
- * Get the commit log of a repository
- * You can use placeholders to get the commit log information.
- * The placeholders are:
- * | Placeholders | Description | key |
- * | ---- | ---- | ---- |
- * |%H    | commit hash| hashL |
- * |%h    | abbreviated commit hash| hashS |
- * |%T    | tree hash | treeL |
- * |%t    | abbreviated tree hash| treeS |
- * |%P    | parent hashes | parentHashL |
- * |%p    | abbreviated parent hashes | parentHashS |
- * |%an   | author name | authorName |
- * |%ae   | author email | authorEmail |
- * |%ad   | author date  | date |
- * |%ar   | author date, relative with now | dateRelative |
- * |%at   | author date, unix timestamp | dateTimeStamp |
- * |%ai   | author date, ISO 8601 format | dateIso |
- * |%as   | author date, short format (YYYY-MM-DD) | dateYMD |
- * |%ah   | author date, human-readable format | dateHuman |
- * |%cn   | committer name | committerName |
- * |%ce   | committer email | committerEmail |
- * |%cd   | committer date | committerDate |
- * |%cr   | committer date, relative with now | committerDateRelative |
- * |%ct   | committer date, unix timestamp | committerDateTimeStamp |
- * |%cs   | committer date, short format (YYYY-MM-DD) | committerDateYMD |
- * |%ch   | committer date, human-readable format | committerDateHuman |
- * |%d    | ref names | refs |
- * |%D    | ref names, separated by commas | refsComma |
- * |%s    | subject | message |
- * |%b    | body | body |
- * |%B    | body, without trailing slash | bodyNoTrailingSlash |
- * |%N    | commit notes | notes |
- * 
+ * Check if a branch has been pushed
  * @param path path to the repository
  * @param branch branch name
- * @param placeholders placeholders to get the commit log information
- * @param start_commit start commit hash, it can be ""
- * @param end_commit end commit hash, it can be ""
- * @return commit log
  */
-fn get_commit_log_format(path: String, placeholders: Vec<String>, start_commit: String, end_commit: String) -> Result<Vec<HashMap<String, String>>, JsError> {
-    let mut format = String::from("--pretty=format:");
-    let commit_range = build_commit_range(&start_commit, &end_commit);
-    // println!("{}", commit_range);
-    for key in placeholders.iter(){
-        format = format + &key + PARAM_INTERVAL;
-    }
-    format = format.trim_end_matches(PARAM_INTERVAL).to_string() + COMMIT_INETRVAL;
-    let key_map = get_format_key_map();
-    let output = get_command_output("git", &path, &["log", &format, &commit_range]);
-    let mut res = Vec::new();
-    match output{
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.trim().trim_end_matches(&COMMIT_INETRVAL).split(&COMMIT_INETRVAL) {
-                if line.is_empty(){
-                    continue;
-                }
-                let datas = line.split(PARAM_INTERVAL).collect::<Vec<_>>();
-                let mut map = HashMap::<String, String>::new();
-                for i in 0..placeholders.len(){
-                    let key = placeholders[i].to_string();
-                    let value = datas[i].trim().to_string();
-                    let key = key_map.get(&key).unwrap();
-                    map.insert(key.to_string(), value.to_string());
+fn is_pushed (path: String, branch: String) -> Result<bool, JsError> {
+    let remote_output = get_branch_in_remote(path.to_string(), branch.to_string());
+    match remote_output {
+        Ok(remote) => {
+            if remote == "" {
+                return Ok(false);
+            }
+            else {
+                let has_pushed = get_command_output("git", &path, &["cherry", &format!("{}/{}", remote.trim(), branch)]);
+                match has_pushed {
+                    Ok(output) => {
+                        let stdout = String::from_utf8_lossy(&output.stdout);
+                        Ok(stdout.trim().is_empty())
+                    }
+                    Err(e) => {
+                        let err = napiError::from(e);
+                        Err(JsError::from(err))
+                    }
                 }
-                res.push(map);
             }
-            Ok(res)
         }
         Err(e) => {
-            let err = napiError::from(e);
-            Err(JsError::from(err))
+            Err(e)
         }
     }
 }
 
 #[napi]
 /**
- * Get the authors of a repository
+ * Check if a commit has been pushed, i.e. it is reachable from a remote-tracking branch
  * @param path path to the repository
- * @param branch branch to get the authors from
-*/
-fn get_branch_authors (path: String, branch: String) ->Result<Vec<Author>, JsError> {
-    let output = get_command_output("git", &path, &["shortlog", &branch, "-sne"]);
+ * @param commit_hash commit hash
+ * @param remote only check the branches of this remote, check all remotes if it is not given
+ */
+fn is_commit_pushed (path: String, commit_hash: String, remote: Option<String>) -> Result<bool, JsError> {
+    let output = get_checked_command_output("git", &path, &["branch", "-r", "--contains", &commit_hash, "--format=%(refname:short)"]);
     match output {
         Ok(output) => {
-            let mut authors = Vec::<Author>::new();
-            let lines = String::from_utf8_lossy(&output.stdout);
-            for line in lines.trim().split("\n") {
-                if line.is_empty(){
-                    continue;
-                }
-                let keys = line.split_ascii_whitespace().collect::<Vec<_>>();
-                let author_name = keys[1].to_string();
-                let mut author_email = keys[2].to_string();
-                if author_email.contains("<") && author_email.contains(">"){
-                    author_email = author_email.split("<").collect::<Vec<_>>()[1].split(">").collect::<Vec<_>>()[0].to_string();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let pushed = stdout.lines().map(|line| line.trim()).any(|branch| {
+                match &remote {
+                    Some(remote) => branch.starts_with(&format!("{}/", remote)),
+                    None => !branch.is_empty(),
                 }
-                authors.push(Author {
-                    name: author_name,
-                    email: author_email,
-                });
-            }
-            Ok(authors)
+            });
+            Ok(pushed)
         }
         Err(e) => {
             let err = napiError::from(e);
@@ -416,26 +789,62 @@ fn get_branch_authors (path: String, branch: String) ->Result<Vec<Author>, JsErr
 
 #[napi]
 /**
- * Get the authors of a repository
+ * Get the status of a repository
+ * Requires a work tree, fails on a bare repository
  * @param path path to the repository
-*/
-fn get_all_authors (path: String) -> Result<Vec<Author>, JsError> {
-    let output = get_command_output("git", &path, &["log", &format!("--pretty=format:%an{}%ae", PARAM_INTERVAL)]);
+ */
+fn get_status (path: String) -> Result<RepoStatus, JsError> {
+    let commit = is_commited(path.to_string())?;
+    if !commit {
+        return Ok(RepoStatus::UnCommit);
+    } else {
+        // a detached HEAD has no branch to compare with its upstream, it is pushed if a remote branch contains it
+        let pushed = match get_head_branch(&path)? {
+            Some(current_branch) => is_pushed(path.to_string(), current_branch)?,
+            None => is_commit_pushed(path.to_string(), "HEAD".to_string(), None)?,
+        };
+        if !pushed {
+            return Ok(RepoStatus::UnPush)
+        } else {
+            return Ok(RepoStatus::Ok)
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the current branch name, the name of an unborn branch too.
+ * When HEAD is detached it is the short hash of the checked out commit, the same name as `get_current_branch_info`
+ * @param path path to the repository
+ */
+fn get_current_branch(path: String) -> Result<String, JsError> {
+    match get_head_branch(&path)? {
+        Some(branch) => Ok(branch),
+        None => {
+            let commit_hash = resolve_ref(path, "HEAD".to_string())?;
+            Ok(commit_hash.chars().take(7).collect::<String>())
+        }
+    }
+}
+
+/**
+ * Get the branch HEAD points to, None when HEAD is detached
+ */
+fn get_head_branch(path: &str) -> Result<Option<String>, JsError> {
+    let output = get_command_output("git", path, &["symbolic-ref", "-q", "--short", "HEAD"]);
     match output {
         Ok(output) => {
-            let mut authors = HashSet::<Author>::new();
-            let output = String::from_utf8_lossy(&output.stdout);
-            println!("{}", output);
-            for line in output.trim().split("\n") {
-                let keys = line.split(PARAM_INTERVAL).collect::<Vec<_>>();
-                let author_name = keys[0].to_string();
-                let author_email = keys[1].to_string();
-                authors.insert(Author {
-                    name: author_name,
-                    email: author_email,
-                });
+            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if output.status.success() && !stdout.is_empty() {
+                return Ok(Some(stdout))
             }
-            Ok(authors.into_iter().collect::<Vec<_>>())
+            // exit code 1 means HEAD is not a symbolic ref, e.g. a tag is checked out
+            if output.status.code() == Some(1) {
+                return Ok(None)
+            }
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git symbolic-ref` exited with {}: {}", output.status, stderr.trim())));
+            Err(JsError::from(err))
         }
         Err(e) => {
             let err = napiError::from(e);
@@ -446,49 +855,83 @@ fn get_all_authors (path: String) -> Result<Vec<Author>, JsError> {
 
 #[napi]
 /**
- * Get the branch creation info of a repository
+ * Get the commit checked out at HEAD, it works with a detached HEAD too,
+ * it fails with "no commits yet" when the current branch has no commits
  * @param path path to the repository
- * @param branch branch to get the branch creation info from
-*/
-fn get_branch_create_info (path: String, branch: String) -> Result<BranchCreatedInfo, JsError> {
-    let format = "--pretty=format:".to_string() + "%an" + PARAM_INTERVAL + "%ae" + PARAM_INTERVAL + "%at" + PARAM_INTERVAL + "%H";
-    let output = get_command_output("git", &path, &["log", &branch, "--reverse", "--max-parents=0", &format]);
+ */
+fn get_head_commit(path: String) -> Result<CommitSummary, JsError> {
+    ensure_git_repository(&path)?;
+    let output = get_command_output("git", &path, &["log", "-1", &commit_summary_format(), "HEAD", "--"]);
     match output {
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            println!("{}", stdout);
-            let keys = stdout.trim().split(PARAM_INTERVAL).collect::<Vec<_>>();
-            let author_name = keys[0].to_string();
-            let author_email = keys[1].to_string();
-            let hash = keys[3].to_string();
-            let time = keys[2].to_string();
-            let author = Author {
-                name: author_name,
-                email: author_email,
-            };
-            Ok(BranchCreatedInfo {
-                name: branch,
-                time: time,
-                author: author,
-                hash
-            })
+            if !output.status.success() {
+                // an unborn branch, HEAD points to a branch without commits
+                let verified = get_command_output("git", &path, &["rev-parse", "--verify", "-q", "HEAD"]);
+                if verified.map(|verified| !verified.status.success()).unwrap_or(false) {
+                    return Err(JsError::from(CustomerGitError::NoCommits(path)))
+                }
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git log` exited with {}: {}", output.status, stderr.trim())));
+                return Err(JsError::from(err))
+            }
+            let stdout = decode_log_output(&output.stdout);
+            match parse_commit_summaries(&stdout).into_iter().next() {
+                Some(commit) => Ok(commit),
+                None => Err(JsError::from(CustomerGitError::NoCommits(path))),
+            }
         }
         Err(e) => {
             let err = napiError::from(e);
             Err(JsError::from(err))
         }
     }
-
-
 }
 
 #[napi]
-fn get_branch_last_commit(path: String, branch: String) -> Result<String, JsError> {
-    let output = get_command_output("git", &path, &["rev-parse", &branch]);
+/**
+ * Count the commits a branch is ahead and behind its upstream
+ * @param path path to the repository
+ * @param branch branch name
+ * @param upstream the ref to compare with, the upstream configured for the branch by default
+ * @return null if the upstream is not given and the branch has no upstream configured
+ */
+fn get_ahead_behind (path: String, branch: String, upstream: Option<String>) -> Result<Option<AheadBehind>, JsError> {
+    let upstream = match upstream {
+        Some(upstream) => upstream,
+        None => {
+            let rev = format!("{}@{{upstream}}", branch);
+            let output = get_command_output("git", &path, &["rev-parse", "--abbrev-ref", "--symbolic-full-name", &rev]);
+            match output {
+                Ok(output) => {
+                    if !output.status.success() {
+                        return Ok(None)
+                    }
+                    String::from_utf8_lossy(&output.stdout).trim().to_string()
+                }
+                Err(e) => {
+                    let err = napiError::from(e);
+                    return Err(JsError::from(err))
+                }
+            }
+        }
+    };
+    let range = format!("{}...{}", upstream, branch);
+    let output = get_checked_command_output("git", &path, &["rev-list", "--left-right", "--count", &range, "--"]);
     match output {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(stdout.trim().to_string())
+            let counts = stdout.split_whitespace().map(|count| count.parse::<i32>()).collect::<Vec<_>>();
+            match counts.as_slice() {
+                [Ok(behind), Ok(ahead)] => Ok(Some(AheadBehind {
+                    upstream,
+                    ahead: *ahead,
+                    behind: *behind,
+                })),
+                _ => {
+                    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("failed to parse the output of rev-list: {}", stdout.trim())));
+                    Err(JsError::from(err))
+                }
+            }
         }
         Err(e) => {
             let err = napiError::from(e);
@@ -499,672 +942,735 @@ fn get_branch_last_commit(path: String, branch: String) -> Result<String, JsErro
 
 #[napi]
 /**
- * Get the branch creation info of a repository
+ * Get the remote of a branch
  * @param path path to the repository
- * @param branchs branchs to get the branch creation info from
-*/
-fn get_branchs_create_info (path: String, branchs: Vec<String>) -> Result<Vec<BranchCreatedInfo>, JsError> {
-    let mut res = Vec::<BranchCreatedInfo>::new();
-    for branch in branchs.iter() {
-        let info = get_branch_create_info(path.to_string(), branch.to_string());
-        match info {
-            Ok(info) => {
-                res.push(info);
-            }
-            Err(e) => {
-                return Err(e);
-            }
+ * @param branch branch name
+ */
+fn get_branch_in_remote (path: String, branch: String) -> Result<String, JsError> {
+    let arg = "branch.".to_string() + &branch + ".remote";
+    let output = get_command_output("git", &path, &["config", "--get", &arg]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(stdout.trim().to_string())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
         }
     }
-    Ok(res)   
 }
 
 #[napi]
 /**
- * Get the repository info of a repository
+ * Check this repository has remote
  * @param path path to the repository
 */
-fn get_repository_info_full (path: String) -> Result<RepositoryFull, JsError> {
-    let branches = get_branches(path.to_string())?;
-    let authors = get_all_authors(path.to_string())?;
-    let current_branch = get_current_branch(path.to_string())?;
-    let mut branches_arr = Vec::<String>::new();
-    for branch in branches.iter() {
-        // get branch info
-        let branch_name = branch.to_string();
-        branches_arr.push(branch_name);
-    };
-    
-    // get repository name
-    let repo_name = util::get_basename(&path);
-    let name;
-    match repo_name {
-        Some(_name) => {
-            name = _name;
+fn has_remote (path: String) -> Result<bool, JsError> {
+    let output = get_command_output("git", &path, &["remote", "show"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(!stdout.trim().is_empty())
         }
-        None => {
-            name= String::from("");
+        Err(e) => {
+           let err = napiError::from(e);
+           Err(JsError::from(err))
         }
-    };
-    // get remote 
-    let remote = get_remote(path.to_string())?;
-    Ok(RepositoryFull {
-        current_branch: current_branch.clone(),
-        branches: branches_arr,
-        authors: authors.iter().map(| item | (*item).clone()).collect::<Vec<Author>>(),
-        name: name,
-        remote: remote,
-        path: path,
-    })
+    }
 }
 
 #[napi]
 /**
- * Get the repository info in a simple way
+ * Get all remotes of a repository
  * @param path path to the repository
- */
-fn get_repository_info_simple (path: String) -> Result<RepositorySimple, JsError> {
-    let branches = get_branches(path.to_string())?;
-    let current_branch = get_current_branch(path.to_string())?;
-    let authors = get_all_authors(path.to_string())?;
-    let mut branches_arr = Vec::<String>::new();
-    let remote = get_remote(path.to_string())?;
-    for branch in branches.iter() {
-        branches_arr.push(branch.to_string());
+*/
+fn get_remote (path: String) -> Result<Vec<Remote>, JsError> {
+    let output = get_checked_command_output("git", &path, &["remote", "-v"]);
+    match output {
+        Ok(output) => {
+            let mut remotes = HashMap::<String, Remote>::new();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let lines = stdout.trim().split("\n").filter(|line| !line.is_empty()).collect::<Vec<&str>>();
+            for line in lines {
+                let parts = line.trim().split_whitespace().collect::<Vec<&str>>();
+                let name = parts[0].to_string();
+                let url = parts[1].to_string();
+                let operate = parts[2].trim_start_matches("(").trim_end_matches(")").to_string();
+                let remote = remotes.get_mut(&name);
+                if let Some(remote) = remote {
+                    remote.operate.push(operate);
+                } else {
+                    remotes.insert(name.to_string(), Remote {
+                        name: name.to_string(),
+                        url,
+                        operate: vec![operate],
+                    });
+                }
+            }
+            Ok(remotes.into_values().collect())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
     }
-    Ok(RepositorySimple {
-        name: util::get_basename(&path).unwrap(),
-        branches: branches_arr,
-        current_branch: current_branch,
-        path: path,
-        authors,
-        remote
-    })
 }
 
-fn log_shortstat_parse (status: &str) -> Result<(i32, i32, i32), String> {
-    let re = Regex::new(r"(?<changes>\d+) files? changed(?:, (?<insertions>\d+) insertions?\(\+\))?(?:, (?<deletions>\d+) deletions?\(-\))?").unwrap();
-    let Some(captures) = re.captures(status) else {
-        return Err("No match found!".to_string())
+#[napi]
+/**
+ * Get all remotes of a repository with the fetch url and the push url separated,
+ * the push url falls back to the fetch url when remote.<name>.pushurl is unset
+ * @param path path to the repository
+*/
+fn get_remotes_detailed (path: String) -> Result<Vec<RemoteDetail>, JsError> {
+    let names_output = get_checked_command_output("git", &path, &["remote"]);
+    let names = match names_output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
     };
-    let mut insertions = 0;
-    let mut changes = 0;
-    let mut deletions = 0;
-    if let Some(changes_str) = captures.name("changes") {
-        changes = changes_str.as_str().parse::<i32>().unwrap();
-    }
-    if let Some(insertions_str) = captures.name("insertions") {
-        insertions = insertions_str.as_str().parse::<i32>().unwrap();
+    // exit code 1 means no url has been configured
+    let output = get_command_output("git", &path, &["config", "--get-regexp", r"^remote\..*\.(url|pushurl)$"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut urls = HashMap::<String, String>::new();
+            for line in stdout.lines() {
+                if let Some((key, url)) = line.split_once(" ") {
+                    urls.insert(key.to_string(), url.to_string());
+                }
+            }
+            let remotes = names.lines().filter(|name| !name.is_empty()).map(|name| {
+                let fetch_url = urls.get(&format!("remote.{}.url", name)).cloned().unwrap_or_default();
+                let push_url = urls.get(&format!("remote.{}.pushurl", name)).cloned().unwrap_or(fetch_url.to_string());
+                RemoteDetail {
+                    name: name.to_string(),
+                    fetch_url,
+                    push_url,
+                }
+            }).collect();
+            Ok(remotes)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
     }
-    if let Some(deletions_str) = captures.name("deletions") {
-        deletions = deletions_str.as_str().parse::<i32>().unwrap();
+}
+
+#[napi]
+/**
+ * Get all tags of a repository
+ * @param path path to the repository
+*/
+fn get_tags (path: String) -> Result<Vec<String>, JsError> {
+    let output = get_checked_command_output("git", &path, &["tag"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(stdout.trim().split("\n").map(|s| s.to_string()).collect())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
     }
-    Ok((changes, insertions, deletions))
 }
 
 #[napi]
 /**
- * Get the statistic of daily contribute in a branch
+ * Get all local and remote-tracking branches with their tip commit in a single call,
+ * the names are short names such as `main` or `origin/main`
+ * @param path path to the repository
  */
-fn get_contribute_stat (path: String, branch: String) -> Result<BranchStatDailyContribute, JsError> {
-    let format = "--pretty=format:".to_string()+ COMMIT_INETRVAL + "%an" + PARAM_INTERVAL + "%ae" + PARAM_INTERVAL + "%cs";
-    let commit_range = build_commit_range("", &branch);
-    let output = get_command_output("git", &path, &["log", "--shortstat", &format, "--reverse", &commit_range]);
+fn get_branches_detail (path: String) -> Result<Vec<BranchDetail>, JsError> {
+    // every field is terminated by NUL, the refs are separated by "\n"
+    let format = "--format=%(refname)%00%(symref)%00%(objectname)%00%(authorname)%00%(committerdate:unix)%00%(upstream:short)%00%(contents:subject)%00";
+    let output = get_checked_command_output("git", &path, &["for-each-ref", "refs/heads", "refs/remotes", format]);
     match output {
         Ok(output) => {
-            let mut authors_stat = HashMap::<String, AuthorStatDailyContribute>::new();
-            let mut total_stat = StatDailyContribute {
-                commit_count: Vec::<i32>::new(),
-                date_list: Vec::<String>::new(),
-                insertion: Vec::<i32>::new(),
-                deletions: Vec::<i32>::new(),
-                change_files: Vec::<i32>::new(),
-            };
             let stdout = String::from_utf8_lossy(&output.stdout);
-            let commits = stdout.trim().split(COMMIT_INETRVAL).filter(| line | line.to_string() != "").collect::<Vec<_>>();
-            // parse commits
-            for i in 0..commits.len() {
-                let commit = commits[i];
-                let lines = commit.trim_end_matches("\n").split("\n").collect::<Vec<_>>();
-                if lines.len() != 2 {continue;}
-                let auth_info = lines[0].split(PARAM_INTERVAL).collect::<Vec<_>>();
-                // parse shortstat
-                let Ok ((changes, insertions, deletions)) = log_shortstat_parse(lines[1]) else {
-                    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("")));
-                    return Err(JsError::from(err))
+            let mut fields = stdout.split('\0').collect::<Vec<&str>>();
+            // the part after the last NUL is not a field
+            fields.pop();
+            let branches = fields.chunks_exact(7).filter_map(|parts| {
+                let refname = parts[0].trim_start_matches('\n');
+                if !parts[1].is_empty() {
+                    // skip symbolic refs like `refs/remotes/origin/HEAD`
+                    return None
+                }
+                let (name, is_remote) = match refname.strip_prefix("refs/remotes/") {
+                    Some(name) => (name, true),
+                    None => (refname.trim_start_matches("refs/heads/"), false),
                 };
-                // println!("======================\n{}\n{}\n============================", auth_info.join("|"), change_info.join("|"));
-                let name = auth_info[0].to_string();
-                let email = auth_info[1].to_string();
-                let date = auth_info[2].to_string();
-                // if this author has contained
-                if authors_stat.contains_key(&name) {
-                    let author = authors_stat.get_mut(&name).unwrap();
-                    let len = author.stat.date_list.len();
-                    // if one day has multiple commits
-                    if author.stat.date_list[len - 1] == date {
-                        author.stat.commit_count[len - 1] += 1;
-                        author.stat.change_files[len - 1] = changes;
-                        author.stat.insertion[len - 1] = insertions;
-                        author.stat.deletions[len - 1] = deletions;
-                    } else {
-                        // new day and first commit
-                        author.stat.date_list.push(date.to_string());
-                        author.stat.commit_count.push(1);
-                        author.stat.insertion.push(insertions);
-                        author.stat.deletions.push(deletions);
-                        author.stat.change_files.push(changes);
+                Some(BranchDetail {
+                    name: name.to_string(),
+                    is_remote,
+                    tip_hash: parts[2].to_string(),
+                    tip_author: parts[3].to_string(),
+                    tip_date: parts[4].to_string(),
+                    upstream: parts[5].to_string(),
+                    tip_subject: parts[6].trim().to_string(),
+                })
+            }).collect();
+            Ok(branches)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get all tags of a repository with the tagged commit and the annotation, the newest tag first
+ * @param path path to the repository
+ */
+fn get_tags_detail (path: String) -> Result<Vec<TagInfo>, JsError> {
+    // every field is terminated by NUL, the annotation spans lines
+    let format = "--format=%(refname:short)%00%(objecttype)%00%(objectname)%00%(*objectname)%00%(taggername)%00%(taggeremail)%00%(creatordate:unix)%00%(contents)%00";
+    let output = get_checked_command_output("git", &path, &["for-each-ref", "refs/tags", "--sort=-creatordate", format]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut fields = stdout.split('\0').collect::<Vec<&str>>();
+            // the part after the last NUL is not a field
+            fields.pop();
+            let tags = fields.chunks_exact(8).map(|parts| {
+                let name = parts[0].trim_start_matches('\n');
+                let is_annotated = parts[1] == "tag";
+                if !is_annotated {
+                    return TagInfo {
+                        name: name.to_string(),
+                        target_hash: parts[2].to_string(),
+                        tagger_name: "".to_string(),
+                        tagger_email: "".to_string(),
+                        date: parts[6].to_string(),
+                        message: "".to_string(),
+                        is_annotated,
                     }
-                } else {
-                    // new author
-                    let mut author = AuthorStatDailyContribute {
-                        author: Author {
-                            name: name.to_string(),
-                            email: email,
-                        },
-                        stat: StatDailyContribute {
-                            commit_count: Vec::<i32>::new(),
-                            date_list: Vec::<String>::new(),
-                            insertion: Vec::<i32>::new(),
-                            deletions: Vec::<i32>::new(),
-                            change_files: Vec::<i32>::new(),
-                        }
-                    };
-                    author.stat.date_list.push(date.to_string());
-                    author.stat.commit_count.push(1);
-                    author.stat.insertion.push(insertions);
-                    author.stat.deletions.push(deletions);
-                    author.stat.change_files.push(changes);
-                    authors_stat.insert(name, author);
                 }
-                // total stat
-                let len = total_stat.date_list.len();
-                if len > 0 && total_stat.date_list[len - 1] == date {
-                    total_stat.commit_count[len - 1] += 1;
-                    total_stat.change_files[len - 1] = changes;
-                    total_stat.insertion[len - 1] = insertions;
-                    total_stat.deletions[len - 1] = deletions;
-                } else {
-                    // new day and first commit
-                    total_stat.date_list.push(date.to_string());
-                    total_stat.commit_count.push(1);
-                    total_stat.insertion.push(insertions);
-                    total_stat.deletions.push(deletions);
-                    total_stat.change_files.push(changes);
-                    // println!("{:#?}", total_stat)
+                TagInfo {
+                    name: name.to_string(),
+                    target_hash: parts[3].to_string(),
+                    tagger_name: parts[4].to_string(),
+                    tagger_email: parts[5].trim_start_matches('<').trim_end_matches('>').to_string(),
+                    date: parts[6].to_string(),
+                    message: parts[7].trim().to_string(),
+                    is_annotated,
                 }
-            }
-            Ok(BranchStatDailyContribute {
-                branch: branch,
-                total_stat: total_stat,
-                authors_stat: authors_stat.into_values().collect::<Vec<AuthorStatDailyContribute>>(),
-            })
+            }).collect();
+            Ok(tags)
         }
         Err(e) => {
             let err = napiError::from(e);
             Err(JsError::from(err))
         }
     }
-
 }
 
 /**
- * Insert the file info list
+ * Map the placeholders to the keys of the result, fail on the first unknown placeholder
  */
-fn insert_file_to_tree(file_list: &mut Vec<RepoFileInfo>, object_mode: &str, object_type: &str, object_name: &str, object_size: &str, object_path: &str) {
-    let file_tree = object_path.split("/").collect::<Vec<&str>>();
-    let mut tmp_file_list = file_list;
-    // println!("{}", file_tree.len());
-    for i in 0..file_tree.len() {
-        let index = tmp_file_list.iter().position(|t| t.name == file_tree[i] && t.is_dir);
-        match index {
-            Some( index ) => {
-                // println!("{}", index);
-                tmp_file_list = &mut tmp_file_list[index].children;
-            }
-            None => {
-                let is_last = i == file_tree.len() - 1;
-                let is_dir = object_mode.starts_with("040000") || !is_last;
-                // println!("{} {} {} {} {} {}",file_tree.len() - 1, i, file_tree[i], is_dir, is_last, object_path);
-                let dir = if i != 0 { file_tree[0..i].join("/") } else {"./".to_string()};
-                let file = RepoFileInfo {
-                    name: file_tree[i].to_string(),
-                    dir,
-                    object_mode: if is_last {object_mode.to_string()} else {"".to_string()},
-                    object_type: if is_last {object_type.to_string()} else {"".to_string()},
-                    object_name: if is_last {object_name.to_string()} else {"".to_string()},
-                    object_size: if is_last {object_size.to_string()} else {"".to_string()},
-                    is_dir,
-                    children: Vec::<RepoFileInfo>::new(),
-                };
-                tmp_file_list.push(file);
-                // println!("{:?}", tmp_file_list);
-                if is_dir {
-                    tmp_file_list = tmp_file_list.last_mut().unwrap().children.as_mut();
-                }
-            }
+fn get_placeholder_keys(placeholders: &Vec<String>) -> Result<Vec<String>, JsError> {
+    let key_map = get_format_key_map();
+    placeholders.iter().map(|placeholder| {
+        match key_map.get(placeholder) {
+            Some(key) => Ok(key.to_string()),
+            None => Err(JsError::from(CustomerGitError::UnknownPlaceholder(placeholder.to_string()))),
         }
-        
-    }
+    }).collect()
+}
+
+fn get_format_key_map() -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    map.insert("%H".to_string(), "hashL".to_string());
+    map.insert("%h".to_string(), "hashS".to_string());
+    map.insert("%T".to_string(), "treeL".to_string());
+    map.insert("%t".to_string(), "treeS".to_string());
+    map.insert("%P".to_string(), "parentHashL".to_string());
+    map.insert("%p".to_string(), "parentHashS".to_string());
+    map.insert("%an".to_string(), "authorName".to_string());
+    map.insert("%ae".to_string(), "authorEmail".to_string());
+    map.insert("%ad".to_string(), "date".to_string());
+    map.insert("%ar".to_string(), "dateRelative".to_string());
+    map.insert("%at".to_string(), "dateTimeStamp".to_string());
+    map.insert("%ai".to_string(), "dateIso".to_string());
+    map.insert("%as".to_string(), "dateYMD".to_string());
+    map.insert("%ah".to_string(), "dateHuman".to_string());
+    map.insert("%cn".to_string(), "committerName".to_string());
+    map.insert("%ce".to_string(), "committerEmail".to_string());
+    map.insert("%cd".to_string(), "committerDate".to_string());
+    map.insert("%cr".to_string(), "committerDateRelative".to_string());
+    map.insert("%ct".to_string(), "committerDateTimeStamp".to_string());
+    map.insert("%cs".to_string(), "committerDateYMD".to_string());
+    map.insert("%ch".to_string(), "committerDateHuman".to_string());
+    map.insert("%d".to_string(), "refs".to_string());
+    map.insert("%D".to_string(), "refsComma".to_string());
+    map.insert("%s".to_string(), "message".to_string());
+    map.insert("%b".to_string(), "body".to_string());
+    map.insert("%B".to_string(), "bodyNoTrailingSlash".to_string());
+    map.insert("%N".to_string(), "notes".to_string());
+    map.insert("%G?".to_string(), "signatureStatus".to_string());
+    map.insert("%GS".to_string(), "signer".to_string());
+    map.insert("%GK".to_string(), "signatureKey".to_string());
+
+    return map;
 }
 
+#[napi]
 /**
- * From the file info list, generate the file tree
+ * Get the commit log of a repository
+ * You can use placeholders to get the commit log information.
+ * The placeholders are:
+ * | Placeholders | Description | key |
+ * | ---- | ---- | ---- |
+ * |%H    | commit hash| hashL |
+ * |%h    | abbreviated commit hash| hashS |
+ * |%T    | tree hash | treeL |
+ * |%t    | abbreviated tree hash| treeS |
+ * |%P    | parent hashes | parentHashL |
+ * |%p    | abbreviated parent hashes | parentHashS |
+ * |%an   | author name | authorName |
+ * |%ae   | author email | authorEmail |
+ * |%ad   | author date  | date |
+ * |%ar   | author date, relative with now | dateRelative |
+ * |%at   | author date, unix timestamp | dateTimeStamp |
+ * |%ai   | author date, ISO 8601 format | dateIso |
+ * |%as   | author date, short format (YYYY-MM-DD) | dateYMD |
+ * |%ah   | author date, human-readable format | dateHuman |
+ * |%cn   | committer name | committerName |
+ * |%ce   | committer email | committerEmail |
+ * |%cd   | committer date | committerDate |
+ * |%cr   | committer date, relative with now | committerDateRelative |
+ * |%ct   | committer date, unix timestamp | committerDateTimeStamp |
+ * |%cs   | committer date, short format (YYYY-MM-DD) | committerDateYMD |
+ * |%ch   | committer date, human-readable format | committerDateHuman |
+ * |%d    | ref names | refs |
+ * |%D    | ref names, separated by commas | refsComma |
+ * |%s    | subject | message |
+ * |%b    | body | body |
+ * |%B    | body, without trailing slash | bodyNoTrailingSlash |
+ * |%N    | commit notes | notes |
+ * |%G?   | signature status, G good, B bad, U unknown validity, N none, etc. | signatureStatus |
+ * |%GS   | name of the signer | signer |
+ * |%GK   | key used to sign | signatureKey |
+ * 
+ * @param path path to the repository
+ * @param branch branch name
+ * @param placeholders placeholders to get the commit log information
+ * @param start_commit start commit hash, it can be ""
+ * @param end_commit end commit hash, it can be ""
+ * @param filter optional filter of the commits, see `CommitLogFilter`
+ * @return commit log
  */
-fn file_info_list_to_tree (file_info_list: Vec<&str>) -> Vec<RepoFileInfo> {
-    let mut file_list: Vec<RepoFileInfo> = Vec::new();
-    for line in file_info_list {
-        let file_info = line.split(PARAM_INTERVAL).collect::<Vec<&str>>();
-        if file_info.len() == 5 {
-            let object_mode = file_info[0];
-            let object_type = file_info[1];
-            let object_size = file_info[2].trim();
-            let object_name = file_info[3];
-            let object_path = file_info[4];
-            // objectMode Code:
-            // 040000: Directory
-            // 100644: Regular non-executable file
-            // 100664: Regular non-executable group-writeable file
-            // 100755: Regular executable file
-            // 120000: Symbolic link
-            // 160000: Gitlink
-            let file_tree = object_path.split("/").collect::<Vec<&str>>();
-            if file_tree.len() > 1 {
-                insert_file_to_tree(&mut file_list, object_mode, object_type, object_name, object_size, object_path)
-            } else { 
-                let is_dir = object_mode.starts_with("040000");
-                let file = RepoFileInfo {
-                    name: object_path.to_string(),
-                    dir: "./".to_string(),
-                    object_mode: object_mode.to_string(),
-                    object_type: object_type.to_string(),
-                    object_name: object_name.to_string(),
-                    object_size: object_size.to_string(),
-                    is_dir,
-                    children: Vec::<RepoFileInfo>::new(),
-                };
-                file_list.push(file);
-            }
-       }
-    }
-    return file_list
+fn get_commit_log_format(path: String, placeholders: Vec<String>, start_commit: String, end_commit: String, filter: Option<CommitLogFilter>) -> Result<Vec<HashMap<String, String>>, JsError> {
+    let commit_range = build_commit_range(&start_commit, &end_commit);
+    // println!("{}", commit_range);
+    let mut args = commit_log_filter_args(&filter);
+    args.push(commit_range);
+    args.extend(pathspec_args(&filter.and_then(|filter| filter.pathspec)));
+    query_commit_log_format(&path, "log", &placeholders, args)
 }
 
 #[napi]
 /**
- * Get the file list of a repository
+ * Get a page of the commit log of a repository, the placeholders are the same as `get_commit_log_format`
+ * @param path path to the repository
+ * @param placeholders placeholders to get the commit log information
+ * @param start_commit start commit hash, it can be ""
+ * @param end_commit end commit hash, it can be ""
+ * @param skip skip this number of commits before starting to return
+ * @param max_count max number of commits in the page
+ * @param filter optional filter of the commits, see `CommitLogFilter`
+ * @return the commits in the page and whether there are more commits after it
  */
-fn get_repo_file_list (path: String, branch_or_hash: String) -> Result<Vec<RepoFileInfo>, JsError> {
-    let format = format!("--format=%(objectmode){}%(objecttype){}%(objectsize:padded){}%(objectname){}%(path)", PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL);
-    let output = get_command_output("git", &path, &["ls-tree", "-r", &branch_or_hash, &format]);
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let lines = stdout.trim().split("\n").collect::<Vec<&str>>();
-            let file_list = file_info_list_to_tree(lines);
-            Ok(file_list)
-
-        }
-        Err(e) => {
-            let err = napiError::from(e);
-            Err(JsError::from(err))
-        }
+fn get_commit_log_page(path: String, placeholders: Vec<String>, start_commit: String, end_commit: String, skip: i32, max_count: i32, filter: Option<CommitLogFilter>) -> Result<CommitLogPage, JsError> {
+    if skip < 0 {
+        return Err(JsError::from(CustomerGitError::InvalidArgument(format!("skip must not be negative: {}", skip))))
     }
+    if max_count <= 0 {
+        return Err(JsError::from(CustomerGitError::InvalidArgument(format!("max_count must be positive: {}", max_count))))
+    }
+    let commit_range = build_commit_range(&start_commit, &end_commit);
+    let mut args = commit_log_filter_args(&filter);
+    // query one more commit to know whether there is a next page, git can't count past i32::MAX anyway
+    args.push(format!("--skip={}", skip));
+    args.push(format!("--max-count={}", max_count.checked_add(1).unwrap_or(max_count)));
+    args.push(commit_range);
+    args.extend(pathspec_args(&filter.and_then(|filter| filter.pathspec)));
+    let mut commits = query_commit_log_format(&path, "log", &placeholders, args)?;
+    let has_more = commits.len() > max_count as usize;
+    commits.truncate(max_count as usize);
+    Ok(CommitLogPage {
+        commits,
+        has_more,
+    })
 }
 
 #[napi]
 /**
- * Get the file status of a commit
+ * Stream the commit log to the callback instead of returning the whole history, the placeholders are the same as `get_commit_log_format`.
+ * The git output is read in a background thread, the callback is called with `(null, commits)` for every batch,
+ * with `(null, null)` when the log is finished, or with `(error, null)` if git fails
+ * @param path path to the repository
+ * @param placeholders placeholders to get the commit log information
+ * @param start_commit start commit hash, it can be ""
+ * @param end_commit end commit hash, it can be ""
+ * @param filter optional filter of the commits, see `CommitLogFilter`
+ * @param batch_size max number of commits passed to the callback in one call
+ * @param callback the callback receiving the batches
  */
-fn get_commit_file_status (path: String, hash: String) -> Result<FileStatusReport, JsError> {
-    let format = format!("--format=%H{}%s{}%an{}%ae{}%at", PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL);
-    let output = get_command_output("git", &path, &["show", &hash, "--name-status", "--oneline", &format]);
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let lines = stdout.trim().split("\n").filter(|t| *t != "").collect::<Vec<&str>>();
-            let commit_info = lines[0].trim().split(PARAM_INTERVAL).collect::<Vec<&str>>();
-            let commit_hash = commit_info[0];
-            let commit_message = commit_info[1];
-            let commit_author = commit_info[2];
-            let commit_author_email = commit_info[3];
-            let commit_time = commit_info[4];
-            let file_status = lines[1..].iter().map(|line| {
-                let params = line.split("\t").collect::<Vec<&str>>();
-                let file_path = params[1].to_string();
-                let mut message = "".to_string();
-                let status_flag = params[0][0..1].to_string();
-                let status = match status_flag.as_str() {
-                    "A" => FileStatusType::Added,
-                    "D" => FileStatusType::Deleted,
-                    "M" => FileStatusType::Modified,
-                    "R" => {
-                        if params.len() == 3 {
-                            message = params[1].to_string() + " => " + params[2];
-                        }
-                        FileStatusType::Renamed
-                    },
-                    "C" => FileStatusType::Copied,
-                    "U" => FileStatusType::Updated,
-                    _ => FileStatusType::Unknown,
-                };
-                FileStatus {
-                    path: file_path,
-                    status,
-                    message,
-                }
-            }).collect::<Vec<FileStatus>>();
-            let file_status_report = FileStatusReport {
-                title: commit_message.to_string(),
-                hash: commit_hash.to_string(),
-                status: file_status,
-                time: commit_time.to_string(),
-                author: Author {
-                    name: commit_author.to_string(),
-                    email: commit_author_email.to_string(),
-                }
-            };
-            Ok(file_status_report)
-        }
-        Err(e) => {
-            let err = napiError::from(e);
+fn get_commit_log_format_stream(path: String, placeholders: Vec<String>, start_commit: String, end_commit: String, filter: Option<CommitLogFilter>, batch_size: u32, callback: JsFunction) -> Result<(), JsError> {
+    ensure_git_repository(&path)?;
+    let keys = get_placeholder_keys(&placeholders)?;
+    let format = commit_log_pretty_format(&placeholders);
+    let mut args = commit_log_filter_args(&filter);
+    args.push(build_commit_range(&start_commit, &end_commit));
+    args.extend(pathspec_args(&filter.and_then(|filter| filter.pathspec)));
+    // the small queue blocks the reader thread until js consumes the batches, so the memory stays bounded
+    let tsfn: ThreadsafeFunction<Option<Vec<HashMap<String, String>>>> = callback
+        .create_threadsafe_function(4, |ctx: ThreadSafeCallContext<Option<Vec<HashMap<String, String>>>>| Ok(vec![ctx.value]))
+        .map_err(JsError::from)?;
+    std::thread::spawn(move || {
+        let output = stream_commit_log_format(&path, &keys, &format, args, batch_size.max(1) as usize, |batch| {
+            tsfn.call(Ok(Some(batch)), ThreadsafeFunctionCallMode::Blocking);
+        });
+        match output {
+            Ok(()) => tsfn.call(Ok(None), ThreadsafeFunctionCallMode::Blocking),
+            Err(e) => tsfn.call(Err(napiError::from(e)), ThreadsafeFunctionCallMode::Blocking),
+        };
+    });
+    Ok(())
+}
+
+#[napi]
+/**
+ * Get the information of a single commit, the placeholders are the same as `get_commit_log_format`
+ * @param path path to the repository
+ * @param hash commit hash
+ * @param placeholders placeholders to get the commit information
+ */
+fn get_commit_format(path: String, hash: String, placeholders: Vec<String>) -> Result<HashMap<String, String>, JsError> {
+    let commits = query_commit_log_format(&path, "show", &placeholders, vec!["-s".to_string(), hash.to_string()])?;
+    match commits.into_iter().next() {
+        Some(commit) => Ok(commit),
+        None => {
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Commit not found: {}", hash)));
             Err(JsError::from(err))
         }
     }
 }
 
-fn parse_file_status (status_flag: &str) -> FileStatusType {
-    match status_flag {
-        "A" => FileStatusType::Added,
-        "D" => FileStatusType::Deleted,
-        "M" => FileStatusType::Modified,
-        "R" => FileStatusType::Renamed,
-        "C" => FileStatusType::Copied,
-        "U" => FileStatusType::Updated,
-        _ =>FileStatusType::Unknown,
+#[napi]
+/**
+ * Search the commits whose message matches the queries, the placeholders are the same as `get_commit_log_format`
+ * @param path path to the repository
+ * @param branch branch to search in, search HEAD if it is ""
+ * @param queries the patterns passed to `git log --grep`
+ * @param placeholders placeholders to get the commit log information
+ * @param ignore_case match the patterns case-insensitively
+ * @param all_match only return the commits matching all of the patterns, instead of any of them
+ */
+fn search_commits(path: String, branch: String, queries: Vec<String>, placeholders: Vec<String>, ignore_case: bool, all_match: bool) -> Result<Vec<HashMap<String, String>>, JsError> {
+    // every pattern is passed as a separate argument to git, never through a shell
+    let mut args = queries.iter().map(|query| format!("--grep={}", query)).collect::<Vec<String>>();
+    if ignore_case {
+        args.push("-i".to_string());
     }
+    if all_match {
+        args.push("--all-match".to_string());
+    }
+    args.push(build_commit_range("", &branch));
+    query_commit_log_format(&path, "log", &placeholders, args)
 }
 
+#[napi]
 /**
- * Get the file list of a repository
+ * Get the commits touching a file, the placeholders are the same as `get_commit_log_format`.
+ * Besides the placeholders, every commit has `filePath`, the path of the file in that commit,
+ * and `oldFilePath` if the file is renamed in that commit
+ * @param path path to the repository
+ * @param file_path the path of the file
+ * @param follow continue the history before the renames of the file, i.e. `git log --follow`
+ * @param placeholders placeholders to get the commit log information
  */
-fn get_file_between_commit_status(path: String, commit_hash1: String, file_path: String) -> Result<(FileStatusType, String), String> {
-    let output: Result<Output, io::Error> = get_command_output("git", &path, &["show", &commit_hash1, "--name-status",  "--format=", "--", &file_path]);
+fn get_file_history(path: String, file_path: String, follow: bool, placeholders: Vec<String>) -> Result<Vec<HashMap<String, String>>, JsError> {
+    ensure_git_repository(&path)?;
+    // with -z every commit is "<COMMIT_INETRVAL><field>\0<field>\0...\0" followed by "\n<status>\0<path>\0..."
+    // %x1e is COMMIT_INETRVAL, a format without any placeholder would be taken as the name of a pretty format
+    let format = String::from("--format=%x1e") + &placeholders.join("%x00");
+    let keys = get_placeholder_keys(&placeholders)?;
+    let mut args = vec!["log", "-z", &format, "--name-status"];
+    if follow {
+        args.push("--follow");
+    }
+    args.extend(["--", &file_path]);
+    let output = get_checked_command_output("git", &path, &args);
     match output {
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let lines = stdout.trim().split_ascii_whitespace().collect::<Vec<&str>>();
-            if lines.len() > 0 {
-                let status_flag = lines[0][0..1].to_string();
-                let mut message = "".to_string();
-                let file_status = parse_file_status(&status_flag);
-                if file_status == FileStatusType::Renamed {
-                    if lines.len() > 1 {
-                        message = lines[1].to_string() + " => " + lines[2];
+            let stdout = decode_log_output(&output.stdout);
+            let mut fields = stdout.split('\0').peekable();
+            let mut res = Vec::new();
+            while let Some(field) = fields.next() {
+                let Some(first) = field.strip_prefix(COMMIT_INETRVAL) else {
+                    continue;
+                };
+                let mut datas = vec![first];
+                for _ in 1..keys.len() {
+                    datas.push(fields.next().unwrap_or(""));
+                }
+                let mut map = parse_commit_log_record(&datas, &keys);
+                let mut current_path = file_path.to_string();
+                // the number of paths tells the entries from the next commit, a path may start with COMMIT_INETRVAL too
+                while let Some(count) = fields.peek().and_then(|status| name_status_path_count(status.trim_start_matches('\n'))) {
+                    fields.next();
+                    let paths = (0..count).map(|_| fields.next().unwrap_or("")).collect::<Vec<&str>>();
+                    if let [old_path, new_path] = paths.as_slice() {
+                        map.insert("oldFilePath".to_string(), old_path.to_string());
+                        current_path = new_path.to_string();
+                    } else {
+                        current_path = paths[0].to_string();
                     }
                 }
-                Ok((file_status, message))
-            }
-            else {
-                Err("No status found".to_string())
+                map.insert("filePath".to_string(), current_path);
+                res.push(map);
             }
+            Ok(res)
         }
         Err(e) => {
-            Err(e.source().unwrap().to_string())
+            let err = napiError::from(e);
+            Err(JsError::from(err))
         }
     }
 }
 
 #[napi]
 /**
- * Get the file change statistic between two commits
- * @param path The path of the repository
- * @param commit_hash1 The commit hash of the first commit
- * @param commit_hash2 The commit hash of the second commit
- * @param file_path The path of the file
+ * Get the topology of the commits to draw a commit graph, the children are listed before their parents
+ * @param path path to the repository
+ * @param branch branch to start from, HEAD if it is ""
+ * @param limit the max number of commits, all of the commits if it is not given
  */
-fn get_file_modify_stat_between_commit(path: String, commit_hash1: String, commit_hash2: String, file_path: String) -> Result<FileLineChangeStat, JsError> {
-    let commit_range = format!("{}...{}", commit_hash1, commit_hash2);
-    let output = get_command_output("git", &path, &["diff", &commit_range , "--shortstat", "--", &file_path]);
+fn get_commit_graph(path: String, branch: String, limit: Option<i32>) -> Result<Vec<CommitGraphNode>, JsError> {
+    let format = "--format=%H%x00%P%x00%D%x00".to_string();
+    let mut args = vec!["log".to_string(), format, "--topo-order".to_string()];
+    if let Some(limit) = limit {
+        args.push(format!("--max-count={}", limit));
+    }
+    args.push(build_commit_range("", &branch));
+    args.push("--".to_string());
+    let output = get_checked_command_output("git", &path, &args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>());
     match output {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
-            match log_shortstat_parse(&stdout) {
-                Ok((_, addition, deletion)) => {
-                    Ok(FileLineChangeStat {
-                        addition,
-                        deletion
-                    })
-                }
-                Err(_) => {
-                    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file change status:\nRepository path: {}\ncommit hash1: {}\ncommit hash2: {}", path, commit_hash1, commit_hash2)));
-                    Err(JsError::from(err))
+            let mut fields = stdout.split('\0').collect::<Vec<&str>>();
+            // the part after the last NUL is not a field
+            fields.pop();
+            let nodes = fields.chunks_exact(3).map(|fields| {
+                CommitGraphNode {
+                    // the commits are separated by "\n"
+                    hash: fields[0].trim_start_matches('\n').to_string(),
+                    parents: fields[1].split_whitespace().map(|parent| parent.to_string()).collect(),
+                    refs: fields[2].split(", ").filter(|r| !r.is_empty()).map(|r| r.to_string()).collect(),
+                    decorations: parse_decorations(fields[2].to_string()),
                 }
-            }
+            }).collect();
+            Ok(nodes)
         }
         Err(e) => {
             let err = napiError::from(e);
-            return Err(JsError::from(err))
+            Err(JsError::from(err))
         }
     }
 }
 
 #[napi]
 /**
- * Get the files change status between two commits
- * @param path The path of the repository
- * @param commit_hash1 The commit hash of the first commit
- * @param commit_hash2 The commit hash of the second commit
+ * Split the ref names of a commit, as printed by the `%d` or `%D` placeholder, into branches and tags
+ * @param decorations the ref names, like ` (HEAD -> main, tag: v1.0, origin/main)`
  */
-fn get_files_status_between_commit (path: String, commit_hash1: String, commit_hash2: String) -> Result<Vec<FileStatus>, JsError> {
-    let output = get_command_output("git", &path, &["diff", "--name-status", &commit_hash1, &commit_hash2]);
-    let mut file_status = Vec::<FileStatus>::new();
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let lines = stdout.trim().split("\n").collect::<Vec<&str>>();
-            for line in lines.iter() {
-                let params = line.split_ascii_whitespace().collect::<Vec<&str>>();
-                let flag = params[0][0..1].to_string();
-                let file_staus = parse_file_status(&flag);
-                let mut message = "".to_string();
-                if file_staus == FileStatusType::Renamed {
-                    if params.len() > 2 {
-                        message = params[1].to_string() + " => " + params[2];
-                    }
-                }
-                file_status.push(FileStatus {
-                    path: params[1].to_string(),
-                    status: file_staus,
-                    message,
-                });
-            }
-            Ok(file_status)
+fn parse_decorations(decorations: String) -> CommitDecorations {
+    let mut res = CommitDecorations::default();
+    let decorations = decorations.trim();
+    let decorations = decorations.strip_prefix('(').and_then(|d| d.strip_suffix(')')).unwrap_or(decorations);
+    for name in decorations.split(", ").map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        if let Some(tag) = name.strip_prefix("tag: ") {
+            res.tags.push(tag.to_string());
+        } else if let Some(branch) = name.strip_prefix("HEAD -> ") {
+            res.head = true;
+            res.branches.push(branch.to_string());
+        } else if name == "HEAD" {
+            // detached HEAD
+            res.head = true;
+        } else {
+            res.branches.push(name.to_string());
         }
-        Err(e) => {
-            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get commit status:\nRepository path: {}\ncommit hash1: {}\ncommit hash2: {}", path, commit_hash1, commit_hash2)));
-            return Err(JsError::from(err))
+    }
+    res
+}
+
+/**
+ * Convert the commit log filter to git log args
+ */
+fn commit_log_filter_args (filter: &Option<CommitLogFilter>) -> Vec<String> {
+    let mut args = Vec::<String>::new();
+    let Some(filter) = filter else {
+        return args
+    };
+    if let Some(since) = filter.since.as_ref().filter(|since| !since.is_empty()) {
+        args.push(format!("--since={}", since));
+    }
+    if let Some(until) = filter.until.as_ref().filter(|until| !until.is_empty()) {
+        args.push(format!("--until={}", until));
+    }
+    if let Some(author) = filter.author.as_ref().filter(|author| !author.is_empty()) {
+        args.push(format!("--author={}", author));
+    }
+    if filter.no_merges.unwrap_or(false) {
+        args.push("--no-merges".to_string());
+    }
+    if filter.first_parent.unwrap_or(false) {
+        args.push("--first-parent".to_string());
+    }
+    args
+}
+
+/**
+ * The pathspecs after "--", nothing if there is no pathspec so the whole repository is included
+ */
+fn pathspec_args (pathspec: &Option<Vec<String>>) -> Vec<String> {
+    match pathspec {
+        Some(pathspec) if !pathspec.is_empty() => std::iter::once("--".to_string()).chain(pathspec.iter().cloned()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/**
+ * Convert the rename options to git diff args
+ */
+fn rename_option_args (options: &Option<RenameOptions>) -> Result<Vec<String>, JsError> {
+    let mut args = Vec::<String>::new();
+    let Some(options) = options else {
+        return Ok(args)
+    };
+    for (name, similarity) in [("find_renames", options.find_renames), ("find_copies", options.find_copies)] {
+        if similarity.is_some_and(|similarity| !(0..=100).contains(&similarity)) {
+            return Err(JsError::from(CustomerGitError::InvalidArgument(format!("{} must be a percent within 0..=100", name))))
         }
     }
+    if let Some(find_renames) = options.find_renames {
+        args.push(format!("-M{}%", find_renames));
+    }
+    let find_copies_harder = options.find_copies_harder.unwrap_or(false);
+    match options.find_copies {
+        Some(find_copies) => args.push(format!("-C{}%", find_copies)),
+        // --find-copies-harder has no effect without -C
+        None if find_copies_harder => args.push("-C".to_string()),
+        None => {}
+    }
+    if find_copies_harder {
+        args.push("--find-copies-harder".to_string());
+    }
+    Ok(args)
 }
 
-#[napi]
 /**
- * Get the file diff context of a file
- * @param repo: the path of the repository
- * @param commit_hash1: the hash of the first commit
- * @param commit_hash2: the hash of the second commit
- * @param file_path: the path of the file
+ * Build the `--pretty` argument of the placeholders, every field ends with NUL, the only byte a commit message can't contain.
+ * A commit is the same number of fields as the placeholders, the commits are separated by "\n"
  */
-fn diff_file_context (repo: String, commit_hash1: String, commit_hash2: String, file_path: String) -> Result<FileDiffContext, JsError> {
-    // 先用从 git show hash1 hash2 --name-status --format="" file_path 来获取文件在两个提交见的状态，是需改还是删除还是重命名等等
-    // 如果是文件中的修改，则调用 git diff --shortstat hash1 hash2 -- file_path 来记录文件中修改的数量，二进制文件不需要做，只需要提示为二进制文件即可
-    //      如果是重命名、删除的话，就不用做，提供说明
-    // 如果是文件中修改的话，使用 git cat-file -p hash:path 来获取文件内容
-    let commit_status = get_file_between_commit_status(repo.to_string(), commit_hash2.to_string(), file_path.to_string());
-    match commit_status {
-        Ok(commit_status) => {
-            let (status, message) = commit_status;
-            let mut context1 = "".to_string();
-            let mut context2 = "".to_string();
-            match status {
-                // 添加
-                FileStatusType::Added =>{
-                    let output = get_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash1, file_path)]);
-                    match output {
-                        Ok(output) => {
-                            let stdout = String::from_utf8_lossy(&output.stdout);
-                            context1 = stdout.to_string();
-                            Ok(FileDiffContext {
-                                commit_hash1: commit_hash1.to_string(),
-                                commit_hash2: commit_hash2.to_string(),
-                                file_path: file_path.to_string(),
-                                change_stat: FileLineChangeStat {
-                                    addition: stdout.trim().lines().count() as i32,
-                                    deletion: 0,
-                                },
-                                context1,
-                                context2,
-                                file_status: status,
-                            })
-                        }
-                        Err(e) => {
-                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}", file_path, commit_hash1)));
-                            Err(JsError::from(err))
-                        }
-                    }
-                } 
-                // 删除
-                FileStatusType::Deleted => {
-                    let output = get_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash1, file_path)]);
-                    match output {
-                        Ok(output) => {
-                            let stdout = String::from_utf8_lossy(&output.stdout);
-                            context1 = stdout.to_string();
-                            Ok(FileDiffContext {
-                                commit_hash1: commit_hash1.to_string(),
-                                commit_hash2: commit_hash2.to_string(),
-                                file_path: file_path.to_string(),
-                                change_stat: FileLineChangeStat {
-                                    addition: 0,
-                                    deletion: stdout.trim().lines().count() as i32,
-                                },
-                                context1,
-                                context2,
-                                file_status: status,
-                            })
-                        }
-                        Err(e) => {
-                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}", file_path, commit_hash2)));
-                            Err(JsError::from(err))
-                        }
-                    }
+fn commit_log_pretty_format(placeholders: &Vec<String>) -> String {
+    let mut format = String::from("--pretty=format:");
+    for key in placeholders.iter(){
+        format = format + key + "%x00";
+    }
+    format
+}
 
-                }
-                // 修改
-                FileStatusType::Modified => {
-                    // 获取修改的数量
-                    let output = get_command_output("git", &repo, &["diff", "--shortstat", &commit_hash1, &commit_hash2, "--", &file_path]);
-                    let mut addition = 0;
-                    let mut deletion = 0;
-                    match output {
-                        Ok(output) => {
-                            let stdout = String::from_utf8_lossy(&output.stdout);
-                            let lines = stdout.trim().split(", ").collect::<Vec<&str>>();
-                            let change_info1 = lines[1].split(" ").collect::<Vec<&str>>();
-                            if lines.len() > 2 {
-                                if change_info1[1].starts_with("insertion") {
-                                    addition = change_info1[0].parse::<i32>().unwrap();
-                                    let change_info2 = lines[2].split(" ").collect::<Vec<&str>>();
-                                    deletion = change_info2[0].parse::<i32>().unwrap();
-                                }
-                            } else {
-                                if change_info1[1].starts_with("insertion") {
-                                    addition = change_info1[0].parse::<i32>().unwrap();
-                                } else {
-                                    deletion = change_info1[0].parse::<i32>().unwrap();
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file diff:\nfile path: {}\ncommit hash1: {}\ncommit hash2: {}", file_path, commit_hash1, commit_hash2)));
-                            return Err(JsError::from(err))
-                        }
-                    }
-                    // 获取文件内容
-                    let mut context1: String;
-                    let context1_output = get_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash1, file_path)]);
-                    match context1_output {
-                        Ok(context1_output) => {
-                            let stdout = String::from_utf8_lossy(&context1_output.stdout);
-                            context1 = stdout.to_string();
-                        }
-                        Err(e) => {
-                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}", file_path, commit_hash2)));
-                            return Err(JsError::from(err))
-                        }
-                    };
-                    let context2_output = get_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash2, file_path)]);
-                    match context2_output {
-                        Ok(context2_output) => {
-                            let stdout = String::from_utf8_lossy(&context2_output.stdout);
-                            context2 = stdout.to_string();
-                        }
-                        Err(e) => {
-                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("")));
-                            return Err(JsError::from(err))
-                        }
-                    };
-                    Ok(FileDiffContext {
-                        commit_hash1: commit_hash1.to_string(),
-                        commit_hash2: commit_hash2.to_string(),
-                        file_path: file_path.to_string(),
-                        change_stat: FileLineChangeStat {
-                            addition,
-                            deletion,
-                        },
-                        context1,
-                        context2,
-                        file_status: status,
-                    })
-                }
-                _ => {
-                    Ok(FileDiffContext {
-                        commit_hash1: commit_hash1.to_string(),
-                        commit_hash2: commit_hash2.to_string(),
-                        file_path: file_path.to_string(),
-                        change_stat: FileLineChangeStat {
-                            addition: 0,
-                            deletion: 0,
-                        },
-                        context1: String::from(""),
-                        context2: String::from(""),
-                        file_status: status,
-                    })
-                }
+/**
+ * Parse the fields of one commit to a map from the keys of the placeholders to the values
+ */
+fn parse_commit_log_record(fields: &[&str], keys: &[String]) -> HashMap<String, String> {
+    let mut map = HashMap::<String, String>::new();
+    for (i, key) in keys.iter().enumerate() {
+        // the first field starts with the "\n" separating it from the previous commit
+        let value = fields.get(i).map(|value| value.trim()).unwrap_or("");
+        map.insert(key.to_string(), value.to_string());
+    }
+    map
+}
+
+/**
+ * Run git log with the placeholders format and the extra args, the output is read commit by commit
+ * and every `batch_size` commits are passed to `on_batch`, so the whole history is never kept in memory
+ */
+fn stream_commit_log_format(path: &str, keys: &[String], format: &str, args: Vec<String>, batch_size: usize, mut on_batch: impl FnMut(Vec<HashMap<String, String>>)) -> io::Result<()> {
+    let mut log_args = vec!["log", format];
+    log_args.extend(args.iter().map(|arg| arg.as_str()));
+    let mut child = build_command("git", path, &log_args).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stderr_reader = spawn_stderr_reader(&mut child, u64::MAX);
+    let reader = BufReader::new(child.stdout.take().unwrap());
+    let mut batch = Vec::new();
+    let mut fields = Vec::<String>::new();
+    for field in reader.split(b'\0') {
+        let field = match field {
+            Ok(field) => field,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e)
             }
+        };
+        fields.push(decode_log_output(&field));
+        if keys.is_empty() || fields.len() < keys.len() {
+            continue;
         }
-        Err(e) => {
-            println!("{}", e);
-            let err = napiError::from(io::Error::new(io::ErrorKind::Other, e));
-            Err(JsError::from(err))
+        let record = std::mem::take(&mut fields);
+        batch.push(parse_commit_log_record(&record.iter().map(|field| field.as_str()).collect::<Vec<&str>>(), keys));
+        if batch.len() >= batch_size {
+            on_batch(std::mem::take(&mut batch));
         }
     }
+    if !batch.is_empty() {
+        on_batch(batch);
+    }
+    let status = child.wait()?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(io::Error::new(io::ErrorKind::Other, format!("`git log` exited with {}: {}", status, stderr.trim())))
+    }
+    Ok(())
 }
 
-#[napi]
 /**
- * get file content in a commit
- * @param repo repo path
- * @param commit_hash commit hash
- * @param file_path file path
+ * Run git log (or git show) with the placeholders format and the extra args, parse every commit to a map
  */
-fn get_file_content (repo: String, commit_hash: String, file_path: String) -> Result<String, JsError> {
-    let output = get_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash, file_path)]);
-    match output {
+fn query_commit_log_format(path: &str, command: &str, placeholders: &Vec<String>, args: Vec<String>) -> Result<Vec<HashMap<String, String>>, JsError> {
+    ensure_git_repository(path)?;
+    let format = commit_log_pretty_format(placeholders);
+    let keys = get_placeholder_keys(placeholders)?;
+    let mut log_args = vec![command, &format];
+    log_args.extend(args.iter().map(|arg| arg.as_str()));
+    let output = get_checked_command_output("git", path, &log_args);
+    let mut res = Vec::new();
+    match output{
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(stdout.to_string())
+            let stdout = decode_log_output(&output.stdout);
+            let mut fields = stdout.split('\0').collect::<Vec<&str>>();
+            // the part after the last NUL is not a field
+            fields.pop();
+            if keys.is_empty() {
+                return Ok(res)
+            }
+            for record in fields.chunks_exact(keys.len()) {
+                res.push(parse_commit_log_record(record, &keys));
+            }
+            Ok(res)
         }
         Err(e) => {
             let err = napiError::from(e);
@@ -1174,12 +1680,33 @@ fn get_file_content (repo: String, commit_hash: String, file_path: String) -> Re
 }
 
 #[napi]
-fn get_file_by_hash(repo: String, file_hash: String) -> Result<String, JsError> {
-    let output = get_command_output("git", &repo, &["cat-file", "-p", &file_hash]);
+/**
+ * Get the authors of a repository
+ * @param path path to the repository
+ * @param branch branch to get the authors from
+*/
+fn get_branch_authors (path: String, branch: String) ->Result<Vec<Author>, JsError> {
+    let output = get_checked_command_output("git", &path, &["shortlog", &branch, "-sne"]);
     match output {
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(stdout.to_string())
+            let mut authors = Vec::<Author>::new();
+            let lines = String::from_utf8_lossy(&output.stdout);
+            for line in lines.trim().split("\n") {
+                if line.is_empty(){
+                    continue;
+                }
+                let keys = line.split_ascii_whitespace().collect::<Vec<_>>();
+                let author_name = keys[1].to_string();
+                let mut author_email = keys[2].to_string();
+                if author_email.contains("<") && author_email.contains(">"){
+                    author_email = author_email.split("<").collect::<Vec<_>>()[1].split(">").collect::<Vec<_>>()[0].to_string();
+                }
+                authors.push(Author {
+                    name: author_name,
+                    email: author_email,
+                });
+            }
+            Ok(authors)
         }
         Err(e) => {
             let err = napiError::from(e);
@@ -1188,502 +1715,6254 @@ fn get_file_by_hash(repo: String, file_hash: String) -> Result<String, JsError>
     }
 }
 
-fn is_binary(content: &str) -> bool {
-    // 判断前8000个字节中是否包含0
-    for c in content.bytes().take(8000) {
-        if c == 0 {
-            return true;
-        }
-    }
-    return false;
-}
-
 #[napi]
 /**
- * get difference file diff statistic between two commits
- * @param repo repo path
- * @param commit_hash1 commit hash1
- * @param commit_hash2 commit hash2
- * @param file_path1 file path in commit1
- * @param file_path2 file path in commit2
- * @returns FileDiffContext
- */
-fn get_diff_file_stat_between_commit(repo: String, commit_hash1: String, commit_hash2: String, file_path1: String, file_path2: String)-> Result<FileLineChangeStat, JsError> {
-    let commit_range = format!("{}...{}", commit_hash1, commit_hash2);
-    let output = get_command_output("git", &repo, &["diff", &commit_range, "--shortstat",  "--", &file_path1, &file_path2]);
+ * Get the authors of a repository
+ * @param path path to the repository
+*/
+fn get_all_authors (path: String) -> Result<Vec<Author>, JsError> {
+    let output = get_checked_command_output("git", &path, &["log", &format!("--pretty=format:%an{}%ae", PARAM_INTERVAL)]);
     match output {
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            match log_shortstat_parse(&stdout) {
-                Ok((_, insertation, deletion)) => {
-                    Ok(FileLineChangeStat {
-                        addition: insertation,
-                        deletion: deletion,
-                    })
-                }
-                Err(e) => {
-                    let err = napiError::from(io::Error::new(io::ErrorKind::Other, "File to parse git diff shortstat"));
-                    Err(JsError::from(err))
-                }
+            let mut authors = HashSet::<Author>::new();
+            let output = String::from_utf8_lossy(&output.stdout);
+            println!("{}", output);
+            for line in output.trim().split("\n") {
+                let keys = line.split(PARAM_INTERVAL).collect::<Vec<_>>();
+                let author_name = keys[0].to_string();
+                let author_email = keys[1].to_string();
+                authors.insert(Author {
+                    name: author_name,
+                    email: author_email,
+                });
             }
+            Ok(authors.into_iter().collect::<Vec<_>>())
         }
         Err(e) => {
             let err = napiError::from(e);
             Err(JsError::from(err))
         }
     }
+}
 
+#[napi]
+/**
+ * Resolve a short hash, a branch, a tag or an expression like `hash^` to the full hash of the commit
+ * @param path path to the repository
+ * @param ref_name the ref to resolve
+ */
+fn resolve_ref (path: String, ref_name: String) -> Result<String, JsError> {
+    let rev = format!("{}^{{commit}}", ref_name);
+    let output = get_command_output("git", &path, &["rev-parse", "--verify", "--end-of-options", &rev]);
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                return Ok(stdout.trim().to_string())
+            }
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = if stderr.contains("ambiguous") {
+                format!("ref `{}` is ambiguous: {}", ref_name, stderr.trim())
+            } else {
+                format!("ref `{}` does not resolve to a commit: {}", ref_name, stderr.trim())
+            };
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, message));
+            Err(JsError::from(err))
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
 
+#[napi]
+/**
+ * Get the info of the checked out branch, when HEAD is detached (e.g. a tag is checked out),
+ * the info is about the checked out commit instead of a branch named "HEAD"
+ * @param path path to the repository
+ */
+fn get_current_branch_info (path: String) -> Result<Branch, JsError> {
+    let commit_hash = get_branch_last_commit(path.to_string(), "HEAD".to_string())?;
+    let (name, is_detached) = match get_head_branch(&path)? {
+        Some(branch) => (branch, false),
+        None => (commit_hash.chars().take(7).collect::<String>(), true),
+    };
+    let rev = if is_detached { commit_hash.to_string() } else { name.to_string() };
+    let created = get_branch_create_info(path.to_string(), rev.to_string())?;
+    let authors = get_branch_authors(path.to_string(), rev)?;
+    Ok(Branch {
+        name,
+        created,
+        authors,
+        commit_hash,
+        is_detached,
+    })
 }
 
 #[napi]
 /**
- * get difference files diff between two commits
- * @param repo repo path
- * @param commit_hash1 commit hash1
- * @param commit_hash2 commit hash2
- * @returns FileDiffContext
+ * Get the branch creation info of a repository, i.e. the commit the branch was created from:
+ * 1. the "branch: Created from" entry of the reflog of a local branch, the time is when the branch was created;
+ * 2. otherwise the newest commit of the branch on the first-parent history of the default branch,
+ *    a merged branch keeps its fork point, but a branch merged by a fast-forward is on that history itself;
+ * 3. the root commit for the default branch, its upstream, or when there is no default branch.
+ * Git doesn't record the fork point anywhere else, the reflog is local and expires, e.g. a cloned branch has none
+ * @param path path to the repository
+ * @param branch branch to get the branch creation info from
+*/
+fn get_branch_create_info (path: String, branch: String) -> Result<BranchCreatedInfo, JsError> {
+    let format = "--pretty=format:".to_string() + "%an" + PARAM_INTERVAL + "%ae" + PARAM_INTERVAL + "%at" + PARAM_INTERVAL + "%H";
+    let (output, created_time) = match find_branch_fork_point(&path, &branch) {
+        Some((hash, created_time)) => (get_checked_command_output("git", &path, &["log", "-1", &format, &hash, "--"]), created_time),
+        None => (get_checked_command_output("git", &path, &["log", &branch, "--reverse", "--max-parents=0", &format, "--"]), None),
+    };
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let keys = stdout.lines().next().unwrap_or_default().split(PARAM_INTERVAL).collect::<Vec<_>>();
+            if keys.len() < 4 {
+                return Err(JsError::from(napiError::from_reason(format!("no commit found on branch {}", branch))))
+            }
+            let author_name = keys[0].to_string();
+            let author_email = keys[1].to_string();
+            let hash = keys[3].to_string();
+            let time = created_time.unwrap_or(keys[2].to_string());
+            let author = Author {
+                name: author_name,
+                email: author_email,
+            };
+            Ok(BranchCreatedInfo {
+                name: branch,
+                time: time,
+                author: author,
+                hash,
+                // the boundary commits of a shallow clone have no parents as well
+                is_shallow: is_shallow(path)
+            })
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Find the commit a branch was created from and the time of the creation if the reflog knows it,
+ * None when the root commit should be used, see `get_branch_create_info`
  */
-fn get_files_diff_context (repo: String, commit_hash1: String, commit_hash2: String) -> Result<Vec<FileDiffContext>, JsError> {
-    let mut result = Vec::new();
-    let files_status = get_files_status_between_commit(repo.to_string(), commit_hash1.to_string(), commit_hash2.to_string());
-    match files_status {
-        Ok(files_status) => {
-            for file_status in files_status.iter() {
-                // println!("{} {}", file_status.path, file_status.status);
-                let mut file_content1 = String::from("");
-                let mut file_content2 = String::from("");
-                let mut addition = 0;
-                let mut deletion = 0;
-                match file_status.status {
-                    FileStatusType::Added => {
-                        let content = get_file_content(repo.to_string(), commit_hash2.to_string(), file_status.path.to_string());
-                        match content {
-                            Ok(content) => {
-                                if is_binary(&content) {
-                                    file_content1 = String::from("Binary file");
-                                    file_content2 = String::from("Binary file");
-                                } else {
-                                    file_content2 = content;
-                                    addition = file_content2.lines().count() as i32;
-                                }
-                            }
-                            Err(_) => {
-                                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}", file_status.path, commit_hash2)));
-                                return Err(JsError::from(err))
-                            }
-                        }
-                    }
-                    FileStatusType::Deleted => {
-                        let content = get_file_content(repo.to_string(), commit_hash1.to_string(), file_status.path.to_string());
-                        match content {
-                            Ok(content) => {
-                                if is_binary(&content) {
-                                    file_content1 = String::from("Binary file");
-                                } else {
-                                    file_content1 = content;
-                                    deletion = file_content1.lines().count() as i32;
-                                }
-                            }
-                            Err(_) => {
-                                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}", file_status.path, commit_hash2)));
-                                return Err(JsError::from(err))
-                            }
-                        }
-                        file_content2 = String::from("File deleted");
-                    }
-                    FileStatusType::Modified => {
-                        let content1 = get_file_content(repo.to_string(), commit_hash1.to_string(), file_status.path.to_string());
-                        let content2 = get_file_content(repo.to_string(), commit_hash2.to_string(), file_status.path.to_string());
-                        let file_change_stat = get_file_modify_stat_between_commit(repo.to_string(), commit_hash1.to_string(), commit_hash2.to_string(), file_status.path.to_string());
-                        match (content1, content2) {
-                            (Ok(content1), Ok(content2)) => {
-                                if is_binary(&content1) && is_binary(&content2) {
-                                    file_content1 = String::from("Binary file");
-                                    file_content2 = String::from("Binary file");
-                                } else if is_binary(&content1) {
-                                    file_content1 = String::from("Binary file");
-                                    file_content2 = content2;
-                                }else if is_binary(&content2) {
-                                    file_content1 = content1;
-                                    file_content2 = String::from("Binary file");
-                                } else {
-                                    file_content1 = content1;
-                                    file_content2 = content2;
-                                }
-                            },
-                            (_, _) => {
-                                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}", file_status.path, commit_hash2)));
-                                return Err(JsError::from(err))
-                            }
-                        }
-                        match file_change_stat {
-                            Ok(file_change_stat) => {
-                                addition = file_change_stat.addition;
-                                deletion = file_change_stat.deletion;
-                            },
-                            Err(e) => {
-                                return Err(e)
-                            }
-                        }
-                    }
-                    FileStatusType::Renamed => {
-                        let reg = Regex::new(r"\s*=>\s*").unwrap();
-                        let names = reg.split(&file_status.message).collect::<Vec<&str>>();
-                        let name1 = names[0];
-                        let name2 = names[1];
-                        let content1 = get_file_content(repo.to_string(), commit_hash1.to_string(), name1.to_string());
-                        let content2 = get_file_content(repo.to_string(), commit_hash2.to_string(), name2.to_string());
-                        let file_change_stat = get_diff_file_stat_between_commit(repo.to_string(), commit_hash1.to_string(), commit_hash2.to_string(), name1.to_string(), name2.to_string());
-                        match (content1, content2) {
-                            (Ok(content1), Ok(content2)) => {
-                                if is_binary(&content1) && is_binary(&content2) {
-                                    file_content1 = String::from("Binary file");
-                                    file_content2 = String::from("Binary file");
-                                } else if is_binary(&content1) {
-                                    file_content1 = String::from("Binary file");
-                                    file_content2 = content2;
-                                }else if is_binary(&content2) {
-                                    file_content1 = content1;
-                                    file_content2 = String::from("Binary file");
-                                } else {
-                                    file_content1 = content1;
-                                    file_content2 = content2;
-                                }
-                            }
-                            (_, _) => {
-                                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}", file_status.path, commit_hash2)));
-                                return Err(JsError::from(err))
-                            }
-                        }
-                        match file_change_stat {
-                            Ok(file_change_stat) => {
-                                addition = file_change_stat.addition;
-                                deletion = file_change_stat.deletion;
-                            },
-                            Err(e) => {
-                                return Err(e)
-                            }
-                        }
-                    }
-                    _ => {}
-                };
-                result.push(FileDiffContext {
-                    commit_hash1: commit_hash1.to_string(),
-                    commit_hash2: commit_hash2.to_string(),
-                    file_path: file_status.path.to_string(),
-                    change_stat: FileLineChangeStat {
-                        addition: addition,
-                        deletion: deletion
-                    },
-                    context1: file_content1,
-                    context2: file_content2,
-                    file_status: file_status.status
-                })
+fn find_branch_fork_point (path: &str, branch: &str) -> Option<(String, Option<String>)> {
+    let name = branch.trim_start_matches("refs/heads/");
+    let local_ref = format!("refs/heads/{}", name);
+    let is_local = get_command_output("git", path, &["show-ref", "--verify", "-q", &local_ref]).map(|output| output.status.success()).unwrap_or(false);
+    if is_local {
+        let format = format!("--format=%H{}%gd{}%gs", PARAM_INTERVAL, PARAM_INTERVAL);
+        if let Ok(output) = get_command_output("git", path, &["reflog", "show", "--date=unix", &format, &local_ref, "--"]) {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // the oldest entry is the last one
+            let oldest = stdout.lines().rfind(|line| !line.trim().is_empty()).unwrap_or_default();
+            let parts = oldest.splitn(3, PARAM_INTERVAL).collect::<Vec<&str>>();
+            if output.status.success() && parts.len() == 3 && parts[2].starts_with("branch: Created from") {
+                let time = parts[1].split_once("@{").map(|(_, time)| time.trim_end_matches('}').to_string());
+                return Some((parts[0].to_string(), time))
+            }
+        }
+    }
+    let default_branch = get_default_branch(path)?;
+    let name = name.trim_start_matches("refs/remotes/");
+    let upstream = get_command_output("git", path, &["rev-parse", "--abbrev-ref", &format!("{}@{{upstream}}", default_branch)])
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if name == default_branch || upstream.as_deref() == Some(name) || name == format!("origin/{}", default_branch) {
+        return None
+    }
+    let mainline = get_checked_command_output("git", path, &["rev-list", "--first-parent", &default_branch, "--"]).ok()?;
+    let mainline = String::from_utf8_lossy(&mainline.stdout).lines().map(|line| line.trim().to_string()).collect::<HashSet<String>>();
+    // with --topo-order the descendants come first, the first commit on the mainline is the newest one
+    let history = get_checked_command_output("git", path, &["rev-list", "--topo-order", branch, "--"]).ok()?;
+    let fork_point = String::from_utf8_lossy(&history.stdout).lines().map(|line| line.trim()).find(|hash| mainline.contains(*hash))?.to_string();
+    Some((fork_point, None))
+}
+
+/**
+ * Get the default branch, the local branch `origin/HEAD` points to, or the local main or master
+ */
+fn get_default_branch (path: &str) -> Option<String> {
+    let mut candidates = Vec::<String>::new();
+    if let Ok(output) = get_command_output("git", path, &["symbolic-ref", "-q", "--short", "refs/remotes/origin/HEAD"]) {
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !stdout.is_empty() {
+            candidates.push(stdout.trim_start_matches("origin/").to_string());
+            candidates.push(stdout);
+        }
+    }
+    candidates.extend(["main".to_string(), "master".to_string()]);
+    candidates.into_iter().find(|candidate| {
+        get_command_output("git", path, &["rev-parse", "--verify", "-q", &format!("{}^{{commit}}", candidate)])
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    })
+}
+
+#[napi]
+fn get_branch_last_commit(path: String, branch: String) -> Result<String, JsError> {
+    let output = get_checked_command_output("git", &path, &["rev-parse", &branch]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(stdout.trim().to_string())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the branch creation info of a repository
+ * @param path path to the repository
+ * @param branchs branchs to get the branch creation info from
+*/
+fn get_branchs_create_info (path: String, branchs: Vec<String>) -> Result<Vec<BranchCreatedInfo>, JsError> {
+    let mut res = Vec::<BranchCreatedInfo>::new();
+    for branch in branchs.iter() {
+        let info = get_branch_create_info(path.to_string(), branch.to_string());
+        match info {
+            Ok(info) => {
+                res.push(info);
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+    Ok(res)   
+}
+
+#[napi]
+/**
+ * Get the repository info of a repository
+ * @param path path to the repository
+*/
+fn get_repository_info_full (path: String) -> Result<RepositoryFull, JsError> {
+    let (branches, current_branch) = get_branches_and_current(&path)?;
+    let authors = get_all_authors(path.to_string())?;
+    let mut branches_arr = Vec::<String>::new();
+    for branch in branches.iter() {
+        // get branch info
+        let branch_name = branch.to_string();
+        branches_arr.push(branch_name);
+    };
+    
+    // get repository name
+    let repo_name = util::get_basename(&path);
+    let name;
+    match repo_name {
+        Some(_name) => {
+            name = _name;
+        }
+        None => {
+            name= String::from("");
+        }
+    };
+    // get remote 
+    let remote = get_remote(path.to_string())?;
+    Ok(RepositoryFull {
+        current_branch: current_branch.clone(),
+        branches: branches_arr,
+        authors: authors.iter().map(| item | (*item).clone()).collect::<Vec<Author>>(),
+        name: name,
+        remote: remote,
+        path: path,
+    })
+}
+
+#[napi]
+/**
+ * Get the repository info in a simple way
+ * @param path path to the repository
+ */
+fn get_repository_info_simple (path: String) -> Result<RepositorySimple, JsError> {
+    let (branches, current_branch) = get_branches_and_current(&path)?;
+    let authors = get_all_authors(path.to_string())?;
+    let mut branches_arr = Vec::<String>::new();
+    let remote = get_remote(path.to_string())?;
+    for branch in branches.iter() {
+        branches_arr.push(branch.to_string());
+    }
+    Ok(RepositorySimple {
+        name: util::get_basename(&path).unwrap(),
+        branches: branches_arr,
+        current_branch: current_branch,
+        path: path,
+        authors,
+        remote
+    })
+}
+
+fn log_shortstat_parse (status: &str) -> Result<(i32, i32, i32), String> {
+    let re = Regex::new(r"(?<changes>\d+) files? changed(?:, (?<insertions>\d+) insertions?\(\+\))?(?:, (?<deletions>\d+) deletions?\(-\))?").unwrap();
+    let Some(captures) = re.captures(status) else {
+        return Err("No match found!".to_string())
+    };
+    let mut insertions = 0;
+    let mut changes = 0;
+    let mut deletions = 0;
+    if let Some(changes_str) = captures.name("changes") {
+        changes = changes_str.as_str().parse::<i32>().unwrap();
+    }
+    if let Some(insertions_str) = captures.name("insertions") {
+        insertions = insertions_str.as_str().parse::<i32>().unwrap();
+    }
+    if let Some(deletions_str) = captures.name("deletions") {
+        deletions = deletions_str.as_str().parse::<i32>().unwrap();
+    }
+    Ok((changes, insertions, deletions))
+}
+
+#[napi]
+/**
+ * Get the statistic of daily contribute in a branch
+ * @param path path to the repository
+ * @param branch branch name, or the end of the range like a tag
+ * @param filter optional filter of the counted commits, e.g. `no_merges` to skip the merge commits or `since` for the last days, see `CommitLogFilter`
+ * @param start_ref optional first commit of the range, it is counted, e.g. the first commit of a release.
+ * The commits from the root are counted if it is not given or ""
+ */
+fn get_contribute_stat (path: String, branch: String, filter: Option<CommitLogFilter>, start_ref: Option<String>) -> Result<BranchStatDailyContribute, JsError> {
+    let mut args = commit_log_filter_args(&filter);
+    match start_ref.filter(|start_ref| !start_ref.is_empty()) {
+        // exclude the parents instead of `<start>^..`, the root commit has no parent to start after
+        Some(start_ref) => {
+            args.push(if branch.is_empty() { "HEAD".to_string() } else { branch.to_string() });
+            args.push(format!("^{}^@", start_ref));
+        }
+        None => args.push(build_commit_range("", &branch)),
+    }
+    args.extend(pathspec_args(&filter.and_then(|filter| filter.pathspec)));
+    get_contribute_stat_in_range(&path, branch, &args, false, &HashMap::new())
+}
+
+#[napi]
+/**
+ * Get the statistic of daily contribute in a branch, only the commits after since_hash are counted.
+ * The result can be merged into a cached result computed up to since_hash.
+ * @param path path to the repository
+ * @param branch branch name
+ * @param since_hash the last commit already counted, it must be an ancestor of the branch
+ */
+fn get_contribute_stat_since (path: String, branch: String, since_hash: String) -> Result<BranchStatDailyContribute, JsError> {
+    // exit code 0 means since_hash is an ancestor of the branch, 1 means not
+    let output = get_command_output("git", &path, &["merge-base", "--is-ancestor", &since_hash, &branch]);
+    match output {
+        Ok(output) => {
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("{} is not an ancestor of {}\n{}", since_hash, branch, stderr.trim())));
+                return Err(JsError::from(err))
             }
-            Ok(result)
         }
         Err(e) => {
-            return Err(e)
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
         }
     }
-}
+    let commit_range = format!("{}..{}", since_hash, branch);
+    get_contribute_stat_in_range(&path, branch, &[commit_range], false, &HashMap::new())
+}
+
+#[napi]
+/**
+ * Get the statistic of daily contribute in a branch, the authors are aggregated by email instead of name
+ * @param path path to the repository
+ * @param branch branch name
+ * @param identity_map optional map from an email to the canonical email, the emails mapped to the same canonical email are merged
+ */
+fn get_contribute_stat_by_email (path: String, branch: String, identity_map: Option<HashMap<String, String>>) -> Result<BranchStatDailyContribute, JsError> {
+    let commit_range = build_commit_range("", &branch);
+    get_contribute_stat_in_range(&path, branch, &[commit_range], true, &identity_map.unwrap_or_default())
+}
+
+/**
+ * Compute the statistic of daily contribute of the commits selected by the log args
+ * @param log_args the commit range and the filter args passed to git log
+ * @param group_by_email aggregate the authors by email (mapped by identity_map) instead of name
+ */
+fn get_contribute_stat_in_range (path: &str, branch: String, log_args: &[String], group_by_email: bool, identity_map: &HashMap<String, String>) -> Result<BranchStatDailyContribute, JsError> {
+    let format = "--pretty=format:".to_string()+ COMMIT_INETRVAL + "%an" + PARAM_INTERVAL + "%ae" + PARAM_INTERVAL + "%cs";
+    let mut args = vec!["log", "--shortstat", &format, "--reverse"];
+    args.extend(log_args.iter().map(|arg| arg.as_str()));
+    let output = get_checked_command_output("git", path, &args);
+    match output {
+        Ok(output) => {
+            let mut authors_stat = HashMap::<String, AuthorStatDailyContribute>::new();
+            let mut total_stat = StatDailyContribute {
+                commit_count: Vec::<i32>::new(),
+                date_list: Vec::<String>::new(),
+                insertion: Vec::<i32>::new(),
+                deletions: Vec::<i32>::new(),
+                change_files: Vec::<i32>::new(),
+            };
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let commits = stdout.trim().split(COMMIT_INETRVAL).filter(| line | line.to_string() != "").collect::<Vec<_>>();
+            // parse commits
+            for i in 0..commits.len() {
+                let commit = commits[i];
+                let lines = commit.split("\n").filter(|line| !line.trim().is_empty()).collect::<Vec<_>>();
+                if lines.is_empty() {continue;}
+                let auth_info = lines[0].split(PARAM_INTERVAL).collect::<Vec<_>>();
+                if auth_info.len() != 3 {continue;}
+                // parse shortstat, a commit without any line change (empty or merge commit) has no shortstat,
+                // it still counts as a commit
+                let (changes, insertions, deletions) = match lines.get(1) {
+                    Some(shortstat) => match log_shortstat_parse(shortstat) {
+                        Ok(stat) => stat,
+                        Err(e) => {
+                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to parse shortstat: {}\n{}", shortstat, e)));
+                            return Err(JsError::from(err))
+                        }
+                    },
+                    None => (0, 0, 0),
+                };
+                // println!("======================\n{}\n{}\n============================", auth_info.join("|"), change_info.join("|"));
+                let name = auth_info[0].to_string();
+                let mut email = auth_info[1].to_string();
+                let date = auth_info[2].to_string();
+                let key = if group_by_email {
+                    email = identity_map.get(&email).cloned().unwrap_or(email);
+                    email.to_string()
+                } else {
+                    name.to_string()
+                };
+                // if this author has contained
+                if authors_stat.contains_key(&key) {
+                    let author = authors_stat.get_mut(&key).unwrap();
+                    let len = author.stat.date_list.len();
+                    // if one day has multiple commits
+                    if author.stat.date_list[len - 1] == date {
+                        author.stat.commit_count[len - 1] += 1;
+                        author.stat.change_files[len - 1] += changes;
+                        author.stat.insertion[len - 1] += insertions;
+                        author.stat.deletions[len - 1] += deletions;
+                    } else {
+                        // new day and first commit
+                        author.stat.date_list.push(date.to_string());
+                        author.stat.commit_count.push(1);
+                        author.stat.insertion.push(insertions);
+                        author.stat.deletions.push(deletions);
+                        author.stat.change_files.push(changes);
+                    }
+                } else {
+                    // new author
+                    let mut author = AuthorStatDailyContribute {
+                        author: Author {
+                            name: name.to_string(),
+                            email: email,
+                        },
+                        stat: StatDailyContribute {
+                            commit_count: Vec::<i32>::new(),
+                            date_list: Vec::<String>::new(),
+                            insertion: Vec::<i32>::new(),
+                            deletions: Vec::<i32>::new(),
+                            change_files: Vec::<i32>::new(),
+                        }
+                    };
+                    author.stat.date_list.push(date.to_string());
+                    author.stat.commit_count.push(1);
+                    author.stat.insertion.push(insertions);
+                    author.stat.deletions.push(deletions);
+                    author.stat.change_files.push(changes);
+                    authors_stat.insert(key, author);
+                }
+                // total stat
+                let len = total_stat.date_list.len();
+                if len > 0 && total_stat.date_list[len - 1] == date {
+                    total_stat.commit_count[len - 1] += 1;
+                    total_stat.change_files[len - 1] += changes;
+                    total_stat.insertion[len - 1] += insertions;
+                    total_stat.deletions[len - 1] += deletions;
+                } else {
+                    // new day and first commit
+                    total_stat.date_list.push(date.to_string());
+                    total_stat.commit_count.push(1);
+                    total_stat.insertion.push(insertions);
+                    total_stat.deletions.push(deletions);
+                    total_stat.change_files.push(changes);
+                    // println!("{:#?}", total_stat)
+                }
+            }
+            Ok(BranchStatDailyContribute {
+                branch: branch,
+                total_stat: total_stat,
+                authors_stat: authors_stat.into_values().collect::<Vec<AuthorStatDailyContribute>>(),
+            })
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+
+}
+
+#[napi]
+/**
+ * Get the total commits and line changes of every author in a branch, the authors are aggregated by name
+ * and sorted by the number of changed lines, the most active author first
+ * @param path path to the repository
+ * @param branch branch name
+ */
+fn get_author_totals (path: String, branch: String) -> Result<Vec<AuthorTotals>, JsError> {
+    let format = "--pretty=format:".to_string() + COMMIT_INETRVAL + "%an" + PARAM_INTERVAL + "%ae";
+    let commit_range = build_commit_range("", &branch);
+    let output = get_checked_command_output("git", &path, &["log", "--shortstat", &format, &commit_range]);
+    match output {
+        Ok(output) => {
+            let mut totals = HashMap::<String, AuthorTotals>::new();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            for commit in stdout.split(COMMIT_INETRVAL) {
+                let lines = commit.split("\n").filter(|line| !line.trim().is_empty()).collect::<Vec<_>>();
+                let Some((name, email)) = lines.first().and_then(|line| line.split_once(PARAM_INTERVAL)) else {
+                    continue;
+                };
+                // a commit without any line change (empty or merge commit) has no shortstat
+                let (changes, insertions, deletions) = match lines.get(1) {
+                    Some(shortstat) => match log_shortstat_parse(shortstat) {
+                        Ok(stat) => stat,
+                        Err(e) => {
+                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to parse shortstat: {}\n{}", shortstat, e)));
+                            return Err(JsError::from(err))
+                        }
+                    },
+                    None => (0, 0, 0),
+                };
+                let total = totals.entry(name.to_string()).or_insert_with(|| AuthorTotals {
+                    author: Author {
+                        name: name.to_string(),
+                        email: email.trim().to_string(),
+                    },
+                    commit_count: 0,
+                    insertions: 0,
+                    deletions: 0,
+                    files_changed: 0,
+                });
+                total.commit_count += 1;
+                total.insertions += insertions;
+                total.deletions += deletions;
+                total.files_changed += changes;
+            }
+            let mut res = totals.into_values().collect::<Vec<AuthorTotals>>();
+            res.sort_by(|a, b| (b.insertions + b.deletions).cmp(&(a.insertions + a.deletions)).then_with(|| a.author.name.cmp(&b.author.name)));
+            Ok(res)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the changed lines between two commits grouped by the file extension,
+ * sorted by the number of changed lines, the most changed extension first
+ * @param path path to the repository
+ * @param ref_a the old commit, the empty tree if it is ""
+ * @param ref_b the new commit, HEAD if it is ""
+ */
+fn get_change_stats_by_extension (path: String, ref_a: String, ref_b: String) -> Result<Vec<ExtensionChangeStat>, JsError> {
+    ensure_git_repository(&path)?;
+    let entries = get_diff_numstat(&path, &["--no-renames", "--end-of-options", &build_diff_range(&ref_a, &ref_b)])?;
+    let mut stats = HashMap::<String, ExtensionChangeStat>::new();
+    for entry in entries.iter() {
+        let extension = util::get_extension(&entry.path).unwrap_or("(none)".to_string());
+        let stat = stats.entry(extension.to_string()).or_insert_with(|| ExtensionChangeStat {
+            extension,
+            additions: 0,
+            deletions: 0,
+            files: 0,
+            binary_files: 0,
+        });
+        stat.files += 1;
+        if entry.is_binary {
+            stat.binary_files += 1;
+        }
+        stat.additions += entry.additions;
+        stat.deletions += entry.deletions;
+    }
+    let mut res = stats.into_values().collect::<Vec<ExtensionChangeStat>>();
+    res.sort_by(|a, b| (b.additions + b.deletions).cmp(&(a.additions + a.deletions)).then_with(|| a.extension.cmp(&b.extension)));
+    Ok(res)
+}
+
+/**
+ * Insert the file info list
+ */
+fn insert_file_to_tree(file_list: &mut Vec<RepoFileInfo>, object_mode: &str, object_type: &str, object_name: &str, object_size: i64, object_path: &str) {
+    let file_tree = object_path.split("/").collect::<Vec<&str>>();
+    let mut tmp_file_list = file_list;
+    // println!("{}", file_tree.len());
+    for i in 0..file_tree.len() {
+        let index = tmp_file_list.iter().position(|t| t.name == file_tree[i] && t.is_dir);
+        match index {
+            Some( index ) => {
+                // println!("{}", index);
+                tmp_file_list = &mut tmp_file_list[index].children;
+            }
+            None => {
+                let is_last = i == file_tree.len() - 1;
+                let is_dir = object_mode.starts_with("040000") || !is_last;
+                // println!("{} {} {} {} {} {}",file_tree.len() - 1, i, file_tree[i], is_dir, is_last, object_path);
+                // the components before i are the path of the parent directory, the root is "./"
+                let dir = if i != 0 { file_tree[0..i].join("/") } else {"./".to_string()};
+                let file = RepoFileInfo {
+                    name: file_tree[i].to_string(),
+                    dir,
+                    object_mode: if is_last {object_mode.to_string()} else {"".to_string()},
+                    object_kind: parse_object_kind(object_mode, is_dir),
+                    object_type: if is_last {object_type.to_string()} else {"".to_string()},
+                    object_name: if is_last {object_name.to_string()} else {"".to_string()},
+                    object_size: if is_last {object_size} else {0},
+                    is_dir,
+                    children: Vec::<RepoFileInfo>::new(),
+                };
+                tmp_file_list.push(file);
+                // println!("{:?}", tmp_file_list);
+                if is_dir {
+                    tmp_file_list = tmp_file_list.last_mut().unwrap().children.as_mut();
+                }
+            }
+        }
+        
+    }
+}
+
+/**
+ * From the file info list, generate the file tree
+ */
+fn file_info_list_to_tree (file_info_list: Vec<&str>) -> Vec<RepoFileInfo> {
+    let mut file_list: Vec<RepoFileInfo> = Vec::new();
+    for line in file_info_list {
+        let file_info = line.split(PARAM_INTERVAL).collect::<Vec<&str>>();
+        if file_info.len() == 5 {
+            let object_mode = file_info[0];
+            let object_type = file_info[1];
+            let object_size = parse_object_size(file_info[2]);
+            let object_name = file_info[3];
+            let object_path = unquote_git_path(file_info[4]);
+            let object_path = object_path.as_str();
+            let file_tree = object_path.split("/").collect::<Vec<&str>>();
+            if file_tree.len() > 1 {
+                insert_file_to_tree(&mut file_list, object_mode, object_type, object_name, object_size, object_path)
+            } else { 
+                let is_dir = object_mode.starts_with("040000");
+                let file = RepoFileInfo {
+                    name: object_path.to_string(),
+                    dir: "./".to_string(),
+                    object_mode: object_mode.to_string(),
+                    object_kind: parse_object_kind(object_mode, is_dir),
+                    object_type: object_type.to_string(),
+                    object_name: object_name.to_string(),
+                    object_size,
+                    is_dir,
+                    children: Vec::<RepoFileInfo>::new(),
+                };
+                file_list.push(file);
+            }
+       }
+    }
+    return file_list
+}
+
+/**
+ * Decode the mode of ls-tree, the untracked files have no mode and are regular files
+ * 040000: Directory
+ * 100644: Regular non-executable file
+ * 100664: Regular non-executable group-writeable file
+ * 100755: Regular executable file
+ * 120000: Symbolic link
+ * 160000: Gitlink
+ */
+fn parse_object_kind (object_mode: &str, is_dir: bool) -> ObjectKind {
+    if is_dir {
+        return ObjectKind::Directory
+    }
+    match object_mode {
+        "040000" => ObjectKind::Directory,
+        "100664" => ObjectKind::GroupWritable,
+        "100755" => ObjectKind::Executable,
+        "120000" => ObjectKind::Symlink,
+        "160000" => ObjectKind::Gitlink,
+        _ => ObjectKind::File,
+    }
+}
+
+/**
+ * Parse the padded `%(objectsize)`, the trees and the gitlinks have the size "-" which is 0
+ */
+fn parse_object_size (object_size: &str) -> i64 {
+    object_size.trim().parse::<i64>().unwrap_or(0)
+}
+
+#[napi]
+/**
+ * Get the file list of a repository
+ */
+fn get_repo_file_list (path: String, branch_or_hash: String) -> Result<Vec<RepoFileInfo>, JsError> {
+    let format = format!("--format=%(objectmode){}%(objecttype){}%(objectsize:padded){}%(objectname){}%(path)", PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL);
+    // -z separates the entries by NUL, but %(path) is still quoted by git when it has special characters
+    let output = get_checked_command_output("git", &path, &["ls-tree", "-r", "-z", &branch_or_hash, &format]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let lines = stdout.split('\0').filter(|line| !line.is_empty()).collect::<Vec<&str>>();
+            let file_list = file_info_list_to_tree(lines);
+            Ok(file_list)
+
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the immediate children of a directory of a repository without reading the subdirectories,
+ * to load a file tree lazily one directory at a time, the children of the result are always empty
+ * @param path path to the repository
+ * @param branch_or_hash the branch or the commit to list
+ * @param subdir the directory relative to the repository root, the root if it is ""
+ */
+fn get_repo_dir_list (path: String, branch_or_hash: String, subdir: String) -> Result<Vec<RepoFileInfo>, JsError> {
+    let format = format!("--format=%(objectmode){}%(objecttype){}%(objectsize:padded){}%(objectname){}%(path)", PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL);
+    let subdir = subdir.trim_matches('/').to_string();
+    let mut args = vec!["ls-tree", "-z", &format, branch_or_hash.as_str()];
+    // the trailing "/" lists the content of the directory instead of the directory itself
+    let pathspec = format!("{}/", subdir);
+    if !subdir.is_empty() {
+        args.extend(["--", pathspec.as_str()]);
+    }
+    let output = get_checked_command_output("git", &path, &args);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let file_list = stdout.split('\0').filter_map(|line| {
+                let file_info = line.split(PARAM_INTERVAL).collect::<Vec<&str>>();
+                if file_info.len() != 5 {
+                    return None
+                }
+                let object_path = unquote_git_path(file_info[4]);
+                let object_path = object_path.as_str();
+                Some(RepoFileInfo {
+                    name: object_path.rsplit('/').next().unwrap_or(object_path).to_string(),
+                    dir: if subdir.is_empty() { "./".to_string() } else { subdir.to_string() },
+                    object_mode: file_info[0].to_string(),
+                    object_kind: parse_object_kind(file_info[0], false),
+                    object_type: file_info[1].to_string(),
+                    object_name: file_info[3].to_string(),
+                    object_size: parse_object_size(file_info[2]),
+                    is_dir: file_info[0].starts_with("040000"),
+                    children: Vec::<RepoFileInfo>::new(),
+                })
+            }).collect();
+            Ok(file_list)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the tree of the untracked files in the working tree, the files ignored by .gitignore are excluded
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param include_ignored also include the ignored files
+ */
+fn get_untracked_tree (path: String, include_ignored: bool) -> Result<Vec<RepoFileInfo>, JsError> {
+    let mut args = vec!["ls-files", "--others", "-z"];
+    if !include_ignored {
+        args.push("--exclude-standard");
+    }
+    let output = get_checked_command_output("git", &path, &args);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // untracked files have no object yet, only the size on the disk is known
+            let lines = stdout.split("\0").filter(|file_path| !file_path.is_empty()).map(|file_path| {
+                let size = std::fs::metadata(std::path::Path::new(&path).join(file_path)).map(|m| m.len()).unwrap_or(0);
+                format!("{}blob{}{}{}{}{}", PARAM_INTERVAL, PARAM_INTERVAL, size, PARAM_INTERVAL, PARAM_INTERVAL, file_path)
+            }).collect::<Vec<String>>();
+            let file_list = file_info_list_to_tree(lines.iter().map(|line| line.as_str()).collect());
+            Ok(file_list)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the file status of a commit
+ */
+fn get_commit_file_status (path: String, hash: String) -> Result<FileStatusReport, JsError> {
+    let format = format!("--format=%H{}%s{}%an{}%ae{}%at", PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL);
+    let output = get_checked_command_output("git", &path, &["show", &hash, "--name-status", "--oneline", &format]);
+    match output {
+        Ok(output) => {
+            let stdout = decode_log_output(&output.stdout);
+            let lines = stdout.trim().split("\n").filter(|t| *t != "").collect::<Vec<&str>>();
+            let commit_info = lines[0].trim().split(PARAM_INTERVAL).collect::<Vec<&str>>();
+            let commit_hash = commit_info[0];
+            let commit_message = commit_info[1];
+            let commit_author = commit_info[2];
+            let commit_author_email = commit_info[3];
+            let commit_time = commit_info[4];
+            let file_status = lines[1..].iter().map(|line| parse_name_status_line(line)).collect::<Vec<FileStatus>>();
+            let file_status_report = FileStatusReport {
+                title: commit_message.to_string(),
+                hash: commit_hash.to_string(),
+                status: file_status,
+                time: commit_time.to_string(),
+                author: Author {
+                    name: commit_author.to_string(),
+                    email: commit_author_email.to_string(),
+                }
+            };
+            Ok(file_status_report)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the metadata and the file status of a commit in one call
+ * @param path path to the repository
+ * @param hash commit hash
+ */
+fn get_commit_detail (path: String, hash: String) -> Result<CommitDetail, JsError> {
+    // an annotated tag, a tree or a blob would print something else than the commit fields
+    let hash = resolve_ref(path.clone(), hash)?;
+    let format = "--format=%H%x00%h%x00%P%x00%an%x00%ae%x00%at%x00%cn%x00%ce%x00%ct%x00%s";
+    let output = get_checked_command_output("git", &path, &["show", "-s", format, "--end-of-options", &hash]);
+    let commit_info = match output {
+        Ok(output) => decode_log_output(&output.stdout),
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    let commit_info = commit_info.trim_end_matches('\n').split('\0').collect::<Vec<&str>>();
+    if commit_info.len() < 10 {
+        let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("unexpected output of `git show` for {}: {}", hash, commit_info.join(" "))));
+        return Err(JsError::from(err))
+    }
+    // the same files as `git show --name-status`, the plumbing needs -M to detect the renames,
+    // a merge only lists the files changed against all of its parents
+    let output = get_checked_command_output("git", &path, &["diff-tree", "-r", "-M", "--root", "--cc", "--name-status", "--no-commit-id", &hash]);
+    let status = match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            stdout.lines().filter(|line| line.contains('\t')).map(parse_name_status_line).collect()
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    Ok(CommitDetail {
+        hash: commit_info[0].to_string(),
+        short_hash: commit_info[1].to_string(),
+        // the root commit has no parent
+        parents: commit_info[2].split_ascii_whitespace().map(|p| p.to_string()).collect(),
+        author: Author {
+            name: commit_info[3].to_string(),
+            email: commit_info[4].to_string(),
+        },
+        time: commit_info[5].to_string(),
+        committer: Author {
+            name: commit_info[6].to_string(),
+            email: commit_info[7].to_string(),
+        },
+        commit_time: commit_info[8].to_string(),
+        title: commit_info[9].to_string(),
+        status,
+    })
+}
+
+/**
+ * Parse a line of `--name-status` output, a renamed file carries "old => new" in the message
+ */
+fn parse_name_status_line (line: &str) -> FileStatus {
+    let params = line.split("\t").collect::<Vec<&str>>();
+    let file_path = params[1].to_string();
+    let mut message = "".to_string();
+    let status = parse_file_status(&params[0][0..1]);
+    if (status == FileStatusType::Renamed || status == FileStatusType::Copied) && params.len() == 3 {
+        message = params[1].to_string() + " => " + params[2];
+    }
+    FileStatus {
+        path: file_path,
+        status,
+        message,
+    }
+}
+
+/**
+ * Get the number of the paths following a status of `--name-status -z`, e.g. 2 for "R100", None if it is not a status
+ */
+fn name_status_path_count (status: &str) -> Option<usize> {
+    let mut chars = status.chars();
+    let count = match chars.next()? {
+        'R' | 'C' => 2,
+        'A' | 'D' | 'M' | 'T' | 'U' | 'X' | 'B' => 1,
+        _ => return None,
+    };
+    if chars.all(|c| c.is_ascii_digit()) {
+        Some(count)
+    } else {
+        None
+    }
+}
+
+fn parse_file_status (status_flag: &str) -> FileStatusType {
+    match status_flag {
+        "A" => FileStatusType::Added,
+        "D" => FileStatusType::Deleted,
+        "M" => FileStatusType::Modified,
+        "R" => FileStatusType::Renamed,
+        "C" => FileStatusType::Copied,
+        "U" => FileStatusType::Updated,
+        _ =>FileStatusType::Unknown,
+    }
+}
+
+/**
+ * Get the file list of a repository
+ */
+fn get_file_between_commit_status(path: String, commit_hash1: String, file_path: String) -> Result<(FileStatusType, String), String> {
+    let output: Result<Output, io::Error> = get_checked_command_output("git", &path, &["show", &commit_hash1, "--name-status",  "--format=", "--", &file_path]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let lines = stdout.trim().split_ascii_whitespace().collect::<Vec<&str>>();
+            if lines.len() > 0 {
+                let status_flag = lines[0][0..1].to_string();
+                let mut message = "".to_string();
+                let file_status = parse_file_status(&status_flag);
+                if file_status == FileStatusType::Renamed {
+                    if lines.len() > 1 {
+                        message = lines[1].to_string() + " => " + lines[2];
+                    }
+                }
+                Ok((file_status, message))
+            }
+            else {
+                Err("No status found".to_string())
+            }
+        }
+        Err(e) => {
+            Err(e.to_string())
+        }
+    }
+}
+
+/**
+ * The changed lines of a file from `git diff --numstat`, a binary file has no line counts
+ */
+struct NumstatEntry {
+    path: String,
+    additions: i32,
+    deletions: i32,
+    is_binary: bool,
+}
+
+/**
+ * Parse the output of `git diff --numstat -z`, a renamed or copied file has an empty path
+ * followed by the old path and the new path, a binary file has "-" as the counts
+ */
+fn parse_numstat(stdout: &str) -> Vec<NumstatEntry> {
+    let mut entries = Vec::new();
+    let mut fields = stdout.split('\0');
+    while let Some(field) = fields.next() {
+        let parts = field.splitn(3, '\t').collect::<Vec<&str>>();
+        if parts.len() != 3 {
+            continue;
+        }
+        let path = if parts[2].is_empty() {
+            // skip the old path
+            fields.next();
+            fields.next().unwrap_or("").to_string()
+        } else {
+            parts[2].to_string()
+        };
+        let is_binary = parts[0] == "-" && parts[1] == "-";
+        entries.push(NumstatEntry {
+            path,
+            additions: parts[0].parse::<i32>().unwrap_or(0),
+            deletions: parts[1].parse::<i32>().unwrap_or(0),
+            is_binary,
+        });
+    }
+    entries
+}
+
+/**
+ * Run `git diff --numstat -z` with the args, e.g. the commits and the paths
+ */
+fn get_diff_numstat(path: &str, args: &[&str]) -> Result<Vec<NumstatEntry>, JsError> {
+    let mut diff_args = vec!["diff", "--numstat", "-z"];
+    diff_args.extend_from_slice(args);
+    let output = get_checked_command_output("git", path, &diff_args);
+    match output {
+        Ok(output) => Ok(parse_numstat(&String::from_utf8_lossy(&output.stdout))),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Sum the line changes of the numstat entries, the binary files count as no line change
+ */
+fn sum_numstat(entries: &[NumstatEntry]) -> FileLineChangeStat {
+    FileLineChangeStat {
+        addition: entries.iter().map(|entry| entry.additions).sum(),
+        deletion: entries.iter().map(|entry| entry.deletions).sum(),
+    }
+}
+
+#[napi]
+/**
+ * Get the best common ancestor of two commits, the old side of a three-dot diff `a...b`
+ * @param path path to the repository
+ * @param ref_a the first commit
+ * @param ref_b the second commit
+ */
+fn get_merge_base (path: String, ref_a: String, ref_b: String) -> Result<String, JsError> {
+    let output = get_command_output("git", &path, &["merge-base", "--end-of-options", &ref_a, &ref_b]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            match output.status.code() {
+                Some(0) => Ok(stdout.trim().to_string()),
+                // git exits with 1 and prints nothing when the commits have no common ancestor
+                Some(1) if stdout.trim().is_empty() && stderr.trim().is_empty() => {
+                    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("no merge base of {} and {}", ref_a, ref_b)));
+                    Err(JsError::from(err))
+                }
+                _ => {
+                    let message = if is_dubious_ownership(&stderr) { dubious_ownership_message(&path) } else { stderr.trim().to_string() };
+                    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git merge-base` exited with {}: {}", output.status, message)));
+                    Err(JsError::from(err))
+                }
+            }
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * The diff functions compare the two commits directly (`git diff a..b`, the same as `git diff a b`) by default,
+ * with three_dot the old side is the merge base of them (`git diff a...b`), i.e. only the changes made on the side of b.
+ * An empty old side is the empty tree, it has no merge base and is returned as it is
+ */
+fn resolve_three_dot_base (path: &str, start: &str, end: &str, three_dot: Option<bool>) -> Result<String, JsError> {
+    if !three_dot.unwrap_or(false) || start.is_empty() || start == EMPTY_TREE_HASH {
+        return Ok(start.to_string())
+    }
+    let end = if end.is_empty() { "HEAD" } else { end };
+    get_merge_base(path.to_string(), start.to_string(), end.to_string())
+}
+
+#[napi]
+/**
+ * Get the number of the changed files and the changed lines made on the side of ref_b since it forked from ref_a (`git diff a...b`),
+ * the binary files are counted as changed files without line changes
+ * @param path path to the repository
+ * @param ref_a the base, the empty tree if it is "" or the parent of a root commit, so all the files of ref_b are added
+ * @param ref_b the compared commit, HEAD if it is ""
+ */
+fn get_range_summary (path: String, ref_a: String, ref_b: String) -> Result<RangeSummary, JsError> {
+    let ref_a = resolve_diff_base(&path, &ref_a);
+    let ref_a = resolve_three_dot_base(&path, &ref_a, &ref_b, Some(true))?;
+    let output = get_checked_command_output("git", &path, &["diff", "--shortstat", &build_diff_range(&ref_a, &ref_b)]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // nothing is printed when there is no change
+            if stdout.trim().is_empty() {
+                return Ok(RangeSummary::default())
+            }
+            match log_shortstat_parse(stdout.trim()) {
+                Ok((files_changed, insertions, deletions)) => Ok(RangeSummary { files_changed, insertions, deletions }),
+                Err(e) => {
+                    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to parse shortstat `{}`: {}", stdout.trim(), e)));
+                    Err(JsError::from(err))
+                }
+            }
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the file change statistic between two commits
+ * @param path The path of the repository
+ * @param commit_hash1 The commit hash of the first commit
+ * @param commit_hash2 The commit hash of the second commit
+ * @param file_path The path of the file
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ */
+fn get_file_modify_stat_between_commit(path: String, commit_hash1: String, commit_hash2: String, file_path: String, three_dot: Option<bool>) -> Result<FileLineChangeStat, JsError> {
+    let commit_hash1 = resolve_three_dot_base(&path, &commit_hash1, &commit_hash2, three_dot)?;
+    let commit_range = build_diff_range(&commit_hash1, &commit_hash2);
+    let entries = get_diff_numstat(&path, &[&commit_range, "--", &file_path])?;
+    Ok(sum_numstat(&entries))
+}
+
+#[napi]
+/**
+ * Get the files change status between two commits
+ * @param path The path of the repository
+ * @param commit_hash1 The commit hash of the first commit
+ * @param commit_hash2 The commit hash of the second commit
+ * @param rename_options The rename and copy detection, see `RenameOptions`
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ * @param pathspec only the files matching the pathspecs, e.g. "src/" or "*.rs", the whole repository if it is not given or empty
+ */
+fn get_files_status_between_commit (path: String, commit_hash1: String, commit_hash2: String, rename_options: Option<RenameOptions>, three_dot: Option<bool>, pathspec: Option<Vec<String>>) -> Result<Vec<FileStatus>, JsError> {
+    let base = resolve_three_dot_base(&path, &commit_hash1, &commit_hash2, three_dot)?;
+    let mut args = vec!["diff".to_string(), "--name-status".to_string()];
+    args.extend(rename_option_args(&rename_options)?);
+    args.push(build_diff_range(&base, &commit_hash2));
+    args.extend(pathspec_args(&pathspec));
+    let output = get_checked_command_output("git", &path, &args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>());
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let file_status = stdout.lines().filter(|line| !line.trim().is_empty()).map(|line| parse_name_status_line(line)).collect::<Vec<FileStatus>>();
+            Ok(file_status)
+        }
+        Err(e) => {
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get commit status:\nRepository path: {}\ncommit hash1: {}\ncommit hash2: {}\n{}", path, commit_hash1, commit_hash2, e)));
+            return Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the status and the changed lines of every file between two commits in one git call
+ * @param path The path of the repository
+ * @param ref_a The old commit
+ * @param ref_b The new commit
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ */
+fn get_files_numstat_between_commit (path: String, ref_a: String, ref_b: String, three_dot: Option<bool>) -> Result<Vec<FileNumstat>, JsError> {
+    ensure_git_repository(&path)?;
+    let ref_a = resolve_three_dot_base(&path, &ref_a, &ref_b, three_dot)?;
+    // --raw and --numstat are printed one after the other, the files are in the same order in both
+    let output = get_checked_command_output("git", &path, &["diff", "--raw", "--numstat", "-z", &build_diff_range(&ref_a, &ref_b)]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut fields = stdout.split('\0').peekable();
+            let mut files = Vec::new();
+            while let Some(meta) = fields.next_if(|field| field.starts_with(':')) {
+                let status = parse_file_status(meta.rsplit(' ').next().unwrap_or("").get(0..1).unwrap_or(""));
+                let first_path = fields.next().unwrap_or("").to_string();
+                let (path, old_path) = if status == FileStatusType::Renamed || status == FileStatusType::Copied {
+                    (fields.next().unwrap_or("").to_string(), Some(first_path))
+                } else {
+                    (first_path, None)
+                };
+                files.push(FileNumstat {
+                    path,
+                    old_path,
+                    status,
+                    additions: 0,
+                    deletions: 0,
+                    is_binary: false,
+                });
+            }
+            let numstat = parse_numstat(&fields.collect::<Vec<&str>>().join("\0"));
+            for (file, entry) in files.iter_mut().zip(numstat.iter()) {
+                file.additions = entry.additions;
+                file.deletions = entry.deletions;
+                file.is_binary = entry.is_binary;
+            }
+            Ok(files)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the file diff context of a file
+ * @param repo: the path of the repository
+ * @param commit_hash1: the hash of the first commit
+ * @param commit_hash2: the hash of the second commit
+ * @param file_path: the path of the file
+ * @param three_dot: compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ */
+fn diff_file_context (repo: String, commit_hash1: String, commit_hash2: String, file_path: String, three_dot: Option<bool>) -> Result<FileDiffContext, JsError> {
+    // 先用从 git show hash1 hash2 --name-status --format="" file_path 来获取文件在两个提交见的状态，是需改还是删除还是重命名等等
+    // 如果是文件中的修改，则调用 git diff --shortstat hash1 hash2 -- file_path 来记录文件中修改的数量，二进制文件不需要做，只需要提示为二进制文件即可
+    //      如果是重命名、删除的话，就不用做，提供说明
+    // 如果是文件中修改的话，使用 git cat-file -p hash:path 来获取文件内容
+    let commit_hash1 = resolve_diff_base(&repo, &commit_hash1);
+    let commit_hash1 = resolve_three_dot_base(&repo, &commit_hash1, &commit_hash2, three_dot)?;
+    let commit_status = get_file_between_commit_status(repo.to_string(), commit_hash2.to_string(), file_path.to_string());
+    match commit_status {
+        Ok(commit_status) => {
+            let (status, message) = commit_status;
+            let mut context1 = "".to_string();
+            let mut context2 = "".to_string();
+            match status {
+                // 添加
+                FileStatusType::Added =>{
+                    let output = get_checked_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash2, file_path)]);
+                    match output {
+                        Ok(output) => {
+                            let stdout = decode_text(&output.stdout);
+                            context2 = stdout.to_string();
+                            Ok(FileDiffContext {
+                                commit_hash1: commit_hash1.to_string(),
+                                commit_hash2: commit_hash2.to_string(),
+                                file_path: file_path.to_string(),
+                                change_stat: FileLineChangeStat {
+                                    addition: stdout.trim().lines().count() as i32,
+                                    deletion: 0,
+                                },
+                                lfs_pointer1: parse_lfs_pointer(&context1),
+                                lfs_pointer2: parse_lfs_pointer(&context2),
+                                context1,
+                                context2,
+                                file_status: status,
+                            })
+                        }
+                        Err(e) => {
+                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}\n{}", file_path, commit_hash2, e)));
+                            Err(JsError::from(err))
+                        }
+                    }
+                } 
+                // 删除
+                FileStatusType::Deleted => {
+                    let output = get_checked_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash1, file_path)]);
+                    match output {
+                        Ok(output) => {
+                            let stdout = decode_text(&output.stdout);
+                            context1 = stdout.to_string();
+                            Ok(FileDiffContext {
+                                commit_hash1: commit_hash1.to_string(),
+                                commit_hash2: commit_hash2.to_string(),
+                                file_path: file_path.to_string(),
+                                change_stat: FileLineChangeStat {
+                                    addition: 0,
+                                    deletion: stdout.trim().lines().count() as i32,
+                                },
+                                lfs_pointer1: parse_lfs_pointer(&context1),
+                                lfs_pointer2: parse_lfs_pointer(&context2),
+                                context1,
+                                context2,
+                                file_status: status,
+                            })
+                        }
+                        Err(e) => {
+                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}\n{}", file_path, commit_hash2, e)));
+                            Err(JsError::from(err))
+                        }
+                    }
+
+                }
+                // 修改
+                FileStatusType::Modified => {
+                    // 获取修改的数量
+                    let output = get_checked_command_output("git", &repo, &["diff", "--shortstat", &commit_hash1, &commit_hash2, "--", &file_path]);
+                    let mut addition = 0;
+                    let mut deletion = 0;
+                    match output {
+                        Ok(output) => {
+                            let stdout = String::from_utf8_lossy(&output.stdout);
+                            // an empty shortstat means there is no line change, e.g. only the mode is changed
+                            if !stdout.trim().is_empty() {
+                                match log_shortstat_parse(stdout.trim()) {
+                                    Ok((_, insertions, deletions)) => {
+                                        addition = insertions;
+                                        deletion = deletions;
+                                    }
+                                    Err(e) => {
+                                        let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to parse shortstat `{}`: {}", stdout.trim(), e)));
+                                        return Err(JsError::from(err))
+                                    }
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file diff:\nfile path: {}\ncommit hash1: {}\ncommit hash2: {}\n{}", file_path, commit_hash1, commit_hash2, e)));
+                            return Err(JsError::from(err))
+                        }
+                    }
+                    // 获取文件内容
+                    let mut context1: String;
+                    let context1_output = get_checked_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash1, file_path)]);
+                    match context1_output {
+                        Ok(context1_output) => {
+                            let stdout = decode_text(&context1_output.stdout);
+                            context1 = stdout.to_string();
+                        }
+                        Err(e) => {
+                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}\n{}", file_path, commit_hash2, e)));
+                            return Err(JsError::from(err))
+                        }
+                    };
+                    let context2_output = get_checked_command_output("git", &repo, &["cat-file", "-p", &format!("{}:{}", commit_hash2, file_path)]);
+                    match context2_output {
+                        Ok(context2_output) => {
+                            let stdout = decode_text(&context2_output.stdout);
+                            context2 = stdout.to_string();
+                        }
+                        Err(e) => {
+                            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}\n{}", file_path, commit_hash2, e)));
+                            return Err(JsError::from(err))
+                        }
+                    };
+                    Ok(FileDiffContext {
+                        commit_hash1: commit_hash1.to_string(),
+                        commit_hash2: commit_hash2.to_string(),
+                        file_path: file_path.to_string(),
+                        change_stat: FileLineChangeStat {
+                            addition,
+                            deletion,
+                        },
+                        lfs_pointer1: parse_lfs_pointer(&context1),
+                        lfs_pointer2: parse_lfs_pointer(&context2),
+                        context1,
+                        context2,
+                        file_status: status,
+                    })
+                }
+                _ => {
+                    Ok(FileDiffContext {
+                        commit_hash1: commit_hash1.to_string(),
+                        commit_hash2: commit_hash2.to_string(),
+                        file_path: file_path.to_string(),
+                        change_stat: FileLineChangeStat {
+                            addition: 0,
+                            deletion: 0,
+                        },
+                        lfs_pointer1: None,
+                        lfs_pointer2: None,
+                        context1: String::from(""),
+                        context2: String::from(""),
+                        file_status: status,
+                    })
+                }
+            }
+        }
+        Err(e) => {
+            println!("{}", e);
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, e));
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the file diff context between a commit and the file on the disk, context1 is the file in the commit
+ * and context2 is the file in the working tree. A file only on the disk is Added, a file removed from
+ * the disk is Deleted, and an unchanged file is Modified with no line change.
+ * Requires a work tree, fails on a bare repository
+ * @param repo: the path of the repository
+ * @param commit_hash: the hash of the commit, e.g. HEAD
+ * @param file_path: the path of the file, relative to the root of the repository
+ */
+fn diff_worktree_file_context (repo: String, commit_hash: String, file_path: String) -> Result<FileDiffContext, JsError> {
+    ensure_git_repository(&repo)?;
+    let committed = match get_checked_command_output("git", &repo, &["cat-file", "-e", &format!("{}:{}", commit_hash, file_path)]) {
+        Ok(_) => Some(get_file_blob(&repo, &commit_hash, &file_path)),
+        Err(_) => None,
+    };
+    let committed = match committed {
+        Some(Ok(content)) => Some(content),
+        Some(Err(e)) => {
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}\n{}", file_path, commit_hash, e)));
+            return Err(JsError::from(err))
+        }
+        None => None,
+    };
+    let on_disk = std::fs::read(std::path::Path::new(&repo).join(&file_path)).ok();
+    let to_text = |content: &[u8]| if is_binary(content) { String::from("Binary file") } else { decode_text(content) };
+    let (file_status, context1, context2, addition, deletion) = match (committed, on_disk) {
+        (None, None) => {
+            let err = napiError::from(io::Error::new(io::ErrorKind::NotFound, format!("File not found in the commit or the working tree:\nfile path: {}\ncommit hash: {}", file_path, commit_hash)));
+            return Err(JsError::from(err))
+        }
+        (None, Some(on_disk)) => {
+            let context2 = to_text(&on_disk);
+            let addition = if is_binary(&on_disk) { 0 } else { context2.lines().count() as i32 };
+            (FileStatusType::Added, "".to_string(), context2, addition, 0)
+        }
+        (Some(committed), None) => {
+            let context1 = to_text(&committed);
+            let deletion = if is_binary(&committed) { 0 } else { context1.lines().count() as i32 };
+            (FileStatusType::Deleted, context1, "".to_string(), 0, deletion)
+        }
+        (Some(committed), Some(on_disk)) => {
+            let mut addition = 0;
+            let mut deletion = 0;
+            if committed != on_disk {
+                let output = get_checked_command_output("git", &repo, &["diff", "--shortstat", &commit_hash, "--", &file_path]);
+                match output {
+                    Ok(output) => {
+                        let stdout = String::from_utf8_lossy(&output.stdout);
+                        if let Ok((_, insertions, deletions)) = log_shortstat_parse(stdout.trim()) {
+                            addition = insertions;
+                            deletion = deletions;
+                        }
+                    }
+                    Err(e) => {
+                        let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file diff:\nfile path: {}\ncommit hash: {}\n{}", file_path, commit_hash, e)));
+                        return Err(JsError::from(err))
+                    }
+                }
+            }
+            (FileStatusType::Modified, to_text(&committed), to_text(&on_disk), addition, deletion)
+        }
+    };
+    Ok(FileDiffContext {
+        commit_hash1: commit_hash.to_string(),
+        commit_hash2: "".to_string(),
+        file_path: file_path.to_string(),
+        change_stat: FileLineChangeStat {
+            addition,
+            deletion,
+        },
+        lfs_pointer1: parse_lfs_pointer(&context1),
+        lfs_pointer2: parse_lfs_pointer(&context2),
+        context1,
+        context2,
+        file_status,
+    })
+}
+
+#[napi]
+/**
+ * get file content in a commit
+ * @param repo repo path
+ * @param commit_hash commit hash
+ * @param file_path file path
+ */
+fn get_file_content (repo: String, commit_hash: String, file_path: String) -> Result<String, JsError> {
+    let output = get_file_blob(&repo, &commit_hash, &file_path);
+    match output {
+        Ok(content) => {
+            Ok(decode_text(&content))
+        }
+        Err(e) if e.kind() == io::ErrorKind::OutOfMemory => Err(JsError::from(CustomerGitError::FileTooLarge(file_path))),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * get the raw bytes of a file in a commit, use it for the binary files instead of `get_file_content`.
+ * The whole blob is read into the memory and copied to the Buffer, so check the size of a large file first.
+ * @param repo repo path
+ * @param commit_hash commit hash
+ * @param file_path file path
+ */
+fn get_file_content_bytes (repo: String, commit_hash: String, file_path: String) -> Result<Buffer, JsError> {
+    match get_file_blob(&repo, &commit_hash, &file_path) {
+        Ok(content) => Ok(Buffer::from(content)),
+        Err(e) if e.kind() == io::ErrorKind::OutOfMemory => Err(JsError::from(CustomerGitError::FileTooLarge(file_path))),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Check if a file exists in a commit without reading its content, false for a directory or an unknown ref
+ * @param path path to the repository
+ * @param ref_name the branch, tag or commit
+ * @param file_path file path
+ */
+fn file_exists_at (path: String, ref_name: String, file_path: String) -> bool {
+    // `cat-file -e` also succeeds for a tree, the type tells the files from the directories
+    let output = get_command_output("git", &path, &["cat-file", "-t", &format!("{}:{}", ref_name, file_path)]);
+    match output {
+        Ok(output) => output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "blob",
+        Err(_) => false,
+    }
+}
+
+/**
+ * Get the raw bytes of a file in a commit, the binary detection must be done on the raw bytes
+ */
+fn get_file_blob (repo: &str, commit_hash: &str, file_path: &str) -> io::Result<Vec<u8>> {
+    let output = get_checked_command_output("git", repo, &["cat-file", "-p", &format!("{}:{}", commit_hash, file_path)])?;
+    Ok(output.stdout)
+}
+
+#[napi]
+/**
+ * Get the type and the size of an object without reading its content, e.g. to skip the preview of a large blob
+ * @param path path to the repository
+ * @param object the object name, a hash, a ref or `<ref>:<file path>`
+ */
+fn get_object_info (path: String, object: String) -> Result<ObjectInfo, JsError> {
+    ensure_git_repository(&path)?;
+    if object.contains('\n') {
+        return Err(JsError::from(CustomerGitError::ObjectNotFound(object)))
+    }
+    let output = build_command("git", &path, &["cat-file", "--batch-check"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            // dropping the stdin closes it, so the batch ends after the object
+            child.stdin.take().unwrap().write_all(format!("{}\n", object).as_bytes())?;
+            child.wait_with_output()
+        });
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // "<hash> <type> <size>", or "<object> missing" and "<object> ambiguous"
+            let parts = stdout.trim_end().rsplitn(3, ' ').collect::<Vec<&str>>();
+            let object_type = match parts.get(1) {
+                Some(&"blob") => ObjectType::Blob,
+                Some(&"tree") => ObjectType::Tree,
+                Some(&"commit") => ObjectType::Commit,
+                Some(&"tag") => ObjectType::Tag,
+                _ => return Err(JsError::from(CustomerGitError::ObjectNotFound(object))),
+            };
+            match parts[0].parse::<i64>() {
+                Ok(size) => Ok(ObjectInfo { object_type, size }),
+                Err(_) => Err(JsError::from(CustomerGitError::ObjectNotFound(object))),
+            }
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Read many blobs through one long-lived `git cat-file --batch` process instead of a process per blob
+ */
+struct CatFileBatch {
+    child: std::process::Child,
+    stdin: std::process::ChildStdin,
+    stdout: BufReader<std::process::ChildStdout>,
+}
+
+impl CatFileBatch {
+    fn new (repo: &str) -> io::Result<CatFileBatch> {
+        let mut child = build_command("git", repo, &["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Ok(CatFileBatch { child, stdin, stdout })
+    }
+
+    /**
+     * Read the raw bytes of a file in a commit, the same as `get_file_blob`
+     */
+    fn read_blob (&mut self, commit_hash: &str, file_path: &str) -> io::Result<Vec<u8>> {
+        let object = format!("{}:{}", commit_hash, file_path);
+        if object.contains('\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("object name contains a newline: {}", object)))
+        }
+        self.stdin.write_all(format!("{}\n", object).as_bytes())?;
+        self.stdin.flush()?;
+        // "<hash> <type> <size>\n<content>\n", or "<object> missing\n"
+        let mut header = String::new();
+        if self.stdout.read_line(&mut header)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "git cat-file --batch exited"))
+        }
+        let header = header.trim_end();
+        let parts = header.split(' ').collect::<Vec<&str>>();
+        let size = match parts.as_slice() {
+            [_, _, size] => size.parse::<usize>().ok(),
+            _ => None,
+        };
+        let Some(size) = size else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("failed to read {}: {}", object, header)))
+        };
+        let max_bytes = MAX_OUTPUT_BYTES.load(Ordering::Relaxed);
+        if max_bytes != 0 && size as u64 > max_bytes {
+            // skip the content and its newline, the next blob would be read from the middle of this one
+            io::copy(&mut (&mut self.stdout).take(size as u64 + 1), &mut io::sink())?;
+            return Err(io::Error::new(io::ErrorKind::OutOfMemory, format!("{} exceeds the limit of {} bytes", object, max_bytes)))
+        }
+        let mut content = vec![0u8; size + 1];
+        self.stdout.read_exact(&mut content)?;
+        content.truncate(size);
+        Ok(content)
+    }
+}
+
+impl Drop for CatFileBatch {
+    fn drop (&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[napi]
+fn get_file_by_hash(repo: String, file_hash: String) -> Result<String, JsError> {
+    let output = get_checked_command_output("git", &repo, &["cat-file", "-p", &file_hash]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(stdout.to_string())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+fn hunk_header_regex () -> Regex {
+    Regex::new(r"^@@ -(?<old_start>\d+)(?:,(?<old_lines>\d+))? \+(?<new_start>\d+)(?:,(?<new_lines>\d+))? @@ ?(?<header>.*)$").unwrap()
+}
+
+/**
+ * Parse a hunk header like "@@ -1,4 +1,5 @@ fn main", an omitted line count is 1
+ */
+fn parse_hunk_header (re: &Regex, line: &str) -> Option<DiffHunk> {
+    let captures = re.captures(line)?;
+    let parse_number = |name: &str| {
+        captures.name(name).map(|m| m.as_str().parse::<i32>().unwrap_or(0)).unwrap_or(1)
+    };
+    Some(DiffHunk {
+        old_start: parse_number("old_start"),
+        old_lines: parse_number("old_lines"),
+        new_start: parse_number("new_start"),
+        new_lines: parse_number("new_lines"),
+        header: captures.name("header").map(|m| m.as_str().to_string()).unwrap_or_default(),
+        lines: Vec::new(),
+    })
+}
+
+/**
+ * Parse the hunks of the unified diff output, the lines before the first hunk header are ignored
+ */
+fn parse_diff_hunks (stdout: &str) -> Vec<DiffHunk> {
+    let re = hunk_header_regex();
+    let mut hunks = Vec::<DiffHunk>::new();
+    for line in stdout.lines() {
+        if let Some(hunk) = parse_hunk_header(&re, line) {
+            hunks.push(hunk);
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        let origin = match line.chars().next() {
+            Some('+') => DiffLineOrigin::Addition,
+            Some('-') => DiffLineOrigin::Deletion,
+            Some(' ') | None => DiffLineOrigin::Context,
+            // "\ No newline at end of file"
+            _ => continue,
+        };
+        hunk.lines.push(DiffLine {
+            origin,
+            content: line.get(1..).unwrap_or("").to_string(),
+        });
+    }
+    hunks
+}
+
+#[napi]
+/**
+ * Get the unified diff hunks of a file between two commits
+ * @param repo repo path
+ * @param commit_hash1 commit hash1
+ * @param commit_hash2 commit hash2
+ * @param file_path file path
+ * @param context_lines number of context lines around the changes, default is 3
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ */
+fn get_file_diff_hunks (repo: String, commit_hash1: String, commit_hash2: String, file_path: String, context_lines: Option<i32>, three_dot: Option<bool>) -> Result<Vec<DiffHunk>, JsError> {
+    let commit_hash1 = resolve_diff_base(&repo, &commit_hash1);
+    let commit_hash1 = resolve_three_dot_base(&repo, &commit_hash1, &commit_hash2, three_dot)?;
+    let unified = format!("--unified={}", context_lines.unwrap_or(3));
+    let output = get_checked_command_output("git", &repo, &["diff", &unified, &commit_hash1, &commit_hash2, "--", &file_path]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(parse_diff_hunks(&stdout))
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Parse the output of `git diff --word-diff=porcelain`, every token line is a span of the current line
+ * and a "~" line ends it
+ */
+fn parse_word_diff_hunks (stdout: &str) -> Vec<WordDiffHunk> {
+    let re = hunk_header_regex();
+    let mut hunks = Vec::<WordDiffHunk>::new();
+    let mut segments = Vec::<WordDiffSegment>::new();
+    for line in stdout.lines() {
+        if let Some(hunk) = parse_hunk_header(&re, line) {
+            hunks.push(WordDiffHunk {
+                old_start: hunk.old_start,
+                old_lines: hunk.old_lines,
+                new_start: hunk.new_start,
+                new_lines: hunk.new_lines,
+                header: hunk.header,
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        let origin = match line.chars().next() {
+            Some('~') => {
+                hunk.lines.push(std::mem::take(&mut segments));
+                continue;
+            }
+            Some('+') => DiffLineOrigin::Addition,
+            Some('-') => DiffLineOrigin::Deletion,
+            Some(' ') => DiffLineOrigin::Context,
+            // "\ No newline at end of file"
+            _ => continue,
+        };
+        let content = line.get(1..).unwrap_or("");
+        // an empty line is a context token without content
+        if !content.is_empty() {
+            segments.push(WordDiffSegment { origin, content: content.to_string() });
+        }
+    }
+    hunks
+}
+
+#[napi]
+/**
+ * Get the word diff of a file between two commits, the changed words of a line are marked
+ * instead of the whole line, to highlight them in a diff viewer
+ * @param repo repo path
+ * @param commit_hash1 commit hash1
+ * @param commit_hash2 commit hash2
+ * @param file_path file path
+ * @param context_lines number of context lines around the changes, default is 3
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ */
+fn get_file_word_diff (repo: String, commit_hash1: String, commit_hash2: String, file_path: String, context_lines: Option<i32>, three_dot: Option<bool>) -> Result<Vec<WordDiffHunk>, JsError> {
+    let commit_hash1 = resolve_diff_base(&repo, &commit_hash1);
+    let commit_hash1 = resolve_three_dot_base(&repo, &commit_hash1, &commit_hash2, three_dot)?;
+    let unified = format!("--unified={}", context_lines.unwrap_or(3));
+    let output = get_checked_command_output("git", &repo, &["diff", "--word-diff=porcelain", &unified, &commit_hash1, &commit_hash2, "--", &file_path]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(parse_word_diff_hunks(&stdout))
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Parse a Git LFS pointer file like "version https://git-lfs.github.com/spec/v1\noid sha256:<hash>\nsize 12345\n",
+ * a pointer is a small text file, so a large content is never a pointer
+ */
+fn parse_lfs_pointer (content: &str) -> Option<LfsPointer> {
+    if content.len() > 1024 || !content.starts_with("version https://git-lfs.github.com/spec/") {
+        return None
+    }
+    let mut oid = None;
+    let mut size = None;
+    for line in content.lines() {
+        match line.split_once(' ') {
+            Some(("oid", value)) => oid = Some(value.trim_start_matches("sha256:").to_string()),
+            Some(("size", value)) => size = value.parse::<i64>().ok(),
+            _ => {}
+        }
+    }
+    Some(LfsPointer { oid: oid?, size: size? })
+}
+
+#[napi]
+/**
+ * Check if a file in a commit is a Git LFS pointer, use it to tell an LFS file from the content of `get_file_content`
+ * @param repo repo path
+ * @param commit_hash commit hash
+ * @param file_path file path
+ * @return the oid and the size of the LFS object, or null if the file is not a pointer
+ */
+fn get_lfs_pointer (repo: String, commit_hash: String, file_path: String) -> Result<Option<LfsPointer>, JsError> {
+    match get_file_blob(&repo, &commit_hash, &file_path) {
+        Ok(content) => Ok(parse_lfs_pointer(&String::from_utf8_lossy(&content))),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+fn is_binary(content: &[u8]) -> bool {
+    content_kind(content) == ContentKind::Binary
+}
+
+#[napi]
+/**
+ * Detect whether the content of a file is UTF-8 text, UTF-16 text or binary
+ * @param content the raw bytes of the file
+ */
+fn detect_content_kind (content: Buffer) -> ContentKind {
+    content_kind(&content)
+}
+
+fn content_kind (content: &[u8]) -> ContentKind {
+    if content.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return ContentKind::Utf8Text
+    }
+    if detect_utf16(content).is_some() {
+        return ContentKind::Utf16Text
+    }
+    // 判断前8000个字节中是否包含0，以及控制字符的比例
+    let sample = &content[..content.len().min(8000)];
+    let mut control = 0;
+    for c in sample.iter().copied() {
+        if c == 0 {
+            return ContentKind::Binary
+        }
+        if c < 0x20 && !matches!(c, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B | 0x08) {
+            control += 1;
+        }
+    }
+    if control * 10 > sample.len() {
+        return ContentKind::Binary
+    }
+    ContentKind::Utf8Text
+}
+
+#[napi]
+/**
+ * get difference file diff statistic between two commits
+ * @param repo repo path
+ * @param commit_hash1 commit hash1
+ * @param commit_hash2 commit hash2
+ * @param file_path1 file path in commit1
+ * @param file_path2 file path in commit2
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ * @returns FileDiffContext
+ */
+fn get_diff_file_stat_between_commit(repo: String, commit_hash1: String, commit_hash2: String, file_path1: String, file_path2: String, three_dot: Option<bool>)-> Result<FileLineChangeStat, JsError> {
+    let commit_hash1 = resolve_three_dot_base(&repo, &commit_hash1, &commit_hash2, three_dot)?;
+    let commit_range = build_diff_range(&commit_hash1, &commit_hash2);
+    let entries = get_diff_numstat(&repo, &[&commit_range, "--", &file_path1, &file_path2])?;
+    Ok(sum_numstat(&entries))
+}
+
+#[napi]
+/**
+ * get difference files diff between two commits
+ * @param repo repo path
+ * @param commit_hash1 commit hash1
+ * @param commit_hash2 commit hash2
+ * @param rename_options the rename and copy detection, see `RenameOptions`
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ * @param pathspec only the files matching the pathspecs, e.g. "src/" or "*.rs", the whole repository if it is not given or empty
+ * @returns FileDiffContext
+ */
+fn get_files_diff_context (repo: String, commit_hash1: String, commit_hash2: String, rename_options: Option<RenameOptions>, three_dot: Option<bool>, pathspec: Option<Vec<String>>) -> Result<Vec<FileDiffContext>, JsError> {
+    get_files_diff_context_with_progress(repo, commit_hash1, commit_hash2, rename_options, three_dot, pathspec, |_| {})
+}
+
+/**
+ * Get the files diff like `get_files_diff_context`, on_progress is called before reading every file
+ * with its 1-based index and the number of the changed files
+ */
+fn get_files_diff_context_with_progress (repo: String, commit_hash1: String, commit_hash2: String, rename_options: Option<RenameOptions>, three_dot: Option<bool>, pathspec: Option<Vec<String>>, on_progress: impl FnMut(DiffProgress)) -> Result<Vec<FileDiffContext>, JsError> {
+    let commit_hash1 = resolve_diff_base(&repo, &commit_hash1);
+    // the old side of the files is read from the merge base
+    let commit_hash1 = resolve_three_dot_base(&repo, &commit_hash1, &commit_hash2, three_dot)?;
+    let files_status = get_files_status_between_commit(repo.to_string(), commit_hash1.to_string(), commit_hash2.to_string(), rename_options.clone(), None, pathspec.clone())?;
+    let mut batch = match CatFileBatch::new(&repo) {
+        Ok(batch) => batch,
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    let mut diff_args = rename_option_args(&rename_options)?;
+    diff_args.extend(pathspec_args(&pathspec));
+    read_files_diff_context(&repo, &mut batch, &commit_hash1, &commit_hash2, &files_status, &diff_args, on_progress)
+}
+
+/**
+ * The error of a blob which can't be read for the diff, a blob over the limit of `set_max_output_bytes` is `CustomerGitError::FileTooLarge`
+ */
+fn blob_read_error (e: io::Error, file_path: &str, commit_hash: &str) -> JsError {
+    if e.kind() == io::ErrorKind::OutOfMemory {
+        return JsError::from(CustomerGitError::FileTooLarge(file_path.to_string()))
+    }
+    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to get file content:\nfile path: {}\ncommit hash: {}\n{}", file_path, commit_hash, e)));
+    JsError::from(err)
+}
+
+/**
+ * Read the contents and the changed lines of the changed files between two commits,
+ * the batch reader can be shared by the diffs of many commits.
+ * diff_args are the rename options and the pathspec `files_status` is listed with
+ */
+fn read_files_diff_context (repo: &str, batch: &mut CatFileBatch, commit_hash1: &str, commit_hash2: &str, files_status: &[FileStatus], diff_args: &[String], mut on_progress: impl FnMut(DiffProgress)) -> Result<Vec<FileDiffContext>, JsError> {
+    // the changed lines of all the modified, renamed and copied files in one git call, keyed by the new path
+    let mut numstat = HashMap::<String, NumstatEntry>::new();
+    if files_status.iter().any(|file_status| matches!(file_status.status, FileStatusType::Modified | FileStatusType::Renamed | FileStatusType::Copied)) {
+        let range = build_diff_range(commit_hash1, commit_hash2);
+        let mut args = vec![range.as_str()];
+        args.extend(diff_args.iter().map(|arg| arg.as_str()));
+        let entries = get_diff_numstat(repo, &args)?;
+        numstat.extend(entries.into_iter().map(|entry| (entry.path.to_string(), entry)));
+    }
+    let mut result = Vec::new();
+    let total = files_status.len() as i32;
+    for (index, file_status) in files_status.iter().enumerate() {
+        on_progress(DiffProgress {
+            current: index as i32 + 1,
+            total,
+            file_path: file_status.path.to_string(),
+        });
+        // println!("{} {}", file_status.path, file_status.status);
+        let mut file_content1 = String::from("");
+        let mut file_content2 = String::from("");
+        let mut addition = 0;
+        let mut deletion = 0;
+        match file_status.status {
+            FileStatusType::Added => {
+                let content = batch.read_blob(commit_hash2, &file_status.path);
+                match content {
+                    Ok(content) => {
+                        if is_binary(&content) {
+                            file_content1 = String::from("Binary file");
+                            file_content2 = String::from("Binary file");
+                        } else {
+                            file_content2 = decode_text(&content);
+                            addition = file_content2.lines().count() as i32;
+                        }
+                    }
+                    Err(e) => {
+                        return Err(blob_read_error(e, &file_status.path, commit_hash2))
+                    }
+                }
+            }
+            FileStatusType::Deleted => {
+                let content = batch.read_blob(commit_hash1, &file_status.path);
+                match content {
+                    Ok(content) => {
+                        if is_binary(&content) {
+                            file_content1 = String::from("Binary file");
+                        } else {
+                            file_content1 = decode_text(&content);
+                            deletion = file_content1.lines().count() as i32;
+                        }
+                    }
+                    Err(e) => {
+                        return Err(blob_read_error(e, &file_status.path, commit_hash1))
+                    }
+                }
+                file_content2 = String::from("File deleted");
+            }
+            FileStatusType::Modified => {
+                let content1 = batch.read_blob(commit_hash1, &file_status.path);
+                let content2 = batch.read_blob(commit_hash2, &file_status.path);
+                match (content1, content2) {
+                    (Ok(content1), Ok(content2)) => {
+                        if is_binary(&content1) && is_binary(&content2) {
+                            file_content1 = String::from("Binary file");
+                            file_content2 = String::from("Binary file");
+                        } else if is_binary(&content1) {
+                            file_content1 = String::from("Binary file");
+                            file_content2 = decode_text(&content2);
+                        }else if is_binary(&content2) {
+                            file_content1 = decode_text(&content1);
+                            file_content2 = String::from("Binary file");
+                        } else {
+                            file_content1 = decode_text(&content1);
+                            file_content2 = decode_text(&content2);
+                        }
+                    },
+                    (Err(e), _) | (_, Err(e)) => {
+                        return Err(blob_read_error(e, &file_status.path, &format!("{}..{}", commit_hash1, commit_hash2)))
+                    }
+                }
+                if let Some(entry) = numstat.get(&file_status.path) {
+                    addition = entry.additions;
+                    deletion = entry.deletions;
+                }
+            }
+            FileStatusType::Renamed | FileStatusType::Copied => {
+                let reg = Regex::new(r"\s*=>\s*").unwrap();
+                let names = reg.split(&file_status.message).collect::<Vec<&str>>();
+                let name1 = names[0];
+                let name2 = names[1];
+                let content1 = batch.read_blob(commit_hash1, name1);
+                let content2 = batch.read_blob(commit_hash2, name2);
+                match (content1, content2) {
+                    (Ok(content1), Ok(content2)) => {
+                        if is_binary(&content1) && is_binary(&content2) {
+                            file_content1 = String::from("Binary file");
+                            file_content2 = String::from("Binary file");
+                        } else if is_binary(&content1) {
+                            file_content1 = String::from("Binary file");
+                            file_content2 = decode_text(&content2);
+                        }else if is_binary(&content2) {
+                            file_content1 = decode_text(&content1);
+                            file_content2 = String::from("Binary file");
+                        } else {
+                            file_content1 = decode_text(&content1);
+                            file_content2 = decode_text(&content2);
+                        }
+                    }
+                    (Err(e), _) | (_, Err(e)) => {
+                        return Err(blob_read_error(e, &file_status.path, &format!("{}..{}", commit_hash1, commit_hash2)))
+                    }
+                }
+                if let Some(entry) = numstat.get(name2) {
+                    addition = entry.additions;
+                    deletion = entry.deletions;
+                }
+            }
+            _ => {}
+        };
+        result.push(FileDiffContext {
+            commit_hash1: commit_hash1.to_string(),
+            commit_hash2: commit_hash2.to_string(),
+            file_path: file_status.path.to_string(),
+            change_stat: FileLineChangeStat {
+                addition: addition,
+                deletion: deletion
+            },
+            lfs_pointer1: parse_lfs_pointer(&file_content1),
+            lfs_pointer2: parse_lfs_pointer(&file_content2),
+            context1: file_content1,
+            context2: file_content2,
+            file_status: file_status.status
+        })
+    }
+    Ok(result)
+}
+
+/**
+ * Resolve the old side of a diff, the parent of a root commit (e.g. `<hash>^`) doesn't exist,
+ * the empty tree is used instead so all the files of the root commit are added
+ */
+fn resolve_diff_base (path: &str, rev: &str) -> String {
+    let is_valid_commit = |rev: &str| {
+        get_command_output("git", path, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    };
+    for suffix in ["^1", "~1", "^", "~"] {
+        if let Some(hash) = rev.strip_suffix(suffix) {
+            if !is_valid_commit(rev) && is_valid_commit(hash) {
+                return EMPTY_TREE_HASH.to_string()
+            }
+            break;
+        }
+    }
+    rev.to_string()
+}
+
+/**
+ * Get the first parent of a commit, or the empty tree if it is a root commit, so the commit can always be diffed against it
+ */
+fn get_first_parent_or_empty_tree (path: &str, hash: &str) -> Result<String, JsError> {
+    // the first line is the commit followed by its parents
+    let output = get_checked_command_output("git", path, &["rev-list", "--parents", "-n", "1", hash, "--"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let parent = stdout.split_whitespace().nth(1).unwrap_or(EMPTY_TREE_HASH);
+            Ok(parent.to_string())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the files diff introduced by a commit, it is diffed against its first parent,
+ * a root commit is diffed against the empty tree so all its files are added
+ * @param path path to the repository
+ * @param hash commit hash
+ */
+fn get_commit_changes (path: String, hash: String) -> Result<Vec<FileDiffContext>, JsError> {
+    let parent = get_first_parent_or_empty_tree(&path, &hash)?;
+    get_files_diff_context(path, parent, hash, None, None, None)
+}
+
+#[napi]
+fn get_commit_within_branches (repo: String, commit_hash: String) -> Result<Vec<String>, JsError> {
+    let formawt = r"--format=%(refname:short)";
+    let output = get_checked_command_output("git", &repo, &["branch", "--contains", &commit_hash, &formawt]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let branches = stdout.split("\n").collect::<Vec<&str>>();
+            let mut result = Vec::<String>::new();
+            for branch in branches {
+                if branch.len() > 0 {
+                    result.push(branch.to_string());
+                }
+            }
+            Ok(result)
+        },
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the number of commits of a branch, it is much cheaper than counting the commit log
+ * @param path path to the repository
+ * @param branch branch name
+ * @param file_path only count the commits touching this path
+ * @return 0 when the repository has no commit yet
+ */
+fn get_branch_commit_count (path: String, branch: String, file_path: Option<String>) -> Result<i32, JsError> {
+    let mut args = vec!["rev-list", "--count", &branch];
+    if let Some(file_path) = file_path.as_ref() {
+        args.push("--");
+        args.push(file_path);
+    }
+    let output = get_checked_command_output("git", &path, &args);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let count = stdout.trim().parse::<i32>().unwrap_or(0);
+            Ok(count)
+        }
+        Err(e) => {
+            // an empty repository has no ref to count from
+            let has_commit = get_command_output("git", &path, &["rev-list", "-n", "1", "--all"])
+                .map(|output| !output.stdout.is_empty())
+                .unwrap_or(true);
+            if !has_commit {
+                return Ok(0)
+            }
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the children of a commit, i.e. the commits whose parent is the given commit
+ * @param path path to the repository
+ * @param commit_hash commit hash
+ * @param within_ref only scan the history reachable from this ref, scan all refs if it is not given
+ */
+fn get_commit_children (path: String, commit_hash: String, within_ref: Option<String>) -> Result<Vec<String>, JsError> {
+    let full_hash = get_branch_last_commit(path.to_string(), format!("{}^{{commit}}", commit_hash))?;
+    // the children are never ancestors of the commit, the walk stops at it
+    let exclude = format!("^{}", full_hash);
+    let mut args = vec!["rev-list", "--parents"];
+    match within_ref.as_deref() {
+        Some(within_ref) => args.extend(["--end-of-options", &exclude, within_ref]),
+        None => args.extend(["--all", &exclude]),
+    }
+    let output = get_checked_command_output("git", &path, &args);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // every line is "<commit> <parent1> <parent2> ..."
+            let children = stdout.lines().filter(|line| line.contains(full_hash.as_str())).filter_map(|line| {
+                let mut hashes = line.split_ascii_whitespace();
+                let child = hashes.next()?;
+                hashes.any(|parent| parent == full_hash).then(|| child.to_string())
+            }).collect();
+            Ok(children)
+        },
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+
+/**
+ * Parse the output of `git blame --line-porcelain`, every line carries the full commit headers
+ */
+fn parse_blame_porcelain (stdout: &str) -> Vec<BlameLine> {
+    let mut blame_lines = Vec::<BlameLine>::new();
+    let mut commit_hash = String::from("");
+    let mut line_number = 0;
+    let mut author = String::from("");
+    let mut email = String::from("");
+    let mut timestamp = String::from("");
+    let mut is_header = true;
+    for line in stdout.lines() {
+        if let Some(content) = line.strip_prefix("\t") {
+            blame_lines.push(BlameLine {
+                line_number,
+                content: content.to_string(),
+                author: author.to_string(),
+                email: email.to_string(),
+                timestamp: timestamp.to_string(),
+                commit_hash: commit_hash.to_string(),
+            });
+            is_header = true;
+            continue;
+        }
+        if is_header {
+            // "<hash> <line number in the original file> <line number in the final file> [<lines in this group>]"
+            let keys = line.split_ascii_whitespace().collect::<Vec<&str>>();
+            if keys.len() >= 3 {
+                commit_hash = keys[0].to_string();
+                line_number = keys[2].parse::<i32>().unwrap_or(0);
+            }
+            is_header = false;
+        } else if let Some(author_name) = line.strip_prefix("author ") {
+            author = author_name.to_string();
+        } else if let Some(author_mail) = line.strip_prefix("author-mail ") {
+            email = author_mail.trim_start_matches("<").trim_end_matches(">").to_string();
+        } else if let Some(author_time) = line.strip_prefix("author-time ") {
+            timestamp = author_time.to_string();
+        }
+    }
+    blame_lines
+}
+
+fn blame_file (path: &str, commit_or_branch: &str, file_path: &str) -> Result<Vec<BlameLine>, String> {
+    let output = get_checked_command_output("git", path, &["blame", "--line-porcelain", commit_or_branch, "--", file_path]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(parse_blame_porcelain(&stdout))
+        }
+        Err(e) => {
+            Err(e.to_string())
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get who last changed every line of a file
+ * @param path path to the repository
+ * @param commit_or_branch the revision to blame
+ * @param file_path the path of the file
+ */
+fn get_blame (path: String, commit_or_branch: String, file_path: String) -> Result<Vec<BlameLine>, JsError> {
+    match blame_file(&path, &commit_or_branch, &file_path) {
+        Ok(blame_lines) => Ok(blame_lines),
+        Err(e) => {
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("Failed to blame file, it may not exist in this revision:\nfile path: {}\nrevision: {}\n{}", file_path, commit_or_branch, e)));
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the bus factor of every file in a branch, computed from the blame ownership.
+ * The files are blamed in a batch of parallel git processes against the commit the branch resolves to
+ * @param path path to the repository
+ * @param branch branch name
+ * @param threshold a file is at risk when its top author owns more than this percent of the lines, default is 80
+ * @param max_authors a file is at risk only when at most this many authors have lines in it, default is 2
+ */
+fn get_bus_factor (path: String, branch: String, threshold: Option<f64>, max_authors: Option<i32>) -> Result<Vec<FileBusFactor>, JsError> {
+    let threshold = threshold.unwrap_or(80.0);
+    let max_authors = max_authors.unwrap_or(2);
+    // blame every file against the same commit even if the branch moves meanwhile
+    let commit = resolve_ref(path.clone(), branch)?;
+    let output = get_checked_command_output("git", &path, &["ls-tree", "-r", "-z", &commit]);
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // <mode> SP <type> SP <object> TAB <file>, submodules are commits and have nothing to blame
+    let files = stdout.split('\0')
+        .filter_map(|entry| entry.split_once('\t'))
+        .filter(|(info, _)| info.split(' ').nth(1) == Some("blob"))
+        .map(|(_, file_path)| file_path.to_string())
+        .collect::<Vec<String>>();
+    let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(files.len().max(1));
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let blames = std::thread::scope(|scope| {
+        let handles = (0..workers).map(|_| scope.spawn(|| {
+            let mut blames = Vec::<(usize, Result<Vec<BlameLine>, String>)>::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                if index >= files.len() {
+                    break;
+                }
+                blames.push((index, blame_file(&path, &commit, &files[index])));
+            }
+            blames
+        })).collect::<Vec<_>>();
+        let mut blames = handles.into_iter().flat_map(|handle| handle.join().unwrap_or_default()).collect::<Vec<_>>();
+        blames.sort_by_key(|(index, _)| *index);
+        blames
+    });
+    let mut result = Vec::<FileBusFactor>::new();
+    for (index, blame) in blames {
+        let file_path = &files[index];
+        // a file git can't blame has no ownership, it shouldn't fail the whole tree
+        let line_authors = match blame {
+            Ok(blame_lines) => blame_lines.into_iter().map(|line| Author {
+                name: line.author,
+                email: line.email,
+            }).collect::<Vec<Author>>(),
+            Err(_) => continue,
+        };
+        if line_authors.is_empty() {
+            continue;
+        }
+        let mut line_count = HashMap::<Author, i32>::new();
+        for author in line_authors.iter() {
+            *line_count.entry(author.clone()).or_insert(0) += 1;
+        }
+        let (top_author, top_count) = line_count.iter().max_by_key(|(_, count)| **count).unwrap();
+        let top_author_percent = *top_count as f64 * 100.0 / line_authors.len() as f64;
+        let author_count = line_count.len() as i32;
+        result.push(FileBusFactor {
+            path: file_path.to_string(),
+            top_author: top_author.clone(),
+            top_author_percent,
+            author_count,
+            at_risk: top_author_percent > threshold && author_count <= max_authors,
+        });
+    }
+    Ok(result)
+}
+
+/**
+ * Parse the commit summaries from the output of git log with the format of `commit_summary_format`
+ */
+fn parse_commit_summaries (stdout: &str) -> Vec<CommitSummary> {
+    let mut fields = stdout.split('\0').collect::<Vec<&str>>();
+    // the part after the last NUL is not a field
+    fields.pop();
+    fields.chunks_exact(COMMIT_SUMMARY_FIELDS).map(parse_commit_summary).collect()
+}
+
+/**
+ * Read a field terminated by NUL from the output of git with `-z`, None at the end of the output
+ */
+fn read_nul_field (reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut buf = Vec::<u8>::new();
+    if reader.read_until(b'\0', &mut buf)? == 0 {
+        return Ok(None)
+    }
+    if buf.last() == Some(&0) {
+        buf.pop();
+    }
+    Ok(Some(decode_log_output(&buf)))
+}
+
+/**
+ * Parse the fields of one commit printed with the format of `commit_summary_format`
+ */
+fn parse_commit_summary (keys: &[&str]) -> CommitSummary {
+    CommitSummary {
+        // the commits are separated by "\n"
+        hash: keys[0].trim_start_matches('\n').to_string(),
+        short_hash: keys[1].to_string(),
+        parents: keys[2].split_ascii_whitespace().map(|p| p.to_string()).collect(),
+        author: Author {
+            name: keys[3].to_string(),
+            email: keys[4].to_string(),
+        },
+        time: keys[5].to_string(),
+        title: keys[6].to_string(),
+    }
+}
+
+// the number of the fields of `commit_summary_format`
+const COMMIT_SUMMARY_FIELDS: usize = 7;
+
+fn commit_summary_format () -> String {
+    "--format=%H%x00%h%x00%P%x00%an%x00%ae%x00%at%x00%s%x00".to_string()
+}
+
+#[napi]
+/**
+ * Get the files diff introduced by every commit in base..tip, in order from the oldest commit.
+ * Merge commits are diffed against their first parent.
+ * @param path path to the repository
+ * @param base the base commit, it is not included in the result
+ * @param tip the tip commit
+ */
+fn get_range_commit_patches (path: String, base: String, tip: String) -> Result<Vec<CommitPatch>, JsError> {
+    let format = commit_summary_format();
+    let output = get_checked_command_output("git", &path, &["log", "--reverse", &format, &format!("{}..{}", base, tip)]);
+    match output {
+        Ok(output) => {
+            let stdout = decode_log_output(&output.stdout);
+            let mut result = Vec::<CommitPatch>::new();
+            // one batch reader for the files of all the commits
+            let mut batch = match CatFileBatch::new(&path) {
+                Ok(batch) => batch,
+                Err(e) => {
+                    let err = napiError::from(e);
+                    return Err(JsError::from(err))
+                }
+            };
+            for commit in parse_commit_summaries(&stdout) {
+                let parent = match commit.parents.first() {
+                    Some(parent) => parent.to_string(),
+                    None => EMPTY_TREE_HASH.to_string(),
+                };
+                let files_status = get_files_status_between_commit(path.to_string(), parent.to_string(), commit.hash.to_string(), None, None, None)?;
+                let files = read_files_diff_context(&path, &mut batch, &parent, &commit.hash, &files_status, &[], |_| {})?;
+                result.push(CommitPatch {
+                    commit,
+                    files,
+                });
+            }
+            Ok(result)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get what changed on a branch since a commit seen before, to refresh a repository without reading it all again
+ * @param path path to the repository
+ * @param branch the branch to check, e.g. HEAD
+ * @param last_hash the head of the branch seen before, a full refresh is needed if it is ""
+ */
+fn get_updates_since (path: String, branch: String, last_hash: String) -> Result<BranchUpdates, JsError> {
+    let head = resolve_ref(path.clone(), branch)?;
+    let full_refresh = |head: String, force_updated: bool| Ok(BranchUpdates { head, commits: Vec::new(), force_updated, needs_full_refresh: true });
+    // the commit may be gone after a gc, or come from another clone
+    if last_hash.is_empty() || resolve_ref(path.clone(), last_hash.clone()).is_err() {
+        return full_refresh(head, false)
+    }
+    // exit code 0 means last_hash is an ancestor of the head, 1 means not, any other code is a failure of git
+    let output = get_command_output("git", &path, &["merge-base", "--is-ancestor", &last_hash, &head]);
+    match output {
+        Ok(output) => match output.status.code() {
+            Some(0) => {}
+            Some(1) => return full_refresh(head, true),
+            _ => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git merge-base --is-ancestor` exited with {}: {}", output.status, stderr.trim())));
+                return Err(JsError::from(err))
+            }
+        },
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    }
+    let format = commit_summary_format();
+    let output = get_checked_command_output("git", &path, &["log", &format, &format!("{}..{}", last_hash, head), "--"]);
+    match output {
+        Ok(output) => {
+            let stdout = decode_log_output(&output.stdout);
+            Ok(BranchUpdates {
+                head,
+                commits: parse_commit_summaries(&stdout),
+                force_updated: false,
+                needs_full_refresh: false,
+            })
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Classify the paths which are probably vendored or generated, so they can be excluded from the statistic.
+ * The heuristics can be overridden by the linguist-vendored and linguist-generated markers of .gitattributes.
+ * @param path_list paths relative to the repository root
+ * @param gitattributes the content of the .gitattributes file, it can be empty
+ */
+fn classify_paths (path_list: Vec<String>, gitattributes: Option<String>) -> Vec<PathClassification> {
+    let attributes = parse_linguist_attributes(&gitattributes.unwrap_or_default());
+    path_list.iter().map(|path| {
+        let mut vendored = is_vendored_path(path);
+        let mut generated = is_generated_path(path);
+        // the later lines of .gitattributes override the former
+        for (regex, name, value) in attributes.iter() {
+            if regex.is_match(path) {
+                if name == "linguist-vendored" {
+                    vendored = *value;
+                } else {
+                    generated = *value;
+                }
+            }
+        }
+        PathClassification {
+            path: path.to_string(),
+            vendored,
+            generated,
+        }
+    }).collect()
+}
+
+#[napi]
+/**
+ * Get the last commit touching each of the paths in one walk of the history,
+ * the walk stops as soon as all the paths are found
+ * @param path path to the repository
+ * @param branch branch name
+ * @param file_paths the paths of the files or the directories
+ * @return the map from the path to its last commit, the paths never touched are absent
+ */
+fn get_files_last_commit (path: String, branch: String, file_paths: Vec<String>) -> Result<HashMap<String, CommitSummary>, JsError> {
+    let mut result = HashMap::<String, CommitSummary>::new();
+    if file_paths.is_empty() {
+        return Ok(result)
+    }
+    let format = commit_summary_format().replacen("--format=", &format!("--format={}", COMMIT_INETRVAL), 1);
+    // -z keeps the paths unquoted, every commit is "<COMMIT_INETRVAL><field>\0...\0\0" followed by "\n<status>\0<path>\0...",
+    // the statuses tell the paths from the next commit
+    let mut args = vec!["log", "--name-status", "-z", &format, &branch, "--"];
+    args.extend(file_paths.iter().map(|file_path| file_path.as_str()));
+    let child = build_command("git", &path, &args).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    let stderr_reader = spawn_stderr_reader(&mut child, u64::MAX);
+    let mut reader = BufReader::new(child.stdout.take().unwrap());
+    let mut current_commit: Option<CommitSummary> = None;
+    let mut walk = || -> io::Result<()> {
+        while result.len() < file_paths.len() {
+            let Some(field) = read_nul_field(&mut reader)? else {
+                break;
+            };
+            if let Some(hash) = field.strip_prefix(COMMIT_INETRVAL) {
+                let mut fields = vec![hash.to_string()];
+                for _ in 1..COMMIT_SUMMARY_FIELDS {
+                    fields.push(read_nul_field(&mut reader)?.unwrap_or_default());
+                }
+                current_commit = Some(parse_commit_summary(&fields.iter().map(|field| field.as_str()).collect::<Vec<&str>>()));
+                continue;
+            }
+            // the empty field closing the summary
+            let Some(count) = name_status_path_count(field.trim_start_matches('\n')) else {
+                continue;
+            };
+            let mut line = String::new();
+            for _ in 0..count {
+                // the new path of a rename
+                line = read_nul_field(&mut reader)?.unwrap_or_default();
+            }
+            let Some(commit) = current_commit.as_ref() else {
+                continue;
+            };
+            for file_path in file_paths.iter() {
+                let dir = file_path.trim_end_matches('/').to_string() + "/";
+                if !result.contains_key(file_path) && (line == *file_path || line.starts_with(&dir)) {
+                    result.insert(file_path.to_string(), commit.clone());
+                }
+            }
+        }
+        Ok(())
+    };
+    if let Err(e) = walk() {
+        let _ = child.kill();
+        let _ = child.wait();
+        let err = napiError::from(e);
+        return Err(JsError::from(err))
+    }
+    let all_found = result.len() == file_paths.len();
+    if all_found {
+        // no need to walk the rest of the history
+        let _ = child.kill();
+    }
+    let status = child.wait();
+    let stderr = stderr_reader.join().unwrap_or_default();
+    match status {
+        // the killed walk has no meaningful exit status, the warnings of a successful walk are ignored
+        Ok(status) if all_found || status.success() => Ok(result),
+        Ok(status) => {
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git log` exited with {}: {}", status, String::from_utf8_lossy(&stderr).trim())));
+            Err(JsError::from(err))
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the stash list of a repository, the newest stash first
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @return an empty list if there is no stash
+ */
+fn get_stash_list (path: String) -> Result<Vec<StashEntry>, JsError> {
+    let format = format!("--format=%gd{}%H{}%ct{}%gs", PARAM_INTERVAL, PARAM_INTERVAL, PARAM_INTERVAL);
+    let output = get_checked_command_output("git", &path, &["stash", "list", &format]);
+    match output {
+        Ok(output) => {
+            let stdout = decode_log_output(&output.stdout);
+            Ok(stdout.lines().filter(|line| !line.trim().is_empty()).filter_map(|line| parse_stash_line(line)).collect())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Parse a line of `git stash list --format=%gd<P>%H<P>%ct<P>%gs`,
+ * the subject is like "WIP on main: 1a2b3c4 title" or "On main: message"
+ */
+fn parse_stash_line (line: &str) -> Option<StashEntry> {
+    let parts = line.splitn(4, PARAM_INTERVAL).collect::<Vec<&str>>();
+    if parts.len() < 4 {
+        return None
+    }
+    let index = parts[0].trim().strip_prefix("stash@{")?.strip_suffix("}")?.parse::<i32>().ok()?;
+    let subject = parts[3];
+    let rest = subject.strip_prefix("WIP on ").or(subject.strip_prefix("On ")).unwrap_or(subject);
+    let (branch, message) = match rest.split_once(": ") {
+        Some((branch, message)) => (branch.to_string(), message.to_string()),
+        None => ("".to_string(), subject.to_string()),
+    };
+    Some(StashEntry {
+        index,
+        branch,
+        message,
+        hash: parts[1].to_string(),
+        timestamp: parts[2].to_string(),
+    })
+}
+
+#[napi]
+/**
+ * Get the reflog of a ref, the newest entry first
+ * @param path path to the repository
+ * @param ref_name the ref, HEAD if it is ""
+ * @param limit max number of entries, all the entries if it is not positive
+ */
+fn get_reflog (path: String, ref_name: String, limit: i32) -> Result<Vec<ReflogEntry>, JsError> {
+    let ref_name = if ref_name.is_empty() { "HEAD".to_string() } else { ref_name };
+    // with --date=unix %gd is the time of the entry like HEAD@{1700000000}, the index is its position
+    let format = format!("--format=%H{}%gd{}%gs", PARAM_INTERVAL, PARAM_INTERVAL);
+    let mut args = vec!["reflog".to_string(), "show".to_string(), "--date=unix".to_string(), format];
+    if limit > 0 {
+        args.push(format!("--max-count={}", limit));
+    }
+    args.extend([ref_name.to_string(), "--".to_string()]);
+    let output = get_checked_command_output("git", &path, &args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>());
+    match output {
+        Ok(output) => {
+            let stdout = decode_log_output(&output.stdout);
+            let lines = stdout.lines().filter(|line| !line.trim().is_empty());
+            Ok(lines.enumerate().filter_map(|(index, line)| parse_reflog_line(line, index)).collect())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Parse a line of `git reflog show --date=unix --format=%H<P>%gd<P>%gs`,
+ * the subject is like "checkout: moving from main to feature" or "commit (amend): title"
+ */
+fn parse_reflog_line (line: &str, index: usize) -> Option<ReflogEntry> {
+    let parts = line.splitn(3, PARAM_INTERVAL).collect::<Vec<&str>>();
+    if parts.len() < 3 {
+        return None
+    }
+    let (name, time) = parts[1].trim().split_once("@{")?;
+    let (action, message) = match parts[2].split_once(": ") {
+        Some((action, message)) => (action.to_string(), message.to_string()),
+        None => (parts[2].to_string(), "".to_string()),
+    };
+    Some(ReflogEntry {
+        hash: parts[0].to_string(),
+        selector: format!("{}@{{{}}}", name, index),
+        action,
+        message,
+        timestamp: time.trim_end_matches('}').to_string(),
+    })
+}
+
+#[napi]
+/**
+ * Get the status of the files changed in a stash, relative to the commit the stash was based on
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param index the n of `stash@{n}`
+ */
+fn get_stash_diff (path: String, index: i32) -> Result<Vec<FileStatus>, JsError> {
+    let stash = format!("stash@{{{}}}", index);
+    let output = get_checked_command_output("git", &path, &["stash", "show", "--name-status", &stash]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(stdout.lines().filter(|line| !line.trim().is_empty()).map(|line| parse_name_status_line(line)).collect())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Save the local changes to a new stash and clean the working tree with `git stash push`
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param message the message of the stash, git's default "WIP on <branch>: ..." if it is ""
+ * @param include_untracked also stash the untracked files and remove them (`--include-untracked`)
+ * @return the new stash, or null if there is no local change to save
+ */
+fn stash_save (path: String, message: String, include_untracked: bool) -> Result<Option<StashEntry>, JsError> {
+    ensure_git_repository(&path)?;
+    let before = resolve_ref(path.clone(), "refs/stash".to_string()).unwrap_or_default();
+    let mut args = vec!["stash", "push"];
+    if include_untracked {
+        args.push("--include-untracked");
+    }
+    if !message.is_empty() {
+        args.extend(["-m", message.as_str()]);
+    }
+    if let Err(e) = get_checked_command_output("git", &path, &args) {
+        let err = napiError::from(e);
+        return Err(JsError::from(err))
+    }
+    // git succeeds without creating a stash when there is nothing to save
+    let after = resolve_ref(path.clone(), "refs/stash".to_string()).unwrap_or_default();
+    if after.is_empty() || after == before {
+        return Ok(None)
+    }
+    Ok(get_stash_list(path)?.into_iter().next())
+}
+
+#[napi]
+/**
+ * Apply the changes of a stash to the working tree and keep the stash
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param index the n of `stash@{n}`
+ * @return `Applied`, or `Conflict` with the conflicted paths, fails with the "Cancelled" code and the files
+ * if the local changes would be overwritten
+ */
+fn stash_apply (path: String, index: i32) -> Result<StashApplyResult, JsError> {
+    ensure_git_repository(&path)?;
+    apply_stash(&path, index)
+}
+
+#[napi]
+/**
+ * Apply the changes of a stash to the working tree and drop it, the stash is only dropped when it applies cleanly,
+ * after a conflict it is kept, so it can be dropped with `stash_drop` once the conflicts are resolved
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param index the n of `stash@{n}`
+ * @return the same as `stash_apply`
+ */
+fn stash_pop (path: String, index: i32) -> Result<StashApplyResult, JsError> {
+    ensure_git_repository(&path)?;
+    let result = apply_stash(&path, index)?;
+    if result.status == StashApplyStatus::Applied {
+        stash_drop(path, index)?;
+    }
+    Ok(result)
+}
+
+#[napi]
+/**
+ * Delete a stash, the stashes after it are renumbered
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param index the n of `stash@{n}`
+ */
+fn stash_drop (path: String, index: i32) -> Result<(), JsError> {
+    let stash = format!("stash@{{{}}}", index);
+    match get_checked_command_output("git", &path, &["stash", "drop", "-q", &stash]) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Run `git stash apply` and tell the conflicts from the other failures
+ */
+fn apply_stash (path: &str, index: i32) -> Result<StashApplyResult, JsError> {
+    let stash = format!("stash@{{{}}}", index);
+    let output = match get_command_output("git", path, &["stash", "apply", &stash]) {
+        Ok(output) => output,
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    if output.status.success() {
+        return Ok(StashApplyResult { status: StashApplyStatus::Applied, conflicts: Vec::new() })
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let files = parse_overwritten_files(&stderr);
+    if !files.is_empty() {
+        return Err(JsError::from(CustomerGitError::LocalChangesOverwritten(files)))
+    }
+    let conflicts = get_unmerged_files(path)?;
+    if !conflicts.is_empty() {
+        return Ok(StashApplyResult { status: StashApplyStatus::Conflict, conflicts })
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git stash apply` exited with {}: {}\n{}", output.status, stderr.trim(), stdout.trim())));
+    Err(JsError::from(err))
+}
+
+#[napi]
+/**
+ * Get the submodules of a repository from `git submodule status` and .gitmodules
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @return an empty list if there is no submodule
+ */
+fn get_submodules (path: String) -> Result<Vec<SubmoduleInfo>, JsError> {
+    let output = get_checked_command_output("git", &path, &["submodule", "status"]);
+    let stdout = match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    // submodule.<name>.path=<path> and submodule.<name>.url=<url>, the name may contain dots
+    let mut names = HashMap::<String, String>::new();
+    let mut urls = HashMap::<String, String>::new();
+    if let Ok(output) = get_checked_command_output("git", &path, &["config", "-f", ".gitmodules", "--list"]) {
+        let config = String::from_utf8_lossy(&output.stdout);
+        for line in config.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let Some((name, field)) = key.strip_prefix("submodule.").and_then(|key| key.rsplit_once('.')) else {
+                continue;
+            };
+            match field {
+                "path" => { names.insert(value.to_string(), name.to_string()); }
+                "url" => { urls.insert(name.to_string(), value.to_string()); }
+                _ => {}
+            }
+        }
+    }
+    let submodules = stdout.lines().filter(|line| line.len() > 1).filter_map(|line| {
+        let (flag, rest) = line.split_at(1);
+        let status = match flag {
+            "-" => SubmoduleStatus::Uninitialized,
+            "+" => SubmoduleStatus::OutOfSync,
+            "U" => SubmoduleStatus::Conflict,
+            _ => SubmoduleStatus::Clean,
+        };
+        let (current_hash, rest) = rest.split_once(' ')?;
+        // the path is followed by " (<describe>)" when the submodule is checked out
+        let submodule_path = match rest.rfind(" (") {
+            Some(index) if rest.ends_with(')') => &rest[..index],
+            _ => rest,
+        };
+        let name = names.get(submodule_path).cloned().unwrap_or(submodule_path.to_string());
+        Some(SubmoduleInfo {
+            url: urls.get(&name).cloned().unwrap_or_default(),
+            name,
+            path: submodule_path.to_string(),
+            current_hash: current_hash.to_string(),
+            status,
+        })
+    }).collect();
+    Ok(submodules)
+}
+
+#[napi]
+/**
+ * Get the signature of a commit and whether it is valid, an unsigned commit has the status None
+ * @param path path to the repository
+ * @param hash commit hash
+ */
+fn get_commit_signature (path: String, hash: String) -> Result<CommitSignature, JsError> {
+    let format = format!("--format=%G?{}%GS{}%GK", PARAM_INTERVAL, PARAM_INTERVAL);
+    let output = get_checked_command_output("git", &path, &["show", "-s", &format, &hash, "--"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let fields = stdout.trim().splitn(3, PARAM_INTERVAL).collect::<Vec<&str>>();
+            let field = |i: usize| fields.get(i).map(|field| field.trim()).unwrap_or("");
+            let status = parse_signature_status(field(0));
+            Ok(CommitSignature {
+                signed: status != SignatureStatus::None,
+                status,
+                signer: field(1).to_string(),
+                key: field(2).to_string(),
+            })
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Map the `%G?` flag, the good signatures with an expired or revoked key or an unknown validity are Unknown
+ */
+fn parse_signature_status (flag: &str) -> SignatureStatus {
+    match flag {
+        "G" => SignatureStatus::Good,
+        "B" => SignatureStatus::Bad,
+        "N" | "" => SignatureStatus::None,
+        _ => SignatureStatus::Unknown,
+    }
+}
+
+#[napi]
+/**
+ * Switch the working tree to a branch, a tag or a commit
+ * @param path path to the repository
+ * @param branch the branch to switch to, it can't start with "-"
+ * @param force discard the local changes instead of failing (`-f`)
+ * @return fails with the "Cancelled" code and the conflicting files if the local changes would be overwritten
+ */
+fn checkout_branch (path: String, branch: String, force: bool) -> Result<(), JsError> {
+    ensure_git_repository(&path)?;
+    // an older `git checkout` takes `--end-of-options` for a ref, e.g. git 2.39
+    reject_option_args(&[("branch", &branch)])?;
+    let mut args = vec!["checkout", "-q"];
+    if force {
+        args.push("-f");
+    }
+    // the trailing "--" keeps the branch from being taken as a path
+    args.extend([branch.as_str(), "--"]);
+    let output = get_command_output("git", &path, &args);
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                return Ok(())
+            }
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let files = parse_overwritten_files(&stderr);
+            if !files.is_empty() {
+                return Err(JsError::from(CustomerGitError::LocalChangesOverwritten(files)))
+            }
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git checkout` exited with {}: {}", output.status, stderr.trim())));
+            Err(JsError::from(err))
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Get the files listed by git when the local changes or the untracked files would be overwritten,
+ * they are indented by a tab after the "would be overwritten by" line
+ */
+fn parse_overwritten_files (stderr: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut in_list = false;
+    for line in stderr.lines() {
+        if line.contains("would be overwritten by") {
+            in_list = true;
+        } else if in_list && line.starts_with('\t') {
+            files.push(line.trim().to_string());
+        } else {
+            in_list = false;
+        }
+    }
+    files
+}
+
+#[napi]
+/**
+ * Create a branch without switching to it
+ * @param path path to the repository
+ * @param name the name of the new branch
+ * @param start_point the commit the branch points to, it can be "" for HEAD
+ */
+fn create_branch (path: String, name: String, start_point: String) -> Result<(), JsError> {
+    ensure_git_repository(&path)?;
+    ensure_branch_name(&path, &name)?;
+    let mut args = vec!["branch", "--end-of-options", name.as_str()];
+    if !start_point.is_empty() {
+        args.push(&start_point);
+    }
+    let output = get_checked_command_output("git", &path, &args);
+    match output {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Delete a local branch
+ * @param path path to the repository
+ * @param name the name of the branch
+ * @param force delete the branch even if it is not fully merged (`-D`)
+ * @return fails with the "Cancelled" code if the branch is not fully merged and force is false
+ */
+fn delete_branch (path: String, name: String, force: bool) -> Result<(), JsError> {
+    ensure_git_repository(&path)?;
+    ensure_branch_name(&path, &name)?;
+    let flag = if force { "-D" } else { "-d" };
+    let output = get_command_output("git", &path, &["branch", flag, &name]);
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                return Ok(())
+            }
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("not fully merged") {
+                return Err(JsError::from(CustomerGitError::BranchNotMerged(name)))
+            }
+            let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git branch` exited with {}: {}", output.status, stderr.trim())));
+            Err(JsError::from(err))
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Fail with `CustomerGitError::InvalidBranchName` if git doesn't accept the name as a branch name
+ */
+fn ensure_branch_name (path: &str, name: &str) -> Result<(), JsError> {
+    let output = get_command_output("git", path, &["check-ref-format", "--branch", name]);
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(_) => Err(JsError::from(CustomerGitError::InvalidBranchName(name.to_string()))),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Stage the files (`git add`), an empty list stages nothing
+ * @param path path to the repository
+ * @param paths the paths of the files or the directories
+ * @return the paths which fail to be staged, empty if all of them are staged
+ */
+fn stage_files (path: String, paths: Vec<String>) -> Result<Vec<PathFailure>, JsError> {
+    run_git_on_paths(&path, &["add"], &paths)
+}
+
+#[napi]
+/**
+ * Unstage the files (`git restore --staged`), the changes are kept in the working tree, an empty list unstages nothing.
+ * Before the first commit there is no HEAD to restore from, the files are removed from the index instead (`git rm --cached`)
+ * @param path path to the repository
+ * @param paths the paths of the files or the directories
+ * @return the paths which fail to be unstaged, empty if all of them are unstaged
+ */
+fn unstage_files (path: String, paths: Vec<String>) -> Result<Vec<PathFailure>, JsError> {
+    if !paths.is_empty() && resolve_ref(path.clone(), "HEAD".to_string()).is_err() {
+        return run_git_on_paths(&path, &["rm", "--cached", "-r", "-q"], &paths)
+    }
+    run_git_on_paths(&path, &["restore", "--staged"], &paths)
+}
+
+#[napi]
+/**
+ * Discard the unstaged changes of the files (`git restore`), an empty list discards nothing
+ * @param path path to the repository
+ * @param paths the paths of the files or the directories
+ * @return the paths which fail to be restored, e.g. the untracked files, empty if all of them are restored
+ */
+fn discard_changes (path: String, paths: Vec<String>) -> Result<Vec<PathFailure>, JsError> {
+    run_git_on_paths(&path, &["restore"], &paths)
+}
+
+/**
+ * Run the git command on all the paths at once, if it fails the paths are retried one by one
+ * to find out which of them fail, git doesn't change anything when one of the paths is invalid
+ */
+fn run_git_on_paths (path: &str, command: &[&str], paths: &[String]) -> Result<Vec<PathFailure>, JsError> {
+    if paths.is_empty() {
+        return Ok(Vec::new())
+    }
+    ensure_git_repository(path)?;
+    let run = |paths: &[String]| {
+        let mut args = command.to_vec();
+        args.push("--");
+        args.extend(paths.iter().map(|path| path.as_str()));
+        get_checked_command_output("git", path, &args)
+    };
+    if run(paths).is_ok() {
+        return Ok(Vec::new())
+    }
+    let mut failures = Vec::new();
+    for file_path in paths.iter() {
+        if let Err(e) = run(std::slice::from_ref(file_path)) {
+            failures.push(PathFailure {
+                path: file_path.to_string(),
+                message: e.to_string(),
+            });
+        }
+    }
+    Ok(failures)
+}
+
+#[napi]
+/**
+ * Commit the staged changes
+ * @param path path to the repository
+ * @param message the commit message
+ * @param options optional amend, allow_empty and author, see `CommitOptions`
+ * @return the hash of the new commit, fails with the output of git and the hooks if the commit is not created
+ */
+fn create_commit (path: String, message: String, options: Option<CommitOptions>) -> Result<String, JsError> {
+    ensure_git_repository(&path)?;
+    let options = options.unwrap_or_default();
+    let mut args = vec!["commit".to_string(), "-q".to_string(), "-m".to_string(), message];
+    if options.amend.unwrap_or(false) {
+        args.push("--amend".to_string());
+    }
+    if options.allow_empty.unwrap_or(false) {
+        args.push("--allow-empty".to_string());
+    }
+    if let Some(author) = options.author.filter(|author| !author.is_empty()) {
+        args.push(format!("--author={}", author));
+    }
+    let output = get_command_output("git", &path, &args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>());
+    match output {
+        Ok(output) => {
+            if !output.status.success() {
+                // the hooks and "nothing to commit" print to the stdout
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git commit` exited with {}: {}\n{}", output.status, stderr.trim(), stdout.trim())));
+                return Err(JsError::from(err))
+            }
+            resolve_ref(path, "HEAD".to_string())
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Apply the changes of a commit onto HEAD with `git cherry-pick`
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param hash the commit to pick
+ * @return `Applied` with the new hash, `Conflict` with the conflicted paths when the pick stops,
+ * or `Empty` when the changes are already applied, fails with the "Cancelled" code and the files
+ * if the local changes would be overwritten
+ */
+fn cherry_pick (path: String, hash: String) -> Result<CherryPickResult, JsError> {
+    apply_commit_changes(&path, &["cherry-pick", &hash])
+}
+
+#[napi]
+/**
+ * Revert the changes of a commit with `git revert`, conflicts are reported the same way as `cherry_pick`
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param hash the commit to revert
+ * @param no_commit stage the revert without committing it (`-n`), the hash of the result is empty then
+ * @param mainline the parent number to revert a merge commit against (`-m`), starting from 1, required for a merge commit
+ */
+fn revert_commit (path: String, hash: String, no_commit: bool, mainline: Option<i32>) -> Result<CherryPickResult, JsError> {
+    let mut args = vec!["revert".to_string(), "--no-edit".to_string()];
+    if no_commit {
+        args.push("-n".to_string());
+    }
+    if let Some(mainline) = mainline {
+        args.push(format!("-m{}", mainline));
+    }
+    args.push(hash);
+    let mut result = apply_commit_changes(&path, &args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>())?;
+    if no_commit && result.status == CherryPickStatus::Applied {
+        result.hash = "".to_string();
+    }
+    Ok(result)
+}
+
+/**
+ * Run `git cherry-pick` or `git revert`, tell the conflicts and the changes already applied from the other failures
+ */
+fn apply_commit_changes (path: &str, args: &[&str]) -> Result<CherryPickResult, JsError> {
+    ensure_git_repository(path)?;
+    let output = get_command_output("git", path, args);
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    if output.status.success() {
+        return Ok(CherryPickResult {
+            status: CherryPickStatus::Applied,
+            hash: resolve_ref(path.to_string(), "HEAD".to_string())?,
+            conflicts: Vec::new(),
+        })
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let files = parse_overwritten_files(&stderr);
+    if !files.is_empty() {
+        return Err(JsError::from(CustomerGitError::LocalChangesOverwritten(files)))
+    }
+    let conflicts = get_unmerged_files(path)?;
+    if !conflicts.is_empty() {
+        return Ok(CherryPickResult { status: CherryPickStatus::Conflict, hash: "".to_string(), conflicts })
+    }
+    if stderr.contains("is now empty") {
+        // the pick is left in progress with nothing to commit, skip it to clean up
+        if let Err(e) = get_checked_command_output("git", path, &[args[0], "--skip"]) {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+        return Ok(CherryPickResult { status: CherryPickStatus::Empty, hash: "".to_string(), conflicts })
+    }
+    // `git revert` stops without anything in progress
+    if String::from_utf8_lossy(&output.stdout).contains("nothing to commit") {
+        return Ok(CherryPickResult { status: CherryPickStatus::Empty, hash: "".to_string(), conflicts })
+    }
+    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git {}` exited with {}: {}", args[0], output.status, stderr.trim())));
+    Err(JsError::from(err))
+}
+
+#[napi]
+/**
+ * Abort the cherry-pick in progress and restore the state before it
+ * @param path path to the repository
+ */
+fn cherry_pick_abort (path: String) -> Result<(), JsError> {
+    ensure_git_repository(&path)?;
+    match get_checked_command_output("git", &path, &["cherry-pick", "--abort"]) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Merge a branch into the current branch with `git merge`
+ * Requires a work tree, fails on a bare repository
+ * @param path path to the repository
+ * @param branch the branch to merge
+ * @param options optional no_ff and squash, see `MergeOptions`
+ * @return the kind of the merge, with the unmerged paths when it stops on conflicts,
+ * fails with the "Cancelled" code and the files if the local changes would be overwritten
+ */
+fn merge_branch (path: String, branch: String, options: Option<MergeOptions>) -> Result<MergeResult, JsError> {
+    ensure_git_repository(&path)?;
+    let options = options.unwrap_or_default();
+    let squash = options.squash.unwrap_or(false);
+    let mut args = vec!["merge", "--no-edit"];
+    if options.no_ff.unwrap_or(false) {
+        args.push("--no-ff");
+    }
+    if squash {
+        args.push("--squash");
+    }
+    args.extend(["--end-of-options", branch.as_str()]);
+    let head = resolve_ref(path.clone(), "HEAD".to_string()).unwrap_or_default();
+    let output = match get_command_output("git", &path, &args) {
+        Ok(output) => output,
+        Err(e) => {
+            let err = napiError::from(e);
+            return Err(JsError::from(err))
+        }
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let files = parse_overwritten_files(&stderr);
+        if !files.is_empty() {
+            return Err(JsError::from(CustomerGitError::LocalChangesOverwritten(files)))
+        }
+        let conflicts = get_unmerged_files(&path)?;
+        if !conflicts.is_empty() {
+            return Ok(MergeResult { status: MergeStatus::Conflict, hash: "".to_string(), conflicts })
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`git merge` exited with {}: {}\n{}", output.status, stderr.trim(), stdout.trim())));
+        return Err(JsError::from(err))
+    }
+    let new_head = resolve_ref(path.clone(), "HEAD".to_string())?;
+    let result = |status: MergeStatus, hash: String| Ok(MergeResult { status, hash, conflicts: Vec::new() });
+    if squash {
+        // a squash never moves HEAD, it is up to date when nothing is staged
+        let staged = get_command_output("git", &path, &["diff", "--cached", "--quiet"]).map(|output| !output.status.success()).unwrap_or(true);
+        return if staged { result(MergeStatus::Squashed, "".to_string()) } else { result(MergeStatus::AlreadyUpToDate, "".to_string()) }
+    }
+    if new_head == head {
+        return result(MergeStatus::AlreadyUpToDate, "".to_string())
+    }
+    // a fast-forward can move HEAD onto a merge commit as well, the new merge commit has the old HEAD as its first parent
+    let first_parent = resolve_ref(path.clone(), format!("{}^1", new_head)).unwrap_or_default();
+    if first_parent == head && resolve_ref(path.clone(), format!("{}^2", new_head)).is_ok() {
+        result(MergeStatus::MergeCommit, new_head)
+    } else {
+        result(MergeStatus::FastForward, new_head)
+    }
+}
+
+/**
+ * Get the unmerged files of a merge, a cherry-pick or a revert stopped on conflicts
+ */
+fn get_unmerged_files (path: &str) -> Result<Vec<String>, JsError> {
+    let output = get_checked_command_output("git", path, &["diff", "--name-only", "--diff-filter=U", "-z"]);
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // every stage of an unmerged file is listed
+            let mut files = Vec::<String>::new();
+            for file in stdout.split('\0').filter(|file| !file.is_empty()) {
+                if !files.iter().any(|f| f == file) {
+                    files.push(file.to_string());
+                }
+            }
+            Ok(files)
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Get the base, ours and theirs versions of a conflicted file to show them in a merge editor
+ * @param path path to the repository
+ * @param file_path path of the conflicted file, relative to the root of the repository
+ */
+fn get_conflict_versions (path: String, file_path: String) -> Result<ConflictVersions, JsError> {
+    ensure_git_repository(&path)?;
+    // a stage is missing when the file doesn't exist on that side
+    let stage = |stage: &str| get_file_blob(&path, stage, &file_path).ok().map(|content| decode_text(&content));
+    Ok(ConflictVersions {
+        base: stage(":1"),
+        ours: stage(":2"),
+        theirs: stage(":3"),
+    })
+}
+
+#[napi]
+/**
+ * Abort the merge in progress and restore the state before it
+ * @param path path to the repository
+ */
+fn merge_abort (path: String) -> Result<(), JsError> {
+    ensure_git_repository(&path)?;
+    match get_checked_command_output("git", &path, &["merge", "--abort"]) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[napi]
+/**
+ * Run any git command in the repository and return its raw output without interpreting it,
+ * for the commands not wrapped by this module. The args are passed to git as they are, no shell is involved,
+ * and the command is not restricted, so don't pass the args of an untrusted source
+ * @param path path to the repository, the working directory of git
+ * @param args the args after `git`, e.g. ["log", "-1", "--format=%H"]
+ */
+fn run_git (path: String, args: Vec<String>) -> Result<GitOutput, JsError> {
+    let output = run_git_command(&path, &args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>());
+    match output {
+        Ok(output) => Ok(output),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Run a git network command with `--progress`, the progress lines of the stderr are passed to on_progress
+ * when the percent changes, the other lines are returned, or kept for the error if the command fails
+ */
+fn run_git_with_progress (path: &str, args: &[&str], on_progress: impl FnMut(TransferProgress)) -> Result<String, JsError> {
+    match get_git_progress_output(path, args, on_progress) {
+        Ok((status, output)) if status.success() => Ok(output),
+        Ok((status, output)) => Err(remote_command_error(args, status, &output)),
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+/**
+ * Run the command like `run_git_with_progress`, return the exit status and the stderr without the progress lines followed by the stdout
+ */
+fn get_git_progress_output (path: &str, args: &[&str], mut on_progress: impl FnMut(TransferProgress)) -> io::Result<(std::process::ExitStatus, String)> {
+    let mut child = build_command("git", path, args).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    // drain the stdout in another thread so git never blocks on a full pipe, a merge reports the conflicts in it
+    let mut stdout = child.stdout.take().unwrap();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut content = Vec::<u8>::new();
+        let _ = stdout.read_to_end(&mut content);
+        content
+    });
+    let mut reader = BufReader::new(child.stderr.take().unwrap());
+    let mut messages = Vec::<String>::new();
+    let mut last_progress: Option<TransferProgress> = None;
+    let mut buf = Vec::<u8>::new();
+    let mut eof = false;
+    while !eof {
+        // the progress is redrawn with "\r", the other lines end with "\n"
+        buf.clear();
+        let mut byte = [0u8; 1];
+        loop {
+            if reader.read(&mut byte)? == 0 {
+                eof = true;
+                break;
+            }
+            if byte[0] == b'\r' || byte[0] == b'\n' {
+                break;
+            }
+            buf.push(byte[0]);
+        }
+        let line = String::from_utf8_lossy(&buf).trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+        match parse_transfer_progress(&line) {
+            Some(progress) => {
+                if last_progress.as_ref() != Some(&progress) {
+                    last_progress = Some(progress.clone());
+                    on_progress(progress);
+                }
+            }
+            None => messages.push(line),
+        }
+    }
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let status = child.wait()?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    if !stdout.trim().is_empty() {
+        messages.push(stdout.trim().to_string());
+    }
+    Ok((status, messages.join("\n")))
+}
+
+/**
+ * Turn the output of a failed network command to a `CustomerGitError` if it is recognized, or to an error carrying the output
+ */
+fn remote_command_error (args: &[&str], status: std::process::ExitStatus, output: &str) -> JsError {
+    if let Some(error) = classify_remote_error(output) {
+        return JsError::from(error)
+    }
+    let command = format!("git {}", args.first().unwrap_or(&""));
+    let err = napiError::from(io::Error::new(io::ErrorKind::Other, format!("`{}` exited with {}: {}", command.trim(), status, output)));
+    JsError::from(err)
+}
+
+/**
+ * Recognize the failures of a network command the caller can react to, e.g. prompt for the credential or retry with force.
+ * The messages of the local git are English because `build_command` sets the C locale, the "remote:" lines are in the language of the server
+ */
+fn classify_remote_error (output: &str) -> Option<CustomerGitError> {
+    let lower = output.to_lowercase();
+    let auth_patterns = ["authentication failed", "could not read username", "could not read password", "permission denied (publickey", "terminal prompts disabled", "invalid username or password"];
+    if auth_patterns.iter().any(|pattern| lower.contains(pattern)) {
+        return Some(CustomerGitError::AuthenticationFailed(output.to_string()))
+    }
+    // "[remote rejected]" is a refusal of a hook, it can't be solved with force
+    if output.contains("[rejected]") {
+        return Some(CustomerGitError::PushRejected(output.to_string()))
+    }
+    if lower.contains("has no upstream branch") || lower.contains("no tracking information") {
+        return Some(CustomerGitError::NoUpstream(output.to_string()))
+    }
+    let files = parse_overwritten_files(output);
+    if !files.is_empty() {
+        return Some(CustomerGitError::LocalChangesOverwritten(files))
+    }
+    None
+}
+
+/**
+ * Parse a progress line like "remote: Counting objects: 10% (1/10)", the speed and the size after it are ignored.
+ * The stage is English with the C locale of `build_command`, a localized server may still send its own stage names
+ */
+fn parse_transfer_progress (line: &str) -> Option<TransferProgress> {
+    // it is called for every line of stderr, the regex is compiled only once
+    static PROGRESS_RE: std::sync::OnceLock<Regex> = std::sync::OnceLock::new();
+    let re = PROGRESS_RE.get_or_init(|| Regex::new(r"^(?:remote: )?(?<stage>[^:]+):\s+(?<percent>\d+)% \((?<current>\d+)/(?<total>\d+)\)").unwrap());
+    let captures = re.captures(line)?;
+    Some(TransferProgress {
+        stage: captures["stage"].trim().to_string(),
+        percent: captures["percent"].parse::<i32>().ok()?,
+        current: captures["current"].parse::<i32>().ok()?,
+        total: captures["total"].parse::<i32>().ok()?,
+    })
+}
+
+/**
+ * Reject the names starting with "-", git would take them for an option, e.g. "--upload-pack=<command>".
+ * `--end-of-options` is not enough, `git pull` doesn't pass it to the fetch
+ */
+fn reject_option_args (args: &[(&str, &str)]) -> Result<(), JsError> {
+    match args.iter().find(|(_, value)| value.starts_with('-')) {
+        Some((name, value)) => Err(JsError::from(CustomerGitError::InvalidArgument(format!("{} can't start with \"-\": {}", name, value)))),
+        None => Ok(()),
+    }
+}
+
+/**
+ * Fetch from a remote, the progress is passed to on_progress
+ * @param remote the remote to fetch, all the remotes are fetched if it is ""
+ * @param refspec the refspec to fetch, the configured refspecs of the remote are used if it is ""
+ * @param prune remove the remote-tracking branches which no longer exist on the remote
+ * @return the messages of git without the progress, e.g. the updated refs and the warnings
+ */
+fn fetch_with_progress (path: &str, remote: &str, refspec: &str, prune: bool, on_progress: impl FnMut(TransferProgress)) -> Result<String, JsError> {
+    ensure_git_repository(path)?;
+    reject_option_args(&[("remote", remote), ("refspec", refspec)])?;
+    let mut args = vec!["fetch", "--progress"];
+    if prune {
+        args.push("--prune");
+    }
+    if remote.is_empty() {
+        args.push("--all");
+    } else {
+        args.push(remote);
+        if !refspec.is_empty() {
+            args.push(refspec);
+        }
+    }
+    run_git_with_progress(path, &args, on_progress)
+}
+
+/**
+ * Pull a branch of a remote into the current branch, the progress is passed to on_progress
+ * @param remote the remote to pull from, the upstream of the current branch is used if it is ""
+ * @param branch the branch of the remote, it is only used with a remote
+ * @return the messages of git without the progress, e.g. the summary of the merge and the warnings
+ */
+fn pull_with_progress (path: &str, remote: &str, branch: &str, on_progress: impl FnMut(TransferProgress)) -> Result<String, JsError> {
+    ensure_git_repository(path)?;
+    reject_option_args(&[("remote", remote), ("branch", branch)])?;
+    let mut args = vec!["pull", "--progress"];
+    if !remote.is_empty() {
+        args.push(remote);
+        if !branch.is_empty() {
+            args.push(branch);
+        }
+    }
+    run_git_with_progress(path, &args, on_progress)
+}
+
+/**
+ * Push a branch to a remote, the progress is passed to on_progress
+ * @param remote the remote to push to, the upstream of the current branch is used if it is ""
+ * @param branch the branch to push, it is only used with a remote
+ * @param force overwrite the remote branch even if it is not a fast-forward (`--force`)
+ * @param set_upstream set the pushed branch as the upstream of the local branch (`--set-upstream`)
+ * @return the messages of git without the progress, e.g. the updated refs and the messages of the remote
+ */
+fn push_with_progress (path: &str, remote: &str, branch: &str, force: bool, set_upstream: bool, on_progress: impl FnMut(TransferProgress)) -> Result<String, JsError> {
+    ensure_git_repository(path)?;
+    reject_option_args(&[("remote", remote), ("branch", branch)])?;
+    let mut args = vec!["push", "--progress"];
+    if force {
+        args.push("--force");
+    }
+    if set_upstream {
+        args.push("--set-upstream");
+    }
+    if !remote.is_empty() {
+        args.push(remote);
+        if !branch.is_empty() {
+            args.push(branch);
+        }
+    }
+    run_git_with_progress(path, &args, on_progress)
+}
+
+/**
+ * Clone a repository into the target directory, the progress is passed to on_progress
+ * @param url the url or the path of the repository
+ * @param target_dir the directory of the new repository, a relative path is resolved against the current directory
+ * @param options optional depth, branch and bare, see `CloneOptions`
+ * @return the absolute path of the new repository
+ */
+fn clone_with_progress (url: &str, target_dir: &str, options: &Option<CloneOptions>, on_progress: impl FnMut(TransferProgress)) -> Result<String, JsError> {
+    let target = std::path::Path::new(target_dir);
+    let target = if target.is_absolute() {
+        target.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(dir) => dir.join(target),
+            Err(e) => {
+                let err = napiError::from(e);
+                return Err(JsError::from(err))
+            }
+        }
+    };
+    let target_path = target.to_string_lossy().to_string();
+    let options = options.clone().unwrap_or_default();
+    let mut args = vec!["clone".to_string(), "--progress".to_string()];
+    if let Some(depth) = options.depth {
+        args.push(format!("--depth={}", depth));
+    }
+    if let Some(branch) = options.branch.filter(|branch| !branch.is_empty()) {
+        args.extend(["--branch".to_string(), branch, "--single-branch".to_string()]);
+    }
+    if options.bare.unwrap_or(false) {
+        args.push("--bare".to_string());
+    }
+    args.extend(["--".to_string(), url.to_string(), target_path.to_string()]);
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>();
+    match get_git_progress_output("", &args, on_progress) {
+        Ok((status, _)) if status.success() => Ok(target_path),
+        Ok((status, mut output)) => {
+            // git removes what it has cloned, anything left in the directory is reported
+            let left = std::fs::read_dir(&target).map(|mut entries| entries.next().is_some()).unwrap_or(false);
+            if left {
+                output = output + &format!("\na partial clone is left in {}", target_path);
+            }
+            Err(remote_command_error(&args, status, &output))
+        }
+        Err(e) => {
+            let err = napiError::from(e);
+            Err(JsError::from(err))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use util::get_current_time;
+
+    use super::*;
+
+    // the tests changing the process-wide settings, e.g. `set_git_env` or `set_max_output_bytes`, take the write lock
+    // and run alone, every other test takes the read lock in the shared helpers
+    static TEST_GLOBALS: std::sync::RwLock<()> = std::sync::RwLock::new(());
+
+    enum GlobalsLock {
+        None,
+        Read(#[allow(dead_code)] std::sync::RwLockReadGuard<'static, ()>),
+        Write,
+    }
+
+    thread_local! {
+        // libtest runs each test on its own thread, the read guard is released when the test ends
+        static GLOBALS_LOCK: std::cell::RefCell<GlobalsLock> = const { std::cell::RefCell::new(GlobalsLock::None) };
+    }
+
+    /**
+     * Hold the read lock of the process-wide settings until the end of the current test
+     */
+    fn read_globals() {
+        GLOBALS_LOCK.with(|lock| {
+            let mut lock = lock.borrow_mut();
+            if let GlobalsLock::None = *lock {
+                *lock = GlobalsLock::Read(TEST_GLOBALS.read().unwrap_or_else(|e| e.into_inner()));
+            }
+        });
+    }
+
+    /**
+     * Hold the write lock of the process-wide settings, and restore the defaults when dropped, even if the test panics
+     */
+    struct GlobalsGuard {
+        _lock: std::sync::RwLockWriteGuard<'static, ()>,
+    }
+
+    impl GlobalsGuard {
+        fn new() -> Self {
+            // a previous test on the same thread (`--test-threads=1`) may still hold the read lock
+            GLOBALS_LOCK.with(|lock| *lock.borrow_mut() = GlobalsLock::None);
+            let guard = GlobalsGuard { _lock: TEST_GLOBALS.write().unwrap_or_else(|e| e.into_inner()) };
+            GLOBALS_LOCK.with(|lock| *lock.borrow_mut() = GlobalsLock::Write);
+            guard
+        }
+    }
+
+    impl Drop for GlobalsGuard {
+        fn drop(&mut self) {
+            set_git_env(HashMap::new());
+            set_max_output_bytes(0);
+            let _ = set_log_encoding("".to_string());
+            GLOBALS_LOCK.with(|lock| *lock.borrow_mut() = GlobalsLock::None);
+        }
+    }
+
+    /**
+     * Run git in the test repository and return the trimmed stdout
+     */
+    fn exec_git(path: &str, args: &[&str]) -> String {
+        read_globals();
+        let output = get_command_output("git", path, args).unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /**
+     * Create an empty repository in the temp dir
+     */
+    fn init_test_repo(name: &str) -> String {
+        read_globals();
+        let dir = std::env::temp_dir().join(format!("git-util-native-{}-{}", name, get_current_time()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_str().unwrap().to_string();
+        exec_git(&path, &["init", "-q", "-b", "main"]);
+        exec_git(&path, &["config", "user.name", "tester"]);
+        exec_git(&path, &["config", "user.email", "tester@example.com"]);
+        path
+    }
+
+    /**
+     * Write a file and commit it with the author date and the committer date, return the hash of the new commit
+     */
+    fn commit_file_at(path: &str, file_path: &str, content: &str, message: &str, date: &str) -> String {
+        let file = std::path::Path::new(path).join(file_path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, content).unwrap();
+        exec_git(path, &["add", "--", file_path]);
+        let output = Command::new("git").current_dir(path).args(["commit", "-q", "-m", message])
+            .env("GIT_AUTHOR_DATE", date).env("GIT_COMMITTER_DATE", date).output().unwrap();
+        assert!(output.status.success());
+        exec_git(path, &["rev-parse", "HEAD"])
+    }
+
+    /**
+     * Write a file and commit it, return the hash of the new commit
+     */
+    fn commit_file(path: &str, file_path: &str, content: &str, message: &str) -> String {
+        let file = std::path::Path::new(path).join(file_path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, content).unwrap();
+        exec_git(path, &["add", "--", file_path]);
+        exec_git(path, &["commit", "-q", "-m", message]);
+        exec_git(path, &["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn test_get_commit_file_status() {    
+        let path = String::from(r"E:\workSpace\Rust\rust_test");
+        let hash = String::from("6a8a10948c80df189f79ff680df66d688b93bdd2");
+        let res = get_commit_file_status(path.to_string(), hash.to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+            }
+        }
+    }
+    #[test]
+    fn test_get_file_commit_status() {
+        let path = String::from(r"E:\workSpace\Rust\git-util-native");
+        let commit_hash1 = String::from("2ffc252bee9edcdfa27d0689e4c9f4f80f72b608^");
+        let commit_hash2 = String::from("2ffc252bee9edcdfa27d0689e4c9f4f80f72b608");
+        let file_path = String::from("src/structs.rs");
+        let res = get_file_between_commit_status(path.to_string(), commit_hash1.to_string(), file_path.to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+            }
+        }
+    }
+    #[test]
+    fn test_diff_file_context() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let commit_hash1 = String::from("fe2eff4^");
+        let commit_hash2 = String::from("fe2eff4");
+        let file_path = String::from("src/electron/workThreads/WorkPool.ts");
+        let res = diff_file_context(path.to_string(), commit_hash1.to_string(), commit_hash2.to_string(), file_path.to_string(), None);
+        match res {
+            Ok(res) => {
+                println!("===============\n{:#?}\n=======================", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+    #[test]
+    fn test_get_file_content() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let commit_hash = String::from("fe2eff4");
+        let file_path = String::from("src/renderer/views/setting/index.vue");
+        let res = get_file_content(path.to_string(), commit_hash.to_string(), file_path.to_string());
+        match res {
+            Ok(res) => {
+                println!("===============\n{:#?}\n=======================", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_files_diff_context() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let commit1_hash = String::from("fe2eff4^");
+        let commit2_hash = String::from("fe2eff4");
+        let t1 = get_current_time();
+        let res = get_files_diff_context(path.to_string(), commit1_hash.to_string(), commit2_hash.to_string(), None, None, None);
+        match res {
+            Ok(res) => {
+                let t2 = get_current_time();
+                println!("time: {}", t2 - t1);
+                // println!("===============\n{:#?}\n== =====================", res);
+                for file_diff in res {
+                    println!("====================================");
+                    println!("{}", file_diff.file_path);
+                    println!("{}", file_diff.file_status);
+                    println!("{}", file_diff.change_stat);
+                }
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_repository() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let res = is_git_repository(path.to_string());
+        println!("{}", res);
+    }
+
+    #[test]
+    fn test_get_remote() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let res = get_remote(path.to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_tag() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let res = get_tags(path.to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+    
+    #[test]
+    fn test_get_file_by_hash() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let res = get_file_by_hash(path.to_string(), "a54c9f80a58e9c23a41aee057f2d14d79cefbe6c".to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_commit_title() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let res = get_commit_log_format(path.to_string(), vec!["%s".to_string(), "%h".to_string()], "274b861".to_string(), "b4864c1".to_string(), None);
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_pushed() {
+        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
+        let res = is_pushed(path.to_string(), "3.3.4".to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_contribute_stat() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let res = get_contribute_stat(path.to_string(),"main".to_string(), None, None);
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res.total_stat);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_commit_within_branches() {
+        let path = String::from(r"E:\workSpace\JavaScript\giter");
+        let hash = "274b8619bd9483f9e0622814987c253285584d0d";
+        let res = get_commit_within_branches(path.to_string(), hash.to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_status() {
+        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
+        let res = get_status(path.to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(e) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_all_authors () {
+        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
+        let res = get_all_authors(path.to_string());
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(_) => {
+                println!("ERROR");
+            }
+        }
+    }
+    #[test]
+    fn test_get_branch_authors () {
+        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
+        let branch = "3.3.3".to_string();
+        let res = get_branch_authors(path.to_string(), branch);
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(_) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_branch_commit_count() {
+        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
+        let branch = "3.3.4".to_string();
+        let res = get_branch_commit_count(path.to_string(), branch, None);
+        match res {
+            Ok(res) => {
+                println!("{:#?}", res);
+            },
+            Err(_) => {
+                println!("ERROR");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_commit_children() {
+        let path = init_test_repo("children");
+        let root = commit_file(&path, "a.txt", "a", "root");
+        let child1 = commit_file(&path, "b.txt", "b", "child 1");
+        exec_git(&path, &["checkout", "-q", "-b", "other", &root]);
+        let child2 = commit_file(&path, "c.txt", "c", "child 2");
+        let mut res = get_commit_children(path.to_string(), root[0..7].to_string(), None).ok().unwrap();
+        res.sort();
+        let mut expected = vec![child1.to_string(), child2.to_string()];
+        expected.sort();
+        assert_eq!(res, expected);
+        let res = get_commit_children(path.to_string(), root.to_string(), Some("main".to_string())).ok().unwrap();
+        assert_eq!(res, vec![child1.to_string()]);
+        assert!(get_commit_children(path.to_string(), child1.to_string(), None).ok().unwrap().is_empty());
+        // an option-like ref is not taken as an option
+        assert!(get_commit_children(path.to_string(), root.to_string(), Some("--all".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_get_bus_factor() {
+        let path = init_test_repo("bus_factor");
+        let content = (1..=10).map(|i| format!("line {}\n", i)).collect::<String>();
+        commit_file(&path, "owned.txt", &content, "add owned file");
+        std::fs::write(std::path::Path::new(&path).join("owned.txt"), content.replace("line 10", "line ten")).unwrap();
+        exec_git(&path, &["commit", "-q", "-a", "-m", "touch one line", "--author", "other <other@example.com>"]);
+        let res = get_bus_factor(path.to_string(), "main".to_string(), None, None).ok().unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].path, "owned.txt");
+        assert_eq!(res[0].top_author.email, "tester@example.com");
+        assert_eq!(res[0].top_author_percent, 90.0);
+        assert_eq!(res[0].author_count, 2);
+        assert!(res[0].at_risk);
+        let res = get_bus_factor(path.to_string(), "main".to_string(), Some(95.0), None).ok().unwrap();
+        assert!(!res[0].at_risk);
+        // the top author owns most lines, but too many others have touched the file
+        let res = get_bus_factor(path.to_string(), "main".to_string(), None, Some(1)).ok().unwrap();
+        assert!(!res[0].at_risk);
+        commit_file(&path, "a b.txt", "a\n", "add a file with a space");
+        let res = get_bus_factor(path.to_string(), "main".to_string(), None, None).ok().unwrap();
+        assert_eq!(res.iter().map(|file| file.path.as_str()).collect::<Vec<_>>(), vec!["a b.txt", "owned.txt"]);
+        // a submodule gitlink has nothing to blame
+        let head = exec_git(&path, &["rev-parse", "HEAD"]);
+        exec_git(&path, &["update-index", "--add", "--cacheinfo", &format!("160000,{},module", head)]);
+        exec_git(&path, &["commit", "-q", "-m", "add a gitlink"]);
+        let res = get_bus_factor(path.to_string(), "main".to_string(), None, None).ok().unwrap();
+        assert_eq!(res.len(), 2);
+    }
+
+    #[test]
+    fn test_has_git() {
+        assert!(has_git());
+        assert!(!has_program("git-util-native-not-exists"));
+    }
+
+    #[test]
+    fn test_get_range_commit_patches() {
+        let path = init_test_repo("range_patches");
+        let base = commit_file(&path, "a.txt", "a\n", "base");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        let first = commit_file(&path, "b.txt", "b\n", "add b");
+        let second = commit_file(&path, "a.txt", "a\naa\n", "modify a");
+        let third = commit_file(&path, "c.txt", "c\n", "add c");
+        let res = get_range_commit_patches(path.to_string(), base.to_string(), "feature".to_string()).ok().unwrap();
+        let hashes = res.iter().map(|p| p.commit.hash.to_string()).collect::<Vec<String>>();
+        assert_eq!(hashes, vec![first, second, third]);
+        assert_eq!(res[0].files.len(), 1);
+        assert_eq!(res[0].files[0].file_path, "b.txt");
+        assert_eq!(res[1].files[0].file_status, FileStatusType::Modified);
+        assert_eq!(res[1].files[0].change_stat.addition, 1);
+        assert_eq!(res[2].commit.title, "add c");
+    }
+
+    #[test]
+    fn test_non_zero_exit_is_error() {
+        let path = init_test_repo("exit_code");
+        commit_file(&path, "a.txt", "a", "init");
+        let res = get_commit_log_format(path.to_string(), vec!["%H".to_string()], "".to_string(), "not-exists".to_string(), None);
+        assert!(res.is_err());
+        let res = get_files_status_between_commit(path.to_string(), "HEAD".to_string(), "not-exists".to_string(), None, None, None);
+        assert!(res.is_err());
+        assert!(get_tags(path.to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_get_commit_detail() {
+        let path = init_test_repo("commit_detail");
+        let root = commit_file(&path, "a.txt", "a", "root commit");
+        std::fs::rename(std::path::Path::new(&path).join("a.txt"), std::path::Path::new(&path).join("b.txt")).unwrap();
+        exec_git(&path, &["add", "-A"]);
+        exec_git(&path, &["commit", "-q", "-m", "rename a"]);
+        let res = get_commit_detail(path.to_string(), root.to_string()).ok().unwrap();
+        assert_eq!(res.hash, root);
+        assert!(res.parents.is_empty());
+        assert_eq!(res.committer.email, "tester@example.com");
+        assert_eq!(res.status.len(), 1);
+        assert_eq!(res.status[0].status, FileStatusType::Added);
+        let res = get_commit_detail(path.to_string(), "HEAD".to_string()).ok().unwrap();
+        assert_eq!(res.parents, vec![root.clone()]);
+        assert_eq!(res.title, "rename a");
+        assert_eq!(res.status[0].status, FileStatusType::Renamed);
+        assert_eq!(res.status[0].message, "a.txt => b.txt");
+        // a tag resolves to its commit, a tree or a blob is not a commit
+        exec_git(&path, &["tag", "-a", "v1", "-m", "release", &root]);
+        assert_eq!(get_commit_detail(path.to_string(), "v1".to_string()).ok().unwrap().hash, root);
+        assert!(get_commit_detail(path.to_string(), "HEAD^{tree}".to_string()).is_err());
+        assert!(get_commit_detail(path.to_string(), "HEAD:b.txt".to_string()).is_err());
+        assert!(get_commit_detail(path.to_string(), "--all".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_get_commit_log_page() {
+        let path = init_test_repo("log_page");
+        for i in 0..5 {
+            commit_file(&path, "a.txt", &i.to_string(), &format!("commit {}", i));
+        }
+        let placeholders = vec!["%s".to_string(), "%h".to_string()];
+        let res = get_commit_log_page(path.to_string(), placeholders.clone(), "".to_string(), "".to_string(), 0, 2, None).ok().unwrap();
+        assert_eq!(res.commits.len(), 2);
+        assert_eq!(res.commits[0].get("message").unwrap(), "commit 4");
+        assert!(res.has_more);
+        let res = get_commit_log_page(path.to_string(), placeholders.clone(), "".to_string(), "".to_string(), 4, 2, None).ok().unwrap();
+        assert_eq!(res.commits.len(), 1);
+        assert_eq!(res.commits[0].get("message").unwrap(), "commit 0");
+        assert!(!res.has_more);
+        let res = get_commit_log_page(path.to_string(), placeholders.clone(), "".to_string(), "".to_string(), 0, i32::MAX, None).ok().unwrap();
+        assert_eq!(res.commits.len(), 5);
+        assert!(!res.has_more);
+        assert!(get_commit_log_page(path.to_string(), placeholders.clone(), "".to_string(), "".to_string(), -1, 2, None).is_err());
+        assert!(get_commit_log_page(path.to_string(), placeholders.clone(), "".to_string(), "".to_string(), 0, 0, None).is_err());
+    }
+
+    #[test]
+    fn test_get_remotes_detailed() {
+        let path = init_test_repo("remotes_detailed");
+        exec_git(&path, &["remote", "add", "origin", "https://example.com/fetch.git"]);
+        exec_git(&path, &["remote", "add", "mirror", "https://example.com/mirror.git"]);
+        exec_git(&path, &["remote", "set-url", "--push", "origin", "git@example.com:push.git"]);
+        let res = get_remotes_detailed(path.to_string()).ok().unwrap();
+        let origin = res.iter().find(|r| r.name == "origin").unwrap();
+        assert_eq!(origin.fetch_url, "https://example.com/fetch.git");
+        assert_eq!(origin.push_url, "git@example.com:push.git");
+        let mirror = res.iter().find(|r| r.name == "mirror").unwrap();
+        assert_eq!(mirror.push_url, "https://example.com/mirror.git");
+    }
+
+    #[test]
+    fn test_get_commit_log_date_filter() {
+        let path = init_test_repo("log_date_filter");
+        for (i, date) in ["2020-01-01T12:00:00+00:00", "2021-01-01T12:00:00+00:00", "2022-01-01T12:00:00+00:00"].iter().enumerate() {
+            commit_file_at(&path, "a.txt", &i.to_string(), &format!("commit {}", i), date);
+        }
+        let filter = CommitLogFilter {
+            since: Some("2020-06-01".to_string()),
+            until: Some("".to_string()),
+            ..Default::default()
+        };
+        let res = get_commit_log_format(path.to_string(), vec!["%s".to_string()], "".to_string(), "".to_string(), Some(filter)).ok().unwrap();
+        assert_eq!(res.len(), 2);
+        let filter = CommitLogFilter {
+            since: Some("2020-06-01".to_string()),
+            until: Some("2021-06-01".to_string()),
+            ..Default::default()
+        };
+        let res = get_commit_log_format(path.to_string(), vec!["%s".to_string()], "".to_string(), "".to_string(), Some(filter)).ok().unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].get("message").unwrap(), "commit 1");
+    }
+
+    #[test]
+    fn test_is_commit_pushed() {
+        let path = init_test_repo("commit_pushed");
+        let pushed = commit_file(&path, "a.txt", "a", "pushed");
+        let remote_path = path.to_string() + "-remote";
+        exec_git(&path, &["clone", "-q", "--bare", &path, &remote_path]);
+        exec_git(&path, &["remote", "add", "origin", &remote_path]);
+        exec_git(&path, &["fetch", "-q", "origin"]);
+        let local = commit_file(&path, "a.txt", "b", "local only");
+        assert!(is_commit_pushed(path.to_string(), pushed.to_string(), None).ok().unwrap());
+        assert!(is_commit_pushed(path.to_string(), pushed.to_string(), Some("origin".to_string())).ok().unwrap());
+        assert!(!is_commit_pushed(path.to_string(), pushed.to_string(), Some("upstream".to_string())).ok().unwrap());
+        assert!(!is_commit_pushed(path.to_string(), local.to_string(), None).ok().unwrap());
+    }
+
+    #[test]
+    fn test_get_commit_log_author_filter() {
+        let path = init_test_repo("log_author_filter");
+        commit_file(&path, "a.txt", "a", "by tester");
+        std::fs::write(std::path::Path::new(&path).join("a.txt"), "b").unwrap();
+        exec_git(&path, &["commit", "-q", "-a", "-m", "by other", "--author", "other <other@example.com>"]);
+        let filter = CommitLogFilter {
+            author: Some("other@".to_string()),
+            ..Default::default()
+        };
+        let res = get_commit_log_page(path.to_string(), vec!["%s".to_string()], "".to_string(), "".to_string(), 0, 10, Some(filter)).ok().unwrap();
+        assert_eq!(res.commits.len(), 1);
+        assert_eq!(res.commits[0].get("message").unwrap(), "by other");
+        let filter = CommitLogFilter {
+            author: Some("nobody".to_string()),
+            ..Default::default()
+        };
+        let res = get_commit_log_format(path.to_string(), vec!["%s".to_string()], "".to_string(), "".to_string(), Some(filter)).ok().unwrap();
+        assert!(res.is_empty());
+    }
+
+    #[test]
+    fn test_get_untracked_tree() {
+        let path = init_test_repo("untracked_tree");
+        commit_file(&path, ".gitignore", "*.log\n", "ignore logs");
+        let root = std::path::Path::new(&path);
+        std::fs::create_dir_all(root.join("src/nested/deep")).unwrap();
+        std::fs::write(root.join("src/nested/deep/new.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("src/nested/.gitignore"), "local.txt\n").unwrap();
+        std::fs::write(root.join("src/nested/local.txt"), "local").unwrap();
+        std::fs::write(root.join("top.txt"), "top").unwrap();
+        std::fs::write(root.join("debug.log"), "log").unwrap();
+        let res = get_untracked_tree(path.to_string(), false).ok().unwrap();
+        let mut names = res.iter().map(|f| f.name.to_string()).collect::<Vec<String>>();
+        names.sort();
+        assert_eq!(names, vec!["src", "top.txt"]);
+        let src = res.iter().find(|f| f.name == "src").unwrap();
+        assert!(src.is_dir);
+        let nested = &src.children[0];
+        assert_eq!(nested.name, "nested");
+        let nested_names = nested.children.iter().map(|f| f.name.to_string()).collect::<Vec<String>>();
+        assert!(nested_names.contains(&"deep".to_string()));
+        assert!(!nested_names.contains(&"local.txt".to_string()));
+        let deep = nested.children.iter().find(|f| f.name == "deep").unwrap();
+        assert_eq!(deep.children[0].name, "new.rs");
+        assert_eq!(deep.children[0].object_size, 12);
+        let res = get_untracked_tree(path.to_string(), true).ok().unwrap();
+        assert!(res.iter().any(|f| f.name == "debug.log"));
+    }
+
+    #[test]
+    fn test_search_commits() {
+        let path = init_test_repo("search_commits");
+        commit_file(&path, "a.txt", "1", "fix JIRA-1234 crash");
+        commit_file(&path, "a.txt", "2", "jira-1234 follow up; rm -rf /");
+        commit_file(&path, "a.txt", "3", "unrelated");
+        let placeholders = vec!["%s".to_string()];
+        let res = search_commits(path.to_string(), "main".to_string(), vec!["JIRA-1234".to_string()], placeholders.clone(), false, false).ok().unwrap();
+        assert_eq!(res.len(), 1);
+        let res = search_commits(path.to_string(), "".to_string(), vec!["JIRA-1234".to_string()], placeholders.clone(), true, false).ok().unwrap();
+        assert_eq!(res.len(), 2);
+        let queries = vec!["jira-1234".to_string(), "; rm -rf".to_string()];
+        let res = search_commits(path.to_string(), "main".to_string(), queries, placeholders.clone(), true, true).ok().unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].get("message").unwrap(), "jira-1234 follow up; rm -rf /");
+    }
+
+    #[test]
+    fn test_get_contribute_stat_since() {
+        let path = init_test_repo("contribute_since");
+        commit_file(&path, "a.txt", "a\n", "first");
+        let since = commit_file(&path, "a.txt", "a\nb\n", "second");
+        commit_file(&path, "b.txt", "1\n2\n3\n", "third");
+        commit_file(&path, "c.txt", "1\n", "fourth");
+        let res = get_contribute_stat_since(path.to_string(), "main".to_string(), since.to_string()).ok().unwrap();
+        assert_eq!(res.total_stat.commit_count.iter().sum::<i32>(), 2);
+        assert_eq!(res.authors_stat.len(), 1);
+        let res = get_contribute_stat_since(path.to_string(), "main".to_string(), "HEAD".to_string()).ok().unwrap();
+        assert!(res.total_stat.commit_count.is_empty());
+        exec_git(&path, &["checkout", "-q", "-b", "side", &since]);
+        let side = commit_file(&path, "d.txt", "d\n", "side");
+        assert!(get_contribute_stat_since(path.to_string(), "main".to_string(), side).is_err());
+    }
+
+    #[test]
+    fn test_get_branch_commit_count_file_path() {
+        let path = init_test_repo("commit_count");
+        assert_eq!(get_branch_commit_count(path.to_string(), "HEAD".to_string(), None).ok().unwrap(), 0);
+        commit_file(&path, "a.txt", "1", "first");
+        commit_file(&path, "b.txt", "1", "second");
+        commit_file(&path, "a.txt", "2", "third");
+        assert_eq!(get_branch_commit_count(path.to_string(), "main".to_string(), None).ok().unwrap(), 3);
+        assert_eq!(get_branch_commit_count(path.to_string(), "main".to_string(), Some("a.txt".to_string())).ok().unwrap(), 2);
+        assert!(get_branch_commit_count(path.to_string(), "not-exists".to_string(), None).is_err());
+    }
+
+    #[test]
+    fn test_classify_paths() {
+        let paths = vec![
+            "node_modules/lodash/index.js",
+            "web/node_modules/react/index.js",
+            "vendor/github.com/pkg/errors/errors.go",
+            "dist/bundle.js",
+            "static/app.min.js",
+            "package-lock.json",
+            "api/service.pb.go",
+            "src/main.rs",
+            "src/vendors.rs",
+            "third/party.js",
+            "lib/generated/schema.ts",
+        ].iter().map(|p| p.to_string()).collect::<Vec<String>>();
+        let gitattributes = "# linguist overrides\nlib/generated/** linguist-generated\nvendor/** -linguist-vendored\n";
+        let res = classify_paths(paths, Some(gitattributes.to_string()));
+        let flags = res.iter().map(|r| (r.vendored, r.generated)).collect::<Vec<(bool, bool)>>();
+        assert_eq!(flags, vec![
+            (true, false),
+            (true, false),
+            (false, false),
+            (false, true),
+            (false, true),
+            (false, true),
+            (false, true),
+            (false, false),
+            (false, false),
+            (false, false),
+            (false, true),
+        ]);
+    }
+
+    #[test]
+    fn test_get_blame() {
+        let path = init_test_repo("blame");
+        let first = commit_file(&path, "a.txt", "one\ntwo\n", "first");
+        std::fs::write(std::path::Path::new(&path).join("a.txt"), "one\n2\nthree\n").unwrap();
+        exec_git(&path, &["commit", "-q", "-a", "-m", "second", "--author", "other <other@example.com>"]);
+        let second = exec_git(&path, &["rev-parse", "HEAD"]);
+        let res = get_blame(path.to_string(), "HEAD".to_string(), "a.txt".to_string()).ok().unwrap();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].line_number, 1);
+        assert_eq!(res[0].content, "one");
+        assert_eq!(res[0].commit_hash, first);
+        assert_eq!(res[0].email, "tester@example.com");
+        assert_eq!(res[2].line_number, 3);
+        assert_eq!(res[2].author, "other");
+        assert_eq!(res[2].commit_hash, second);
+        assert!(!res[2].timestamp.is_empty());
+        assert!(get_blame(path.to_string(), first.to_string(), "missing.txt".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_get_commit_format() {
+        let path = init_test_repo("commit_format");
+        let first = commit_file(&path, "a.txt", "1", "first");
+        commit_file(&path, "a.txt", "2", "second");
+        let res = get_commit_format(path.to_string(), first[0..8].to_string(), vec!["%H".to_string(), "%s".to_string(), "%ae".to_string()]).ok().unwrap();
+        assert_eq!(res.get("hashL").unwrap(), &first);
+        assert_eq!(res.get("message").unwrap(), "first");
+        assert_eq!(res.get("authorEmail").unwrap(), "tester@example.com");
+        assert!(get_commit_format(path.to_string(), "0123456789abcdef".to_string(), vec!["%H".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_get_files_last_commit() {
+        let path = init_test_repo("files_last_commit");
+        let a = commit_file(&path, "dir/a.txt", "a", "add a");
+        let b = commit_file(&path, "dir/b.txt", "b", "add b");
+        commit_file(&path, "other.txt", "o", "add other");
+        let c = commit_file(&path, "dir/sub/c.txt", "c", "add c");
+        commit_file(&path, "other.txt", "o2", "modify other");
+        let file_paths = vec!["dir/a.txt", "dir/b.txt", "dir/sub", "missing.txt"].iter().map(|p| p.to_string()).collect();
+        let res = get_files_last_commit(path.to_string(), "main".to_string(), file_paths).ok().unwrap();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res.get("dir/a.txt").unwrap().hash, a);
+        assert_eq!(res.get("dir/b.txt").unwrap().hash, b);
+        assert_eq!(res.get("dir/sub").unwrap().hash, c);
+        assert_eq!(res.get("dir/sub").unwrap().title, "add c");
+        assert!(get_files_last_commit(path.to_string(), "not-exists".to_string(), vec!["dir/a.txt".to_string()]).is_err());
+        assert!(get_files_last_commit(path.to_string(), "main".to_string(), vec!["missing.txt".to_string()]).ok().unwrap().is_empty());
+        // the path git would quote without -z
+        let quoted = commit_file(&path, "dir/\"é\".txt", "q", "add quoted");
+        let res = get_files_last_commit(path.to_string(), "main".to_string(), vec!["dir/\"é\".txt".to_string()]).ok().unwrap();
+        assert_eq!(res.get("dir/\"é\".txt").unwrap().hash, quoted);
+    }
+
+    #[test]
+    fn test_get_repository_info_full() {
+        let path = init_test_repo("repository_info_full");
+        commit_file(&path, "a.txt", "a", "init");
+        exec_git(&path, &["branch", "feature"]);
+        let res = get_repository_info_full(path.to_string()).ok().unwrap();
+        assert_eq!(res.current_branch, "main");
+        assert_eq!(res.branches, vec!["feature", "main"]);
+        assert_eq!(res.authors.len(), 1);
+        assert!(res.remote.is_empty());
+        exec_git(&path, &["checkout", "-q", "--detach"]);
+        let res = get_repository_info_simple(path.to_string()).ok().unwrap();
+        assert_eq!(res.current_branch, exec_git(&path, &["rev-parse", "--short=7", "HEAD"]));
+        assert_eq!(res.current_branch, get_current_branch(path.to_string()).ok().unwrap());
+    }
+
+    #[test]
+    fn test_get_contribute_stat_aligned() {
+        let path = init_test_repo("contribute_aligned");
+        commit_file_at(&path, "a.txt", "1\n", "day 1", "2024-01-01T10:00:00+00:00");
+        commit_file_at(&path, "a.txt", "1\n2\n", "day 1 again", "2024-01-01T11:00:00+00:00");
+        commit_file_at(&path, "b.txt", "1\n", "day 2", "2024-01-02T10:00:00+00:00");
+        commit_file_at(&path, "c.txt", "1\n", "day 3", "2024-01-03T10:00:00+00:00");
+        let res = get_contribute_stat(path.to_string(), "main".to_string(), None, None).ok().unwrap();
+        let stats = res.authors_stat.iter().map(|a| &a.stat).chain(std::iter::once(&res.total_stat));
+        for stat in stats {
+            assert_eq!(stat.date_list.len(), 3);
+            assert_eq!(stat.date_list.len(), stat.commit_count.len());
+            assert_eq!(stat.date_list.len(), stat.insertion.len());
+            assert_eq!(stat.date_list.len(), stat.deletions.len());
+            assert_eq!(stat.date_list.len(), stat.change_files.len());
+        }
+    }
+
+    #[test]
+    fn test_get_contribute_stat_same_day_sum() {
+        let path = init_test_repo("contribute_same_day");
+        commit_file_at(&path, "a.txt", "1\n2\n", "first", "2024-01-01T10:00:00+00:00");
+        commit_file_at(&path, "b.txt", "1\n2\n3\n", "second", "2024-01-01T11:00:00+00:00");
+        commit_file_at(&path, "a.txt", "1\n", "third", "2024-01-01T12:00:00+00:00");
+        let res = get_contribute_stat(path.to_string(), "main".to_string(), None, None).ok().unwrap();
+        for stat in [&res.total_stat, &res.authors_stat[0].stat] {
+            assert_eq!(stat.date_list, vec!["2024-01-01"]);
+            assert_eq!(stat.commit_count, vec![3]);
+            assert_eq!(stat.insertion, vec![5]);
+            assert_eq!(stat.deletions, vec![1]);
+            assert_eq!(stat.change_files, vec![3]);
+        }
+    }
+
+    #[test]
+    fn test_get_contribute_stat_binary_and_mode_only() {
+        let path = init_test_repo("contribute_binary");
+        commit_file_at(&path, "a.sh", "echo 1\n", "text", "2024-01-01T10:00:00+00:00");
+        std::fs::write(std::path::Path::new(&path).join("image.png"), [0u8, 159, 146, 150, 0, 1]).unwrap();
+        exec_git(&path, &["add", "image.png"]);
+        exec_git(&path, &["commit", "-q", "-m", "binary only"]);
+        exec_git(&path, &["update-index", "--chmod=+x", "a.sh"]);
+        exec_git(&path, &["commit", "-q", "-m", "mode only"]);
+        exec_git(&path, &["commit", "-q", "--allow-empty", "-m", "empty"]);
+        let res = get_contribute_stat(path.to_string(), "main".to_string(), None, None).ok().unwrap();
+        assert_eq!(res.total_stat.commit_count.iter().sum::<i32>(), 4);
+        assert_eq!(res.total_stat.insertion.iter().sum::<i32>(), 1);
+    }
+
+    #[test]
+    fn test_get_contribute_stat_by_email() {
+        let path = init_test_repo("contribute_by_email");
+        commit_file(&path, "a.txt", "1", "tester");
+        for (i, author) in ["tester <tester@other.com>", "Tester Renamed <tester@example.com>"].iter().enumerate() {
+            std::fs::write(std::path::Path::new(&path).join("a.txt"), i.to_string() + "x").unwrap();
+            exec_git(&path, &["commit", "-q", "-a", "-m", "change", "--author", author]);
+        }
+        let res = get_contribute_stat(path.to_string(), "main".to_string(), None, None).ok().unwrap();
+        assert_eq!(res.authors_stat.len(), 2);
+        let res = get_contribute_stat_by_email(path.to_string(), "main".to_string(), None).ok().unwrap();
+        assert_eq!(res.authors_stat.len(), 2);
+        let stat = res.authors_stat.iter().find(|a| a.author.email == "tester@example.com").unwrap();
+        assert_eq!(stat.stat.commit_count.iter().sum::<i32>(), 2);
+        let identity_map = HashMap::from([("tester@other.com".to_string(), "tester@example.com".to_string())]);
+        let res = get_contribute_stat_by_email(path.to_string(), "main".to_string(), Some(identity_map)).ok().unwrap();
+        assert_eq!(res.authors_stat.len(), 1);
+        assert_eq!(res.authors_stat[0].stat.commit_count.iter().sum::<i32>(), 3);
+    }
+
+    #[test]
+    fn test_get_file_diff_hunks() {
+        let path = init_test_repo("diff_hunks");
+        let content = (1..=20).map(|i| format!("line {}\n", i)).collect::<String>();
+        let first = commit_file(&path, "a.txt", &content, "first");
+        let changed = content.replace("line 2\n", "line two\n").replace("line 18\n", "").replace("line 20\n", "line 20\nline 21\n");
+        let second = commit_file(&path, "a.txt", &changed, "second");
+        let res = get_file_diff_hunks(path.to_string(), first.to_string(), second.to_string(), "a.txt".to_string(), Some(1), None).ok().unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!((res[0].old_start, res[0].old_lines, res[0].new_start, res[0].new_lines), (1, 3, 1, 3));
+        let origins = res[0].lines.iter().map(|l| &l.origin).collect::<Vec<&DiffLineOrigin>>();
+        assert_eq!(origins, vec![&DiffLineOrigin::Context, &DiffLineOrigin::Deletion, &DiffLineOrigin::Addition, &DiffLineOrigin::Context]);
+        assert_eq!(res[0].lines[1].content, "line 2");
+        assert_eq!(res[0].lines[2].content, "line two");
+        assert_eq!(res[1].lines.iter().filter(|l| l.origin == DiffLineOrigin::Addition).count(), 1);
+        assert_eq!(res[1].lines.iter().filter(|l| l.origin == DiffLineOrigin::Deletion).count(), 1);
+    }
+
+    #[test]
+    fn test_get_files_diff_context_binary() {
+        let path = init_test_repo("diff_context_binary");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        std::fs::write(std::path::Path::new(&path).join("image.png"), [137u8, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 255, 254]).unwrap();
+        exec_git(&path, &["add", "image.png"]);
+        exec_git(&path, &["commit", "-q", "-m", "add image"]);
+        let res = get_files_diff_context(path.to_string(), first.to_string(), "HEAD".to_string(), None, None, None).ok().unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].context2, "Binary file");
+        assert_eq!(res[0].change_stat.addition, 0);
+    }
+
+    #[test]
+    fn test_get_file_blob() {
+        let path = init_test_repo("file_blob");
+        let bytes = [137u8, 80, 78, 71, 0, 255, 254, 10, 13];
+        std::fs::write(std::path::Path::new(&path).join("image.png"), bytes).unwrap();
+        exec_git(&path, &["add", "image.png"]);
+        exec_git(&path, &["commit", "-q", "-m", "add image"]);
+        assert_eq!(get_file_blob(&path, "HEAD", "image.png").unwrap(), bytes.to_vec());
+        assert!(get_file_blob(&path, "HEAD", "missing.png").is_err());
+    }
+
+    #[test]
+    fn test_get_stash_list_and_diff() {
+        let path = init_test_repo("stash");
+        commit_file(&path, "a.txt", "a\n", "init");
+        assert_eq!(get_stash_list(path.clone()).ok().unwrap().len(), 0);
+        std::fs::write(std::path::Path::new(&path).join("a.txt"), "a\nb\n").unwrap();
+        exec_git(&path, &["stash", "push", "-m", "first change"]);
+        std::fs::write(std::path::Path::new(&path).join("b.txt"), "b\n").unwrap();
+        exec_git(&path, &["add", "b.txt"]);
+        exec_git(&path, &["stash"]);
+        let list = get_stash_list(path.clone()).ok().unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].index, 0);
+        assert_eq!(list[0].branch, "main");
+        assert!(list[0].message.ends_with("init"));
+        assert_eq!(list[1].index, 1);
+        assert_eq!(list[1].message, "first change");
+        assert_eq!(list[1].hash, exec_git(&path, &["rev-parse", "stash@{1}"]));
+        let diff = get_stash_diff(path.clone(), 0).ok().unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].path, "b.txt");
+        assert_eq!(diff[0].status, FileStatusType::Added);
+        let diff = get_stash_diff(path.clone(), 1).ok().unwrap();
+        assert_eq!(diff[0].path, "a.txt");
+        assert_eq!(diff[0].status, FileStatusType::Modified);
+        assert!(get_stash_diff(path, 5).is_err());
+    }
+
+    #[test]
+    fn test_get_tags_detail() {
+        let path = init_test_repo("tags_detail");
+        let first = commit_file_at(&path, "a.txt", "a\n", "first", "2024-01-01T10:00:00+00:00");
+        exec_git(&path, &["tag", "v0.1"]);
+        let second = commit_file_at(&path, "a.txt", "b\n", "second", "2024-02-01T10:00:00+00:00");
+        let output = Command::new("git").current_dir(&path).args(["tag", "-a", "v0.2", "-m", "release 0.2\n\nnotes"])
+            .env("GIT_COMMITTER_DATE", "2024-03-01T10:00:00+00:00").output().unwrap();
+        assert!(output.status.success());
+        let tags = get_tags_detail(path.clone()).ok().unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].name, "v0.2");
+        assert!(tags[0].is_annotated);
+        assert_eq!(tags[0].target_hash, second);
+        assert_eq!(tags[0].tagger_name, "tester");
+        assert_eq!(tags[0].tagger_email, "tester@example.com");
+        assert_eq!(tags[0].date, "1709287200");
+        assert_eq!(tags[0].message, "release 0.2\n\nnotes");
+        assert_eq!(tags[1].name, "v0.1");
+        assert!(!tags[1].is_annotated);
+        assert_eq!(tags[1].target_hash, first);
+        assert_eq!(tags[1].tagger_name, "");
+        assert_eq!(tags[1].message, "");
+        assert_eq!(tags[1].date, "1704103200");
+        // the separator bytes are kept in the annotation
+        exec_git(&path, &["tag", "-a", "v0.3", "-m", "odd \x1f subject\n\nbody \x1e lines"]);
+        let tags = get_tags_detail(path.clone()).ok().unwrap();
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags.iter().find(|tag| tag.name == "v0.3").unwrap().message, "odd \x1f subject\n\nbody \x1e lines");
+    }
+
+    #[test]
+    fn test_resolve_ref() {
+        let path = init_test_repo("resolve_ref");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        let second = commit_file(&path, "a.txt", "b\n", "second");
+        exec_git(&path, &["tag", "-a", "v1", "-m", "annotated", &first]);
+        assert_eq!(resolve_ref(path.clone(), "main".to_string()).ok().unwrap(), second);
+        assert_eq!(resolve_ref(path.clone(), second[..8].to_string()).ok().unwrap(), second);
+        assert_eq!(resolve_ref(path.clone(), "HEAD^".to_string()).ok().unwrap(), first);
+        // an annotated tag resolves to the commit, not the tag object
+        assert_eq!(resolve_ref(path.clone(), "v1".to_string()).ok().unwrap(), first);
+        assert!(resolve_ref(path.clone(), "missing".to_string()).is_err());
+        assert!(resolve_ref(path, "--all".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_get_ahead_behind() {
+        let path = init_test_repo("ahead_behind");
+        commit_file(&path, "a.txt", "a\n", "init");
+        assert!(get_ahead_behind(path.clone(), "main".to_string(), None).ok().unwrap().is_none());
+        exec_git(&path, &["branch", "base"]);
+        exec_git(&path, &["branch", "--set-upstream-to=base", "main"]);
+        commit_file(&path, "a.txt", "b\n", "ahead 1");
+        commit_file(&path, "a.txt", "c\n", "ahead 2");
+        exec_git(&path, &["checkout", "-q", "base"]);
+        commit_file(&path, "b.txt", "b\n", "behind 1");
+        let result = get_ahead_behind(path.clone(), "main".to_string(), None).ok().unwrap().unwrap();
+        assert_eq!(result.upstream, "base");
+        assert_eq!(result.ahead, 2);
+        assert_eq!(result.behind, 1);
+        let result = get_ahead_behind(path.clone(), "base".to_string(), Some("main".to_string())).ok().unwrap().unwrap();
+        assert_eq!(result.ahead, 1);
+        assert_eq!(result.behind, 2);
+        assert!(get_ahead_behind(path, "main".to_string(), Some("missing".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_get_working_tree_status_and_stat() {
+        let path = init_test_repo("working_tree_status");
+        commit_file(&path, "a.txt", "a\n", "init");
+        commit_file(&path, "b.txt", "b\n", "add b");
+        commit_file(&path, "old name.txt", "line1\nline2\nline3\n", "add old");
+        let root = std::path::Path::new(&path);
+        std::fs::write(root.join("a.txt"), "a\nstaged\n").unwrap();
+        exec_git(&path, &["add", "a.txt"]);
+        std::fs::write(root.join("a.txt"), "a\nstaged\nunstaged\n").unwrap();
+        std::fs::remove_file(root.join("b.txt")).unwrap();
+        exec_git(&path, &["mv", "old name.txt", "new name.txt"]);
+        std::fs::write(root.join("untracked.txt"), "u\n").unwrap();
+        let mut status = get_working_tree_status(path.clone()).ok().unwrap();
+        status.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(status.len(), 4);
+        assert_eq!(status[0].path, "a.txt");
+        assert_eq!(status[0].status, FileStatusType::Modified);
+        assert!(status[0].staged && status[0].unstaged && !status[0].untracked);
+        assert_eq!(status[1].path, "b.txt");
+        assert_eq!(status[1].status, FileStatusType::Deleted);
+        assert!(!status[1].staged && status[1].unstaged);
+        assert_eq!(status[2].path, "new name.txt");
+        assert_eq!(status[2].status, FileStatusType::Renamed);
+        assert_eq!(status[2].message, "old name.txt => new name.txt");
+        assert!(status[2].staged && !status[2].unstaged);
+        assert_eq!(status[3].path, "untracked.txt");
+        assert!(status[3].untracked && !status[3].staged && !status[3].unstaged);
+        let stat = get_working_tree_stat(path.clone()).ok().unwrap();
+        assert_eq!(stat.staged_insertions, 1);
+        assert_eq!(stat.staged_deletions, 0);
+        assert_eq!(stat.unstaged_files, 2);
+        assert_eq!(stat.unstaged_insertions, 1);
+        assert_eq!(stat.unstaged_deletions, 1);
+        exec_git(&path, &["stash", "-u"]);
+        assert_eq!(get_working_tree_status(path.clone()).ok().unwrap().len(), 0);
+        assert_eq!(get_working_tree_stat(path).ok().unwrap().staged_files, 0);
+    }
+
+    #[test]
+    fn test_detect_content_kind() {
+        let utf16le = "fn main() {}\r\n".encode_utf16().flat_map(|u| u.to_le_bytes()).collect::<Vec<u8>>();
+        let utf16be = "fn main() {}\r\n".encode_utf16().flat_map(|u| u.to_be_bytes()).collect::<Vec<u8>>();
+        let mut utf16le_bom = vec![0xFF, 0xFE];
+        utf16le_bom.extend(&utf16le);
+        assert_eq!(content_kind(b"fn main() {}\n"), ContentKind::Utf8Text);
+        assert_eq!(content_kind("中文\n".as_bytes()), ContentKind::Utf8Text);
+        assert_eq!(content_kind(b""), ContentKind::Utf8Text);
+        assert_eq!(content_kind(&utf16le), ContentKind::Utf16Text);
+        assert_eq!(content_kind(&utf16be), ContentKind::Utf16Text);
+        assert_eq!(content_kind(&utf16le_bom), ContentKind::Utf16Text);
+        assert_eq!(content_kind(&[137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13]), ContentKind::Binary);
+        assert_eq!(content_kind(&[1, 2, 3, 4, 5, 6, 7, 65, 66, 67]), ContentKind::Binary);
+        assert_eq!(decode_text(&utf16le_bom), "fn main() {}\r\n");
+        assert_eq!(decode_text(&utf16be), "fn main() {}\r\n");
+    }
+
+    #[test]
+    fn test_get_files_diff_context_utf16() {
+        let path = init_test_repo("diff_utf16");
+        let first = commit_file(&path, "readme.txt", "readme\n", "init");
+        let mut content = vec![0xFF, 0xFE];
+        content.extend("line1\r\nline2\r\n".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        std::fs::write(std::path::Path::new(&path).join("source.cs"), content).unwrap();
+        exec_git(&path, &["add", "source.cs"]);
+        exec_git(&path, &["commit", "-q", "-m", "add utf16"]);
+        let diff = get_files_diff_context(path.clone(), first, "HEAD".to_string(), None, None, None).ok().unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].context2, "line1\r\nline2\r\n");
+        assert_eq!(diff[0].change_stat.addition, 2);
+        assert_eq!(get_file_content(path, "HEAD".to_string(), "source.cs".to_string()).ok().unwrap(), "line1\r\nline2\r\n");
+    }
+
+    #[test]
+    fn test_get_current_branch_info_detached() {
+        let path = init_test_repo("detached_head");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "a.txt", "b\n", "second");
+        let branch = get_current_branch_info(path.clone()).ok().unwrap();
+        assert_eq!(branch.name, "main");
+        assert!(!branch.is_detached);
+        assert_eq!(branch.created.hash, first);
+        exec_git(&path, &["tag", "v1", &first]);
+        exec_git(&path, &["checkout", "-q", "v1"]);
+        let branch = get_current_branch_info(path.clone()).ok().unwrap();
+        assert!(branch.is_detached);
+        assert_eq!(branch.commit_hash, first);
+        assert_eq!(branch.name, &first[..7]);
+        assert_eq!(branch.authors.len(), 1);
+        assert_eq!(get_current_branch(path.clone()).ok().unwrap(), &first[..7]);
+        assert_eq!(get_branches(path.clone()).ok().unwrap(), vec!["main".to_string()]);
+        // the detached commit is pushed when a remote branch contains it
+        assert_eq!(get_status(path.clone()).ok().unwrap(), RepoStatus::UnPush);
+        exec_git(&path, &["update-ref", "refs/remotes/origin/main", "main"]);
+        assert_eq!(get_status(path).ok().unwrap(), RepoStatus::Ok);
+        let unborn = init_test_repo("detached_head_unborn");
+        assert_eq!(get_current_branch(unborn).ok().unwrap(), "main");
+    }
+
+    #[test]
+    fn test_not_a_repository_error() {
+        let dir = std::env::temp_dir().join(format!("git-util-native-not-a-repo-{}", get_current_time()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_str().unwrap().to_string();
+        assert!(ensure_git_repository(&path).is_err());
+        assert!(ensure_git_repository(&(path.to_string() + "/missing")).is_err());
+        assert!(get_branches(path.clone()).is_err());
+        assert!(is_commited(path.clone()).is_err());
+        assert!(get_commit_log_format(path, vec!["%H".to_string()], "".to_string(), "".to_string(), None).is_err());
+        let repo = init_test_repo("is_a_repo");
+        assert!(ensure_git_repository(&repo).is_ok());
+        std::fs::create_dir_all(std::path::Path::new(&repo).join("sub")).unwrap();
+        assert!(ensure_git_repository(&(repo + "/sub")).is_ok());
+    }
+
+    #[test]
+    fn test_is_git_repository_bare() {
+        let path = init_test_repo("is_repo");
+        assert!(is_git_repository(path.clone()));
+        let bare = path.to_string() + "-bare";
+        exec_git(&path, &["init", "-q", "--bare", &bare]);
+        assert!(is_git_repository(bare));
+        let dir = std::env::temp_dir().join(format!("git-util-native-plain-dir-{}", get_current_time()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(!is_git_repository(dir.to_str().unwrap().to_string()));
+    }
+
+    #[test]
+    fn test_bare_repository_read_only() {
+        let path = init_test_repo("bare_source");
+        commit_file(&path, "src/a.txt", "a\n", "first");
+        commit_file(&path, "b.txt", "b\n", "second");
+        exec_git(&path, &["tag", "v1"]);
+        let bare = path.to_string() + "-bare";
+        exec_git(&path, &["clone", "-q", "--bare", &path, &bare]);
+        assert!(is_bare_repository(bare.clone()));
+        assert!(!is_bare_repository(path.clone()));
+        assert_eq!(get_branches(bare.clone()).ok().unwrap(), vec!["main".to_string()]);
+        assert_eq!(get_current_branch(bare.clone()).ok().unwrap(), "main");
+        assert_eq!(get_tags(bare.clone()).ok().unwrap(), vec!["v1".to_string()]);
+        assert_eq!(get_commit_log_format(bare.clone(), vec!["%s".to_string()], "".to_string(), "main".to_string(), None).ok().unwrap().len(), 2);
+        assert_eq!(get_repo_file_list(bare.clone(), "main".to_string()).ok().unwrap().len(), 2);
+        assert_eq!(get_file_content(bare.clone(), "main".to_string(), "src/a.txt".to_string()).ok().unwrap(), "a\n");
+        assert_eq!(get_repository_info_simple(bare.clone()).ok().unwrap().current_branch, "main");
+        assert!(get_contribute_stat(bare.clone(), "main".to_string(), None, None).is_ok());
+        assert!(get_files_diff_context(bare.clone(), "main^".to_string(), "main".to_string(), None, None, None).is_ok());
+        assert!(get_working_tree_status(bare.clone()).is_err());
+        assert!(is_commited(bare).is_err());
+    }
+
+    #[test]
+    fn test_cat_file_batch() {
+        let path = init_test_repo("cat_file_batch");
+        commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "dir/b c.txt", "", "second");
+        std::fs::write(std::path::Path::new(&path).join("bin"), [0u8, 1, 2, 10]).unwrap();
+        exec_git(&path, &["add", "bin"]);
+        exec_git(&path, &["commit", "-q", "-m", "third"]);
+        let mut batch = CatFileBatch::new(&path).unwrap();
+        assert_eq!(batch.read_blob("HEAD", "a.txt").unwrap(), b"a\n".to_vec());
+        assert!(batch.read_blob("HEAD", "missing file.txt").is_err());
+        assert_eq!(batch.read_blob("HEAD", "dir/b c.txt").unwrap(), Vec::<u8>::new());
+        assert_eq!(batch.read_blob("HEAD", "bin").unwrap(), vec![0u8, 1, 2, 10]);
+        assert!(batch.read_blob("HEAD~2", "bin").is_err());
+        assert_eq!(batch.read_blob("HEAD~2", "a.txt").unwrap(), get_file_blob(&path, "HEAD~2", "a.txt").unwrap());
+    }
+
+    #[test]
+    fn test_diff_file_context_modified_stat() {
+        let path = init_test_repo("diff_file_context_stat");
+        let first = commit_file(&path, "a.txt", "1\n2\n", "first");
+        let second = commit_file(&path, "a.txt", "1\n2\n3\n", "only insertions");
+        let third = commit_file(&path, "a.txt", "1\n", "only deletions");
+        let fourth = commit_file(&path, "a.txt", "x\ny\n", "both");
+        let stat = diff_file_context(path.clone(), first, second.to_string(), "a.txt".to_string(), None).ok().unwrap().change_stat;
+        assert_eq!((stat.addition, stat.deletion), (1, 0));
+        let stat = diff_file_context(path.clone(), second, third.to_string(), "a.txt".to_string(), None).ok().unwrap().change_stat;
+        assert_eq!((stat.addition, stat.deletion), (0, 2));
+        let stat = diff_file_context(path, third, fourth, "a.txt".to_string(), None).ok().unwrap().change_stat;
+        assert_eq!((stat.addition, stat.deletion), (2, 1));
+    }
+
+    #[test]
+    fn test_get_files_status_between_commit_rename_options() {
+        let path = init_test_repo("rename_options");
+        let content = (0..20).map(|i| format!("line {}\n", i)).collect::<String>();
+        commit_file(&path, "keep.txt", &content, "keep");
+        let first = commit_file(&path, "src/old.txt", &content.replace("line", "text"), "first");
+        exec_git(&path, &["mv", "src/old.txt", "src/new.txt"]);
+        std::fs::write(std::path::Path::new(&path).join("src/new.txt"), content.replace("line", "text").replace("text 1\n", "changed\n").replace("text 2\n", "changed\n")).unwrap();
+        std::fs::write(std::path::Path::new(&path).join("copy.txt"), &content).unwrap();
+        exec_git(&path, &["add", "-A"]);
+        exec_git(&path, &["commit", "-q", "-m", "rename and copy"]);
+        let status = get_files_status_between_commit(path.clone(), first.to_string(), "HEAD".to_string(), None, None, None).ok().unwrap();
+        let renamed = status.iter().find(|s| s.status == FileStatusType::Renamed).unwrap();
+        assert_eq!(renamed.message, "src/old.txt => src/new.txt");
+        assert_eq!(status.iter().find(|s| s.path == "copy.txt").unwrap().status, FileStatusType::Added);
+        // a strict threshold treats the modified rename as a deletion and an addition
+        let strict = Some(RenameOptions { find_renames: Some(95), ..Default::default() });
+        let status = get_files_status_between_commit(path.clone(), first.to_string(), "HEAD".to_string(), strict, None, None).ok().unwrap();
+        assert!(status.iter().all(|s| s.status != FileStatusType::Renamed));
+        // the source of the copy is not modified, it is only found with --find-copies-harder
+        let copies = Some(RenameOptions { find_copies_harder: Some(true), ..Default::default() });
+        let status = get_files_status_between_commit(path.clone(), first.to_string(), "HEAD".to_string(), copies, None, None).ok().unwrap();
+        let copied = status.iter().find(|s| s.status == FileStatusType::Copied).unwrap();
+        assert_eq!(copied.message, "keep.txt => copy.txt");
+        assert_eq!(rename_option_args(&Some(RenameOptions { find_renames: Some(30), find_copies: Some(60), find_copies_harder: None })).ok().unwrap(), vec!["-M30%", "-C60%"]);
+        assert!(rename_option_args(&Some(RenameOptions { find_renames: Some(101), ..Default::default() })).is_err());
+        assert!(rename_option_args(&Some(RenameOptions { find_copies: Some(-1), ..Default::default() })).is_err());
+        // the copy is read from its source like a rename
+        let copies = Some(RenameOptions { find_copies_harder: Some(true), ..Default::default() });
+        let contexts = get_files_diff_context(path.clone(), first.to_string(), "HEAD".to_string(), copies, None, None).ok().unwrap();
+        let copied = contexts.iter().find(|c| c.file_status == FileStatusType::Copied).unwrap();
+        assert_eq!(copied.context1, content);
+        assert_eq!(copied.context2, content);
+        assert_eq!((copied.change_stat.addition, copied.change_stat.deletion), (0, 0));
+        let renamed = contexts.iter().find(|c| c.file_status == FileStatusType::Renamed).unwrap();
+        assert_eq!((renamed.change_stat.addition, renamed.change_stat.deletion), (2, 2));
+        // the changed lines are counted with the same pathspec as the files
+        let contexts = get_files_diff_context(path.clone(), first.to_string(), "HEAD".to_string(), None, None, Some(vec!["src/".to_string()])).ok().unwrap();
+        assert_eq!(contexts.len(), 1);
+        assert_eq!((contexts[0].change_stat.addition, contexts[0].change_stat.deletion), (2, 2));
+        assert!(get_files_diff_context(path.clone(), first.to_string(), "HEAD".to_string(), Some(RenameOptions { find_renames: Some(150), ..Default::default() }), None, None).is_err());
+    }
+
+    #[test]
+    fn test_get_file_history() {
+        let path = init_test_repo("file_history");
+        let content = (0..20).map(|i| format!("line {}\n", i)).collect::<String>();
+        let first = commit_file(&path, "old.txt", &content, "first");
+        commit_file(&path, "other.txt", "other\n", "other");
+        exec_git(&path, &["mv", "old.txt", "new.txt"]);
+        exec_git(&path, &["commit", "-q", "-m", "rename"]);
+        let rename = exec_git(&path, &["rev-parse", "HEAD"]);
+        let last = commit_file(&path, "new.txt", &(content.to_string() + "end\n"), "last");
+        let placeholders = vec!["%H".to_string(), "%s".to_string()];
+        let history = get_file_history(path.clone(), "new.txt".to_string(), true, placeholders.clone()).ok().unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0]["hashL"], last);
+        assert_eq!(history[0]["filePath"], "new.txt");
+        assert_eq!(history[1]["hashL"], rename);
+        assert_eq!(history[1]["oldFilePath"], "old.txt");
+        assert_eq!(history[1]["filePath"], "new.txt");
+        assert_eq!(history[2]["hashL"], first);
+        assert_eq!(history[2]["message"], "first");
+        assert_eq!(history[2]["filePath"], "old.txt");
+        assert!(!history[2].contains_key("oldFilePath"));
+        let history = get_file_history(path.clone(), "new.txt".to_string(), false, placeholders).ok().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(get_file_history(path, "new.txt".to_string(), false, vec![]).ok().unwrap()[0]["filePath"], "new.txt");
+    }
+
+    #[test]
+    fn test_diff_worktree_file_context() {
+        let path = init_test_repo("diff_worktree");
+        commit_file(&path, "a.txt", "1\n2\n", "first");
+        commit_file(&path, "b.txt", "b1\nb2\nb3\n", "second");
+        let root = std::path::Path::new(&path);
+        std::fs::write(root.join("a.txt"), "1\nchanged\n3\n").unwrap();
+        std::fs::remove_file(root.join("b.txt")).unwrap();
+        std::fs::write(root.join("new.txt"), "n\n").unwrap();
+        let diff = diff_worktree_file_context(path.clone(), "HEAD".to_string(), "a.txt".to_string()).ok().unwrap();
+        assert_eq!(diff.file_status, FileStatusType::Modified);
+        assert_eq!(diff.context1, "1\n2\n");
+        assert_eq!(diff.context2, "1\nchanged\n3\n");
+        assert_eq!((diff.change_stat.addition, diff.change_stat.deletion), (2, 1));
+        let diff = diff_worktree_file_context(path.clone(), "HEAD".to_string(), "b.txt".to_string()).ok().unwrap();
+        assert_eq!(diff.file_status, FileStatusType::Deleted);
+        assert_eq!(diff.change_stat.deletion, 3);
+        let diff = diff_worktree_file_context(path.clone(), "HEAD".to_string(), "new.txt".to_string()).ok().unwrap();
+        assert_eq!(diff.file_status, FileStatusType::Added);
+        assert_eq!(diff.context2, "n\n");
+        assert_eq!(diff.change_stat.addition, 1);
+        let diff = diff_worktree_file_context(path.clone(), "HEAD~1".to_string(), "a.txt".to_string()).ok().unwrap();
+        assert_eq!(diff.change_stat.deletion, 1);
+        // b.txt is neither in the first commit nor on the disk
+        assert!(diff_worktree_file_context(path, "HEAD~1".to_string(), "b.txt".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_get_commit_graph() {
+        let path = init_test_repo("commit_graph");
+        let root = commit_file(&path, "a.txt", "a\n", "root");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        let feature = commit_file(&path, "b.txt", "b\n", "feature");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let main = commit_file(&path, "c.txt", "c\n", "main");
+        exec_git(&path, &["merge", "-q", "--no-ff", "-m", "merge", "feature"]);
+        exec_git(&path, &["tag", "v1"]);
+        let merge = exec_git(&path, &["rev-parse", "HEAD"]);
+        let graph = get_commit_graph(path.clone(), "".to_string(), None).ok().unwrap();
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph[0].hash, merge);
+        assert_eq!(graph[0].parents, vec![main.to_string(), feature.to_string()]);
+        assert_eq!(graph[0].refs, vec!["HEAD -> main".to_string(), "tag: v1".to_string()]);
+        assert_eq!(graph[0].decorations, CommitDecorations { branches: vec!["main".to_string()], tags: vec!["v1".to_string()], head: true });
+        assert_eq!(graph[3].hash, root);
+        assert!(graph[3].parents.is_empty());
+        let feature_node = graph.iter().find(|node| node.hash == feature).unwrap();
+        assert_eq!(feature_node.refs, vec!["feature".to_string()]);
+        assert_eq!(get_commit_graph(path.clone(), "feature".to_string(), None).ok().unwrap().len(), 2);
+        assert_eq!(get_commit_graph(path, "".to_string(), Some(1)).ok().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_submodules() {
+        let lib = init_test_repo("submodule_lib");
+        let lib_first = commit_file(&lib, "lib.txt", "1\n", "first");
+        let path = init_test_repo("submodule_super");
+        commit_file(&path, "a.txt", "a\n", "init");
+        assert_eq!(get_submodules(path.clone()).ok().unwrap().len(), 0);
+        exec_git(&path, &["-c", "protocol.file.allow=always", "submodule", "add", "-q", "--name", "my.lib", &lib, "deps/lib"]);
+        exec_git(&path, &["commit", "-q", "-m", "add submodule"]);
+        let submodules = get_submodules(path.clone()).ok().unwrap();
+        assert_eq!(submodules.len(), 1);
+        assert_eq!(submodules[0].name, "my.lib");
+        assert_eq!(submodules[0].path, "deps/lib");
+        assert_eq!(submodules[0].url, lib);
+        assert_eq!(submodules[0].current_hash, lib_first);
+        assert_eq!(submodules[0].status, SubmoduleStatus::Clean);
+        let checkout = path.to_string() + "/deps/lib";
+        exec_git(&checkout, &["config", "user.name", "tester"]);
+        exec_git(&checkout, &["config", "user.email", "tester@example.com"]);
+        let lib_second = commit_file(&checkout, "lib.txt", "2\n", "second");
+        let submodules = get_submodules(path.clone()).ok().unwrap();
+        assert_eq!(submodules[0].status, SubmoduleStatus::OutOfSync);
+        assert_eq!(submodules[0].current_hash, lib_second);
+        let clone = path.to_string() + "-clone";
+        exec_git(&path, &["clone", "-q", &path, &clone]);
+        let submodules = get_submodules(clone).ok().unwrap();
+        assert_eq!(submodules[0].status, SubmoduleStatus::Uninitialized);
+        assert_eq!(submodules[0].current_hash, lib_first);
+    }
+
+    #[test]
+    fn test_get_commit_signature() {
+        let path = init_test_repo("commit_signature");
+        let hash = commit_file(&path, "a.txt", "a\n", "unsigned");
+        let signature = get_commit_signature(path.clone(), hash).ok().unwrap();
+        assert!(!signature.signed);
+        assert_eq!(signature.status, SignatureStatus::None);
+        assert_eq!(signature.signer, "");
+        let log = get_commit_log_format(path.clone(), vec!["%G?".to_string(), "%GS".to_string()], "".to_string(), "".to_string(), None).ok().unwrap();
+        assert_eq!(log[0]["signatureStatus"], "N");
+        assert!(get_commit_signature(path, "missing".to_string()).is_err());
+        assert_eq!(parse_signature_status("G"), SignatureStatus::Good);
+        assert_eq!(parse_signature_status("B"), SignatureStatus::Bad);
+        assert_eq!(parse_signature_status("E"), SignatureStatus::Unknown);
+        assert_eq!(parse_signature_status("X"), SignatureStatus::Unknown);
+    }
+
+    #[test]
+    fn test_get_commit_log_format_date_placeholders() {
+        let path = init_test_repo("date_placeholders");
+        commit_file_at(&path, "a.txt", "a\n", "first", "2024-01-02T03:04:05+00:00");
+        let placeholders = ["%ai", "%as", "%ah", "%ch", "%cs"].iter().map(|p| p.to_string()).collect::<Vec<String>>();
+        let log = get_commit_log_format(path, placeholders, "".to_string(), "".to_string(), None).ok().unwrap();
+        assert_eq!(log[0]["dateIso"], "2024-01-02 03:04:05 +0000");
+        assert_eq!(log[0]["dateYMD"], "2024-01-02");
+        assert!(!log[0]["dateHuman"].is_empty());
+        assert!(!log[0]["committerDateHuman"].is_empty());
+        assert_eq!(log[0]["committerDateYMD"], "2024-01-02");
+    }
+
+    #[test]
+    fn test_unknown_placeholder() {
+        let path = init_test_repo("unknown_placeholder");
+        commit_file(&path, "a.txt", "a\n", "first");
+        let placeholders = vec!["%H".to_string(), "%xz".to_string()];
+        assert!(get_placeholder_keys(&placeholders).is_err());
+        assert_eq!(get_placeholder_keys(&vec!["%H".to_string(), "%s".to_string()]).ok().unwrap(), vec!["hashL", "message"]);
+        assert!(get_commit_log_format(path.clone(), placeholders.clone(), "".to_string(), "".to_string(), None).is_err());
+        assert!(get_commit_format(path.clone(), "HEAD".to_string(), placeholders.clone()).is_err());
+        assert!(get_file_history(path, "a.txt".to_string(), false, placeholders).is_err());
+    }
+
+    #[test]
+    fn test_log_with_sentinel_in_message() {
+        let path = init_test_repo("sentinel_in_message");
+        let message = "subject <<PARAM_INTERVAL>> text\n\nbody <<COMMIT_INETRVAL>> text";
+        let hash = commit_file(&path, "a.txt", "a\n", message);
+        commit_file(&path, "b.txt", "b\n", "second");
+        let placeholders = vec!["%s".to_string(), "%b".to_string(), "%H".to_string()];
+        let log = get_commit_log_format(path, placeholders, "".to_string(), "".to_string(), None).ok().unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1]["message"], "subject <<PARAM_INTERVAL>> text");
+        assert_eq!(log[1]["body"], "body <<COMMIT_INETRVAL>> text");
+        assert_eq!(log[1]["hashL"], hash);
+    }
+
+    #[test]
+    fn test_stream_commit_log_format() {
+        let path = init_test_repo("stream_commit_log");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "b.txt", "b\n", "second");
+        let third = commit_file(&path, "c.txt", "c\n", "third");
+        let placeholders = vec!["%H".to_string(), "%s".to_string()];
+        let keys = get_placeholder_keys(&placeholders).ok().unwrap();
+        let format = commit_log_pretty_format(&placeholders);
+        let mut batches = Vec::new();
+        stream_commit_log_format(&path, &keys, &format, vec!["HEAD".to_string()], 2, |batch| batches.push(batch)).unwrap();
+        assert_eq!(batches.iter().map(|batch| batch.len()).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(batches[0][0]["hashL"], third);
+        assert_eq!(batches[1][0]["hashL"], first);
+        assert_eq!(batches[1][0]["message"], "first");
+        assert!(stream_commit_log_format(&path, &keys, &format, vec!["not-exists".to_string()], 2, |_| {}).is_err());
+        // the separator bytes are allowed in a commit message
+        std::fs::write(std::path::Path::new(&path).join("d.txt"), "d\n").unwrap();
+        exec_git(&path, &["add", "d.txt"]);
+        exec_git(&path, &["commit", "-q", "-m", "odd \x1f subject \x1e", "-m", "body\n\nwith \x1e lines"]);
+        let odd = exec_git(&path, &["rev-parse", "HEAD"]);
+        let placeholders = vec!["%H".to_string(), "%B".to_string(), "%s".to_string()];
+        let log = get_commit_log_format(path.clone(), placeholders.clone(), "".to_string(), "".to_string(), None).ok().unwrap();
+        assert_eq!(log.len(), 4);
+        assert_eq!(log[0]["hashL"], odd);
+        assert_eq!(log[0]["message"], "odd \x1f subject \x1e");
+        assert!(log[0]["bodyNoTrailingSlash"].ends_with("with \x1e lines"));
+        assert_eq!(log[1]["hashL"], third);
+        let keys = get_placeholder_keys(&placeholders).ok().unwrap();
+        let mut commits = Vec::new();
+        stream_commit_log_format(&path, &keys, &commit_log_pretty_format(&placeholders), vec!["HEAD".to_string()], 3, |batch| commits.extend(batch)).unwrap();
+        assert_eq!(commits, log);
+    }
+
+    #[test]
+    fn test_merge_filters() {
+        let path = init_test_repo("merge_filters");
+        commit_file_at(&path, "a.txt", "a\n", "root", "2024-01-01T10:00:00+00:00");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        commit_file_at(&path, "b.txt", "b\n", "feature", "2024-01-01T11:00:00+00:00");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        commit_file_at(&path, "c.txt", "c\n", "main", "2024-01-01T12:00:00+00:00");
+        exec_git(&path, &["merge", "-q", "--no-ff", "-m", "merge", "feature"]);
+        let messages = |filter: CommitLogFilter| {
+            let log = get_commit_log_format(path.clone(), vec!["%s".to_string()], "".to_string(), "".to_string(), Some(filter)).ok().unwrap();
+            log.iter().map(|commit| commit["message"].to_string()).collect::<Vec<String>>()
+        };
+        assert_eq!(messages(CommitLogFilter::default()).len(), 4);
+        assert!(!messages(CommitLogFilter { no_merges: Some(true), ..Default::default() }).contains(&"merge".to_string()));
+        assert_eq!(messages(CommitLogFilter { first_parent: Some(true), ..Default::default() }), vec!["merge", "main", "root"]);
+        let stat = get_contribute_stat(path.clone(), "main".to_string(), None, None).ok().unwrap();
+        assert_eq!(stat.total_stat.commit_count.iter().sum::<i32>(), 4);
+        let filter = CommitLogFilter { no_merges: Some(true), ..Default::default() };
+        let stat = get_contribute_stat(path, "main".to_string(), Some(filter), None).ok().unwrap();
+        assert_eq!(stat.total_stat.commit_count.iter().sum::<i32>(), 3);
+    }
+
+    #[test]
+    fn test_get_author_totals() {
+        let path = init_test_repo("author_totals");
+        commit_file(&path, "a.txt", "1\n2\n", "first");
+        commit_file(&path, "b.txt", "1\n2\n3\n", "second");
+        std::fs::write(std::path::Path::new(&path).join("a.txt"), "1\n").unwrap();
+        exec_git(&path, &["commit", "-q", "-a", "-m", "by other", "--author", "other <other@example.com>"]);
+        exec_git(&path, &["commit", "-q", "--allow-empty", "-m", "empty"]);
+        let totals = get_author_totals(path, "main".to_string()).ok().unwrap();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].author.name, "tester");
+        assert_eq!(totals[0].commit_count, 3);
+        assert_eq!(totals[0].insertions, 5);
+        assert_eq!(totals[0].deletions, 0);
+        assert_eq!(totals[0].files_changed, 2);
+        assert_eq!(totals[1].author.email, "other@example.com");
+        assert_eq!(totals[1].commit_count, 1);
+        assert_eq!(totals[1].deletions, 1);
+    }
+
+    #[test]
+    fn test_get_change_stats_by_extension() {
+        let path = init_test_repo("stats_by_extension");
+        let first = commit_file(&path, "a.rs", "1\n", "first");
+        commit_file(&path, "a.rs", "2\n3\n", "rust");
+        commit_file(&path, "src/b.rs", "1\n", "more rust");
+        commit_file(&path, "Makefile", "all:\n", "no extension");
+        let image = std::path::Path::new(&path).join("logo.png");
+        std::fs::write(image, [0u8, 1, 2, 0, 255]).unwrap();
+        exec_git(&path, &["add", "logo.png"]);
+        exec_git(&path, &["commit", "-q", "-m", "binary"]);
+        let stats = get_change_stats_by_extension(path.clone(), first, "HEAD".to_string()).ok().unwrap();
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].extension, "rs");
+        assert_eq!(stats[0].files, 2);
+        assert_eq!(stats[0].additions, 3);
+        assert_eq!(stats[0].deletions, 1);
+        assert_eq!(stats[1].extension, "(none)");
+        assert_eq!(stats[1].additions, 1);
+        assert_eq!(stats[2].extension, "png");
+        assert_eq!(stats[2].binary_files, 1);
+        assert_eq!(stats[2].additions, 0);
+        assert!(get_change_stats_by_extension(path.clone(), "HEAD".to_string(), "not-exists".to_string()).is_err());
+        // an option-like ref is not taken as an option
+        assert!(get_change_stats_by_extension(path.clone(), "--output=stats.txt".to_string(), "HEAD".to_string()).is_err());
+        assert!(std::fs::read_dir(&path).unwrap().all(|entry| !entry.unwrap().file_name().to_string_lossy().starts_with("stats.txt")));
+        let stats = get_change_stats_by_extension(path, "".to_string(), "".to_string()).ok().unwrap();
+        assert_eq!(stats[0].additions, 3);
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        let entries = parse_numstat("3\t1\ta.txt\0-\t-\tlogo.png\0002\t0\t\0old name.txt\0new name.txt\0");
+        assert_eq!(entries.len(), 3);
+        assert_eq!((entries[0].path.as_str(), entries[0].additions, entries[0].deletions, entries[0].is_binary), ("a.txt", 3, 1, false));
+        assert_eq!((entries[1].path.as_str(), entries[1].additions, entries[1].is_binary), ("logo.png", 0, true));
+        assert_eq!(entries[2].path, "new name.txt");
+        assert_eq!(entries[2].additions, 2);
+    }
+
+    #[test]
+    fn test_get_file_modify_stat_numstat() {
+        let path = init_test_repo("modify_stat_numstat");
+        let first = commit_file(&path, "a.txt", "1\n2\n", "first");
+        std::fs::write(std::path::Path::new(&path).join("logo.png"), [0u8, 1, 2]).unwrap();
+        exec_git(&path, &["add", "logo.png"]);
+        exec_git(&path, &["commit", "-q", "-m", "binary"]);
+        std::fs::write(std::path::Path::new(&path).join("logo.png"), [0u8, 3, 4]).unwrap();
+        std::fs::write(std::path::Path::new(&path).join("a.txt"), "1\n3\n4\n").unwrap();
+        exec_git(&path, &["commit", "-q", "-a", "-m", "second"]);
+        let stat = get_file_modify_stat_between_commit(path.clone(), first.clone(), "HEAD".to_string(), "a.txt".to_string(), None).ok().unwrap();
+        assert_eq!((stat.addition, stat.deletion), (2, 1));
+        let stat = get_file_modify_stat_between_commit(path.clone(), "HEAD~1".to_string(), "HEAD".to_string(), "logo.png".to_string(), None).ok().unwrap();
+        assert_eq!((stat.addition, stat.deletion), (0, 0));
+        exec_git(&path, &["mv", "a.txt", "b.txt"]);
+        exec_git(&path, &["commit", "-q", "-m", "rename"]);
+        let stat = get_diff_file_stat_between_commit(path, "HEAD~1".to_string(), "HEAD".to_string(), "a.txt".to_string(), "b.txt".to_string(), None).ok().unwrap();
+        assert_eq!((stat.addition, stat.deletion), (0, 0));
+    }
+
+    #[test]
+    fn test_get_files_numstat_between_commit() {
+        let path = init_test_repo("files_numstat");
+        commit_file(&path, "a.txt", "1\n2\n3\n4\n5\n", "first");
+        commit_file(&path, "d.txt", "d\n", "second");
+        std::fs::write(std::path::Path::new(&path).join("logo.png"), [0u8, 1, 2]).unwrap();
+        exec_git(&path, &["add", "logo.png"]);
+        exec_git(&path, &["commit", "-q", "-m", "binary"]);
+        exec_git(&path, &["mv", "a.txt", "b.txt"]);
+        std::fs::write(std::path::Path::new(&path).join("b.txt"), "1\n2\n3\n4\n5\n6\n").unwrap();
+        std::fs::write(std::path::Path::new(&path).join("logo.png"), [0u8, 3, 4]).unwrap();
+        exec_git(&path, &["rm", "-q", "d.txt"]);
+        exec_git(&path, &["add", "-A"]);
+        exec_git(&path, &["commit", "-q", "-m", "changes"]);
+        let files = get_files_numstat_between_commit(path.clone(), "HEAD~1".to_string(), "HEAD".to_string(), None).ok().unwrap();
+        assert_eq!(files.len(), 3);
+        let renamed = files.iter().find(|file| file.path == "b.txt").unwrap();
+        assert_eq!(renamed.status, FileStatusType::Renamed);
+        assert_eq!(renamed.old_path.as_deref(), Some("a.txt"));
+        assert_eq!((renamed.additions, renamed.deletions), (1, 0));
+        let deleted = files.iter().find(|file| file.path == "d.txt").unwrap();
+        assert_eq!(deleted.status, FileStatusType::Deleted);
+        assert_eq!(deleted.deletions, 1);
+        let binary = files.iter().find(|file| file.path == "logo.png").unwrap();
+        assert_eq!(binary.status, FileStatusType::Modified);
+        assert!(binary.is_binary);
+        assert!(get_files_numstat_between_commit(path, "HEAD".to_string(), "not-exists".to_string(), None).is_err());
+    }
+
+    #[test]
+    fn test_get_commit_changes() {
+        let path = init_test_repo("commit_changes");
+        let root = commit_file(&path, "a.txt", "1\n2\n", "root");
+        let second = commit_file(&path, "a.txt", "1\n3\n", "second");
+        let changes = get_commit_changes(path.clone(), root.clone()).ok().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].file_status, FileStatusType::Added);
+        assert_eq!(changes[0].commit_hash1, EMPTY_TREE_HASH);
+        assert_eq!(changes[0].change_stat.addition, 2);
+        let changes = get_commit_changes(path.clone(), second).ok().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].file_status, FileStatusType::Modified);
+        assert_eq!(changes[0].commit_hash1, root);
+        assert_eq!((changes[0].change_stat.addition, changes[0].change_stat.deletion), (1, 1));
+        assert!(get_commit_changes(path, "not-exists".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_diff_root_commit() {
+        let path = init_test_repo("diff_root_commit");
+        let root = commit_file(&path, "a.txt", "1\n2\n", "root");
+        commit_file(&path, "a.txt", "1\n", "second");
+        assert_eq!(resolve_diff_base(&path, &format!("{}^", root)), EMPTY_TREE_HASH);
+        assert_eq!(resolve_diff_base(&path, "HEAD^"), "HEAD^");
+        assert_eq!(resolve_diff_base(&path, "not-exists^"), "not-exists^");
+        let context = diff_file_context(path.clone(), format!("{}^", root), root.to_string(), "a.txt".to_string(), None).ok().unwrap();
+        assert_eq!(context.file_status, FileStatusType::Added);
+        assert_eq!(context.commit_hash1, EMPTY_TREE_HASH);
+        assert_eq!(context.context2, "1\n2\n");
+        assert_eq!(context.change_stat.addition, 2);
+        let files = get_files_diff_context(path, format!("{}^", root), root, None, None, None).ok().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_status, FileStatusType::Added);
+    }
+
+    #[test]
+    fn test_checkout_branch() {
+        let path = init_test_repo("checkout_branch");
+        commit_file(&path, "a.txt", "a\n", "first");
+        exec_git(&path, &["branch", "feature"]);
+        let feature = commit_file(&path, "a.txt", "b\n", "second");
+        exec_git(&path, &["branch", "-f", "feature", &feature]);
+        exec_git(&path, &["reset", "-q", "--hard", "HEAD~1"]);
+        checkout_branch(path.clone(), "feature".to_string(), false).ok().unwrap();
+        assert_eq!(exec_git(&path, &["rev-parse", "--abbrev-ref", "HEAD"]), "feature");
+        std::fs::write(std::path::Path::new(&path).join("a.txt"), "local\n").unwrap();
+        assert!(checkout_branch(path.clone(), "main".to_string(), false).is_err());
+        assert_eq!(parse_overwritten_files("error: Your local changes to the following files would be overwritten by checkout:\n\ta.txt\n\tb c.txt\nPlease commit your changes or stash them before you switch branches.\nAborting\n"), vec!["a.txt", "b c.txt"]);
+        assert_eq!(exec_git(&path, &["rev-parse", "--abbrev-ref", "HEAD"]), "feature");
+        checkout_branch(path.clone(), "main".to_string(), true).ok().unwrap();
+        assert_eq!(exec_git(&path, &["rev-parse", "--abbrev-ref", "HEAD"]), "main");
+        assert_eq!(std::fs::read_to_string(std::path::Path::new(&path).join("a.txt")).unwrap(), "a\n");
+        assert!(checkout_branch(path.clone(), "not-exists".to_string(), false).is_err());
+        // an option-like branch is rejected instead of creating the branch
+        assert!(checkout_branch(path.clone(), "--orphan=evil".to_string(), false).is_err());
+        assert!(exec_git(&path, &["branch", "--list", "evil"]).is_empty());
+        assert_eq!(exec_git(&path, &["rev-parse", "--abbrev-ref", "HEAD"]), "main");
+    }
+
+    #[test]
+    fn test_create_and_delete_branch() {
+        let path = init_test_repo("create_delete_branch");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "a.txt", "b\n", "second");
+        create_branch(path.clone(), "old".to_string(), first.to_string()).ok().unwrap();
+        assert_eq!(exec_git(&path, &["rev-parse", "old"]), first);
+        create_branch(path.clone(), "topic/head".to_string(), "".to_string()).ok().unwrap();
+        assert_eq!(exec_git(&path, &["rev-parse", "topic/head"]), exec_git(&path, &["rev-parse", "HEAD"]));
+        assert!(create_branch(path.clone(), "old".to_string(), "".to_string()).is_err());
+        assert!(create_branch(path.clone(), "a..b".to_string(), "".to_string()).is_err());
+        assert!(create_branch(path.clone(), "-f".to_string(), "".to_string()).is_err());
+        // an option-like start point is not taken as an option
+        assert!(create_branch(path.clone(), "forced".to_string(), "--force".to_string()).is_err());
+        assert!(exec_git(&path, &["branch", "--list", "forced"]).is_empty());
+        delete_branch(path.clone(), "old".to_string(), false).ok().unwrap();
+        exec_git(&path, &["checkout", "-q", "-b", "unmerged"]);
+        commit_file(&path, "b.txt", "b\n", "unmerged");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        assert!(delete_branch(path.clone(), "unmerged".to_string(), false).is_err());
+        assert!(!exec_git(&path, &["branch", "--list", "unmerged"]).is_empty());
+        delete_branch(path.clone(), "unmerged".to_string(), true).ok().unwrap();
+        assert!(exec_git(&path, &["branch", "--list", "unmerged"]).is_empty());
+        assert!(delete_branch(path, "not-exists".to_string(), true).is_err());
+    }
+
+    #[test]
+    fn test_parse_transfer_progress() {
+        let progress = parse_transfer_progress("Receiving objects:  45% (450/1000), 1.20 MiB | 500.00 KiB/s").unwrap();
+        assert_eq!(progress, TransferProgress { stage: "Receiving objects".to_string(), percent: 45, current: 450, total: 1000 });
+        assert_eq!(parse_transfer_progress("remote: Counting objects: 100% (3/3), done.").unwrap().stage, "Counting objects");
+        // a server with another language sends its own stage names
+        assert_eq!(parse_transfer_progress("remote: Zählen der Objekte: 50% (1/2)").unwrap().stage, "Zählen der Objekte");
+        assert!(parse_transfer_progress("From ../remote").is_none());
+    }
+
+    #[test]
+    fn test_fetch_with_progress() {
+        let path = init_test_repo("fetch_progress");
+        commit_file(&path, "a.txt", "a\n", "first");
+        let remote_path = path.to_string() + "-remote";
+        exec_git(&path, &["clone", "-q", "--bare", &path, &remote_path]);
+        exec_git(&path, &["remote", "add", "origin", &remote_path]);
+        let mut progress = Vec::new();
+        fetch_with_progress(&path, "origin", "", true, |p| progress.push(p)).ok().unwrap();
+        assert!(!exec_git(&path, &["rev-parse", "--verify", "origin/main"]).is_empty());
+        assert!(progress.windows(2).all(|pair| pair[0] != pair[1]));
+        let err = fetch_with_progress(&path, "not-exists", "", false, |_| {});
+        assert!(err.is_err());
+        // a remote, a refspec or a branch starting with "-" is rejected instead of being taken for an option
+        let marker = std::path::Path::new(&path).join("marker");
+        let option = format!("--upload-pack=touch {}", marker.display());
+        assert!(fetch_with_progress(&path, &option, "", false, |_| {}).is_err());
+        assert!(fetch_with_progress(&path, "origin", &option, false, |_| {}).is_err());
+        assert!(pull_with_progress(&path, &option, "", |_| {}).is_err());
+        assert!(push_with_progress(&path, "origin", "--delete", false, false, |_| {}).is_err());
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_push_and_pull() {
+        let path = init_test_repo("push_pull");
+        commit_file(&path, "a.txt", "a\n", "first");
+        let remote_path = path.to_string() + "-remote";
+        exec_git(&path, &["init", "-q", "--bare", "-b", "main", &remote_path]);
+        exec_git(&path, &["remote", "add", "origin", &remote_path]);
+        assert!(push_with_progress(&path, "", "", false, false, |_| {}).is_err());
+        let messages = push_with_progress(&path, "origin", "main", false, true, |_| {}).ok().unwrap();
+        // the updated ref is reported on the stderr
+        assert!(messages.contains("main -> main"));
+        assert_eq!(exec_git(&path, &["rev-parse", "--abbrev-ref", "main@{upstream}"]), "origin/main");
+        let clone_path = path.to_string() + "-clone";
+        exec_git(&path, &["clone", "-q", &remote_path, &clone_path]);
+        exec_git(&clone_path, &["config", "user.name", "tester"]);
+        exec_git(&clone_path, &["config", "user.email", "tester@example.com"]);
+        let pushed = commit_file(&clone_path, "b.txt", "b\n", "from clone");
+        push_with_progress(&clone_path, "", "", false, false, |_| {}).ok().unwrap();
+        commit_file(&path, "c.txt", "c\n", "diverged");
+        assert!(push_with_progress(&path, "origin", "main", false, false, |_| {}).is_err());
+        exec_git(&path, &["reset", "-q", "--hard", "HEAD~1"]);
+        pull_with_progress(&path, "origin", "main", |_| {}).ok().unwrap();
+        assert_eq!(exec_git(&path, &["rev-parse", "HEAD"]), pushed);
+        commit_file(&path, "d.txt", "d\n", "local");
+        exec_git(&clone_path, &["pull", "-q"]);
+        commit_file(&clone_path, "e.txt", "e\n", "remote");
+        push_with_progress(&clone_path, "", "", false, false, |_| {}).ok().unwrap();
+        assert!(push_with_progress(&path, "origin", "main", false, false, |_| {}).is_err());
+        push_with_progress(&path, "origin", "main", true, false, |_| {}).ok().unwrap();
+    }
+
+    #[test]
+    fn test_classify_remote_error() {
+        let rejected = " ! [rejected]        main -> main (non-fast-forward)\nerror: failed to push some refs to '/tmp/r'";
+        assert!(matches!(classify_remote_error(rejected), Some(CustomerGitError::PushRejected(_))));
+        let auth = "fatal: Authentication failed for 'https://example.com/repo.git/'";
+        assert!(matches!(classify_remote_error(auth), Some(CustomerGitError::AuthenticationFailed(_))));
+        let prompt = "fatal: could not read Username for 'https://example.com': terminal prompts disabled";
+        assert!(matches!(classify_remote_error(prompt), Some(CustomerGitError::AuthenticationFailed(_))));
+        let no_upstream = "fatal: The current branch topic has no upstream branch.";
+        assert!(matches!(classify_remote_error(no_upstream), Some(CustomerGitError::NoUpstream(_))));
+        assert!(matches!(classify_remote_error("There is no tracking information for the current branch."), Some(CustomerGitError::NoUpstream(_))));
+        assert!(classify_remote_error(" ! [remote rejected] main -> main (pre-receive hook declined)").is_none());
+    }
+
+    #[test]
+    fn test_clone_with_progress() {
+        let path = init_test_repo("clone_progress");
+        commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "b.txt", "b\n", "second");
+        exec_git(&path, &["branch", "other", "HEAD~1"]);
+        let url = format!("file://{}", path);
+        let target = path.to_string() + "-clone";
+        let options = Some(CloneOptions { depth: Some(1), branch: Some("main".to_string()), ..Default::default() });
+        let mut progress = Vec::new();
+        let cloned = clone_with_progress(&url, &target, &options, |p| progress.push(p)).ok().unwrap();
+        assert_eq!(cloned, target);
+        assert_eq!(exec_git(&cloned, &["rev-list", "--count", "HEAD"]), "1");
+        assert!(exec_git(&cloned, &["branch", "-r"]).lines().all(|line| !line.contains("other")));
+        let bare = clone_with_progress(&path, &(path.to_string() + "-bare"), &Some(CloneOptions { bare: Some(true), ..Default::default() }), |_| {}).ok().unwrap();
+        assert_eq!(exec_git(&bare, &["rev-parse", "--is-bare-repository"]), "true");
+        assert!(clone_with_progress(&(path.to_string() + "-not-exists"), &(path.to_string() + "-failed"), &None, |_| {}).is_err());
+        assert!(!std::path::Path::new(&(path.to_string() + "-failed")).exists());
+    }
+
+    #[test]
+    fn test_stage_unstage_discard() {
+        let path = init_test_repo("stage_unstage");
+        commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "b.txt", "b\n", "second");
+        let root = std::path::Path::new(&path);
+        std::fs::write(root.join("a.txt"), "changed a\n").unwrap();
+        std::fs::write(root.join("b.txt"), "changed b\n").unwrap();
+        std::fs::write(root.join("c.txt"), "c\n").unwrap();
+        assert!(stage_files(path.clone(), vec![]).ok().unwrap().is_empty());
+        assert_eq!(exec_git(&path, &["diff", "--cached", "--name-only"]), "");
+        let failures = stage_files(path.clone(), vec!["a.txt".to_string(), "missing.txt".to_string(), "c.txt".to_string()]).ok().unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].path, "missing.txt");
+        assert_eq!(exec_git(&path, &["diff", "--cached", "--name-only"]), "a.txt\nc.txt");
+        assert!(unstage_files(path.clone(), vec!["c.txt".to_string()]).ok().unwrap().is_empty());
+        assert_eq!(exec_git(&path, &["diff", "--cached", "--name-only"]), "a.txt");
+        let failures = discard_changes(path.clone(), vec!["b.txt".to_string(), "c.txt".to_string()]).ok().unwrap();
+        assert_eq!(failures.iter().map(|failure| failure.path.as_str()).collect::<Vec<&str>>(), vec!["c.txt"]);
+        assert_eq!(std::fs::read_to_string(root.join("b.txt")).unwrap(), "b\n");
+        assert!(root.join("c.txt").exists());
+    }
+
+    #[test]
+    fn test_unstage_files_unborn() {
+        let path = init_test_repo("unstage_unborn");
+        let root = std::path::Path::new(&path);
+        std::fs::create_dir_all(root.join("dir")).unwrap();
+        std::fs::write(root.join("a.txt"), "a\n").unwrap();
+        std::fs::write(root.join("dir/b.txt"), "b\n").unwrap();
+        exec_git(&path, &["add", "-A"]);
+        let failures = unstage_files(path.clone(), vec!["a.txt".to_string(), "dir".to_string(), "missing.txt".to_string()]).ok().unwrap();
+        assert_eq!(failures.iter().map(|failure| failure.path.as_str()).collect::<Vec<&str>>(), vec!["missing.txt"]);
+        assert_eq!(exec_git(&path, &["ls-files"]), "");
+        // the files are kept in the working tree
+        assert!(root.join("a.txt").exists() && root.join("dir/b.txt").exists());
+    }
+
+    #[test]
+    fn test_create_commit() {
+        let path = init_test_repo("create_commit");
+        let root = std::path::Path::new(&path);
+        std::fs::write(root.join("a.txt"), "a\n").unwrap();
+        exec_git(&path, &["add", "a.txt"]);
+        let hash = create_commit(path.clone(), "first".to_string(), None).ok().unwrap();
+        assert_eq!(hash, exec_git(&path, &["rev-parse", "HEAD"]));
+        assert!(create_commit(path.clone(), "nothing".to_string(), None).is_err());
+        let options = CommitOptions { allow_empty: Some(true), author: Some("other <other@example.com>".to_string()), ..Default::default() };
+        let empty = create_commit(path.clone(), "empty".to_string(), Some(options)).ok().unwrap();
+        assert_eq!(exec_git(&path, &["log", "-1", "--format=%ae", &empty]), "other@example.com");
+        let options = CommitOptions { amend: Some(true), allow_empty: Some(true), ..Default::default() };
+        let amended = create_commit(path.clone(), "amended".to_string(), Some(options)).ok().unwrap();
+        assert_ne!(amended, empty);
+        assert_eq!(exec_git(&path, &["rev-list", "--count", "HEAD"]), "2");
+        assert_eq!(exec_git(&path, &["log", "-1", "--format=%s"]), "amended");
+        let hook = root.join(".git").join("hooks").join("pre-commit");
+        std::fs::write(&hook, "#!/bin/sh\necho rejected by hook\nexit 1\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        assert!(create_commit(path.clone(), "hooked".to_string(), Some(CommitOptions { allow_empty: Some(true), ..Default::default() })).is_err());
+        assert_eq!(exec_git(&path, &["rev-parse", "HEAD"]), amended);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_dubious_ownership() {
+        let path = init_test_repo("dubious_ownership");
+        commit_file(&path, "a.txt", "a\n", "first");
+        // only root can give the repository to another user
+        if std::os::unix::fs::chown(&path, Some(12345), Some(12345)).is_err() {
+            return
+        }
+        assert!(ensure_git_repository(&path).is_err());
+        assert_eq!(validate_repository(path.clone()), RepositoryValidation::DubiousOwnership);
+        let err = get_checked_command_output("git", &path, &["status"]).unwrap_err();
+        assert!(err.to_string().contains("safe.directory"));
+        assert!(is_dubious_ownership("fatal: detected dubious ownership in repository at '/tmp/repo'"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_set_git_env() {
+        let _globals = GlobalsGuard::new();
+        let echo = |name: &str| {
+            let output = get_command_output("sh", "", &["-c", &format!("echo \"${}\"", name)]).unwrap();
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        assert_eq!(echo("GIT_TERMINAL_PROMPT"), "0");
+        assert_eq!(echo("LC_ALL"), "C");
+        assert_eq!(echo("LANGUAGE"), "C");
+        std::env::set_var("GIT_UTIL_NATIVE_REMOVED", "inherited");
+        let mut env = HashMap::new();
+        env.insert("GIT_UTIL_NATIVE_TEST".to_string(), "set".to_string());
+        env.insert("GIT_UTIL_NATIVE_REMOVED".to_string(), "".to_string());
+        set_git_env(env);
+        assert_eq!(echo("GIT_UTIL_NATIVE_TEST"), "set");
+        assert_eq!(echo("GIT_UTIL_NATIVE_REMOVED"), "");
+        set_git_env(HashMap::new());
+        assert_eq!(echo("GIT_UTIL_NATIVE_TEST"), "");
+        assert_eq!(echo("GIT_UTIL_NATIVE_REMOVED"), "inherited");
+    }
+
+    #[test]
+    fn test_get_reflog() {
+        let path = init_test_repo("reflog");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        let second = commit_file(&path, "a.txt", "b\n", "second");
+        exec_git(&path, &["reset", "-q", "--hard", "HEAD~1"]);
+        let reflog = get_reflog(path.clone(), "".to_string(), 0).ok().unwrap();
+        assert_eq!(reflog.len(), 3);
+        assert_eq!(reflog[0].selector, "HEAD@{0}");
+        assert_eq!(reflog[0].hash, first);
+        assert_eq!(reflog[0].action, "reset");
+        assert_eq!(reflog[0].message, "moving to HEAD~1");
+        assert!(reflog[0].timestamp.parse::<i64>().is_ok());
+        assert_eq!(reflog[1].selector, "HEAD@{1}");
+        assert_eq!(reflog[1].hash, second);
+        assert_eq!((reflog[1].action.as_str(), reflog[1].message.as_str()), ("commit", "second"));
+        assert_eq!(reflog[2].action, "commit (initial)");
+        assert_eq!(get_reflog(path.clone(), "main".to_string(), 1).ok().unwrap().len(), 1);
+        assert!(get_reflog(path, "not-exists".to_string(), 0).is_err());
+    }
+
+    #[test]
+    fn test_get_branches_detail() {
+        let remote = init_test_repo("branches_detail_remote");
+        let base = commit_file_at(&remote, "a.txt", "a\n", "base commit", "2024-01-01T10:00:00+00:00");
+        let path = format!("{}_clone", remote);
+        let _ = std::fs::remove_dir_all(&path);
+        let output = Command::new("git").args(["clone", "-q", &remote, &path]).output().unwrap();
+        assert!(output.status.success());
+        exec_git(&path, &["config", "user.name", "tester"]);
+        exec_git(&path, &["config", "user.email", "tester@example.com"]);
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        let tip = commit_file_at(&path, "b.txt", "b\n", "feature work", "2024-02-01T10:00:00+00:00");
+        let branches = get_branches_detail(path.clone()).ok().unwrap();
+        assert_eq!(branches.len(), 3);
+        let feature = branches.iter().find(|b| b.name == "feature").unwrap();
+        assert!(!feature.is_remote);
+        assert_eq!(feature.tip_hash, tip);
+        assert_eq!(feature.tip_subject, "feature work");
+        assert_eq!(feature.tip_author, "tester");
+        assert_eq!(feature.tip_date, "1706781600");
+        assert_eq!(feature.upstream, "");
+        let main = branches.iter().find(|b| b.name == "main").unwrap();
+        assert!(!main.is_remote);
+        assert_eq!(main.tip_hash, base);
+        assert_eq!(main.upstream, "origin/main");
+        let tracking = branches.iter().find(|b| b.is_remote).unwrap();
+        assert_eq!(tracking.name, "origin/main");
+        assert_eq!(tracking.tip_subject, "base commit");
+    }
+
+    #[test]
+    fn test_get_branch_refs() {
+        let remote = init_test_repo("branch_refs_remote");
+        commit_file(&remote, "a.txt", "a\n", "first");
+        let path = format!("{}_clone", remote);
+        let output = Command::new("git").args(["clone", "-q", "-o", "team/origin", &remote, &path]).output().unwrap();
+        assert!(output.status.success());
+        exec_git(&path, &["branch", "feature"]);
+        let branches = get_branch_refs(path.clone()).ok().unwrap();
+        let local = branches.iter().filter(|b| !b.is_remote).map(|b| b.name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(local, vec!["feature", "main"]);
+        assert!(branches.iter().filter(|b| !b.is_remote).all(|b| b.remote_name.is_empty()));
+        let main = branches.iter().find(|b| b.name == "team/origin/main").unwrap();
+        assert!(main.is_remote);
+        assert_eq!(main.remote_name, "team/origin");
+        // the `HEAD -> main` line is tokenized to the symbolic ref name only
+        let head = branches.iter().find(|b| b.name == "team/origin/HEAD").unwrap();
+        assert_eq!(head.remote_name, "team/origin");
+    }
+
+    #[test]
+    fn test_parse_decorations() {
+        let decorations = parse_decorations(" (HEAD -> main, tag: v1.0, origin/main, origin/HEAD)".to_string());
+        assert!(decorations.head);
+        assert_eq!(decorations.branches, vec!["main".to_string(), "origin/main".to_string(), "origin/HEAD".to_string()]);
+        assert_eq!(decorations.tags, vec!["v1.0".to_string()]);
+        let decorations = parse_decorations("HEAD, tag: v2".to_string());
+        assert!(decorations.head);
+        assert!(decorations.branches.is_empty());
+        assert_eq!(decorations.tags, vec!["v2".to_string()]);
+        assert_eq!(parse_decorations("".to_string()), CommitDecorations::default());
+    }
+
+    #[test]
+    fn test_get_repo_stats() {
+        let path = init_test_repo("repo_stats");
+        commit_file(&path, "a.txt", "a\n", "first");
+        let stats = get_repo_stats(path.clone()).ok().unwrap();
+        // a blob, a tree and a commit
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.in_pack, 0);
+        assert_eq!(stats.packs, 0);
+        exec_git(&path, &["gc", "-q"]);
+        let stats = get_repo_stats(path.clone()).ok().unwrap();
+        assert_eq!(stats.count, 0);
+        assert_eq!(stats.in_pack, 3);
+        assert_eq!(stats.packs, 1);
+        assert!(stats.size_pack_kb > 0);
+        assert_eq!(stats.garbage, 0);
+    }
+
+    #[test]
+    fn test_is_shallow() {
+        let path = init_test_repo("shallow_source");
+        commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "a.txt", "b\n", "second");
+        let third = commit_file(&path, "a.txt", "c\n", "third");
+        assert!(!is_shallow(path.clone()));
+        assert!(!get_branch_create_info(path.clone(), "main".to_string()).ok().unwrap().is_shallow);
+        let shallow = format!("{}_shallow", path);
+        let output = Command::new("git").args(["clone", "-q", "--depth", "1", &format!("file://{}", path), &shallow]).output().unwrap();
+        assert!(output.status.success());
+        assert!(is_shallow(shallow.clone()));
+        let info = get_branch_create_info(shallow.clone(), "main".to_string()).ok().unwrap();
+        assert!(info.is_shallow);
+        assert_eq!(info.hash, third);
+    }
+
+    #[test]
+    fn test_get_branch_create_info_fork_point() {
+        let path = init_test_repo("branch_create_fork");
+        let root = commit_file(&path, "a.txt", "a\n", "root");
+        let fork = commit_file(&path, "a.txt", "b\n", "main work");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        commit_file(&path, "b.txt", "b\n", "feature start");
+        commit_file(&path, "b.txt", "c\n", "feature work");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let create_hash = |branch: &str| get_branch_create_info(path.clone(), branch.to_string()).ok().unwrap().hash;
+        assert_eq!(create_hash("feature"), fork);
+        assert_eq!(create_hash("refs/heads/feature"), fork);
+        assert_eq!(create_hash("main"), root);
+        commit_file(&path, "a.txt", "c\n", "more main work");
+        assert_eq!(create_hash("feature"), fork);
+        assert_eq!(create_hash("main"), root);
+        exec_git(&path, &["merge", "-q", "--no-ff", "-m", "merge", "feature"]);
+        assert_eq!(create_hash("feature"), fork);
+        // without the reflog the fork point is found on the first-parent history of main
+        exec_git(&path, &["reflog", "expire", "--expire=all", "refs/heads/feature"]);
+        exec_git(&path, &["update-ref", "refs/heads/copied", "feature"]);
+        assert_eq!(create_hash("feature"), fork);
+        assert_eq!(create_hash("copied"), fork);
+        assert_eq!(create_hash("main"), root);
+    }
+
+    #[test]
+    fn test_cherry_pick() {
+        let path = init_test_repo("cherry_pick");
+        commit_file(&path, "a.txt", "a\n", "first");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        let fix = commit_file(&path, "b.txt", "fix\n", "fix");
+        let conflicting = commit_file(&path, "a.txt", "feature\n", "feature change");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let main = commit_file(&path, "c.txt", "c\n", "main work");
+        let result = cherry_pick(path.clone(), fix.clone()).ok().unwrap();
+        assert_eq!(result.status, CherryPickStatus::Applied);
+        assert_eq!(result.hash, resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap());
+        assert_eq!(resolve_ref(path.clone(), "HEAD~1".to_string()).ok().unwrap(), main);
+        // the fix is already on main
+        let result = cherry_pick(path.clone(), fix.clone()).ok().unwrap();
+        assert_eq!(result.status, CherryPickStatus::Empty);
+        assert!(!std::path::Path::new(&path).join(".git/CHERRY_PICK_HEAD").exists());
+        let head = commit_file(&path, "a.txt", "main\n", "main change");
+        let result = cherry_pick(path.clone(), conflicting).ok().unwrap();
+        assert_eq!(result.status, CherryPickStatus::Conflict);
+        assert_eq!(result.conflicts, vec!["a.txt".to_string()]);
+        assert!(cherry_pick_abort(path.clone()).is_ok());
+        assert_eq!(resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap(), head);
+        assert!(is_commited(path.clone()).ok().unwrap());
+        assert!(cherry_pick_abort(path).is_err());
+    }
 
-#[napi]
-fn get_commit_within_branches (repo: String, commit_hash: String) -> Result<Vec<String>, JsError> {
-    let formawt = r"--format=%(refname:short)";
-    let output = get_command_output("git", &repo, &["branch", "--contains", &commit_hash, &formawt]);
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let branches = stdout.split("\n").collect::<Vec<&str>>();
-            let mut result = Vec::<String>::new();
-            for branch in branches {
-                if branch.len() > 0 {
-                    result.push(branch.to_string());
-                }
-            }
-            Ok(result)
-        },
-        Err(e) => {
-            let err = napiError::from(e);
-            Err(JsError::from(err))
-        }
+    #[test]
+    fn test_revert_commit() {
+        let path = init_test_repo("revert_commit");
+        commit_file(&path, "a.txt", "a\n", "first");
+        let change = commit_file(&path, "a.txt", "b\n", "change");
+        let result = revert_commit(path.clone(), change.clone(), true, None).ok().unwrap();
+        assert_eq!(result.status, CherryPickStatus::Applied);
+        assert_eq!(result.hash, "");
+        assert_eq!(resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap(), change);
+        assert!(!is_commited(path.clone()).ok().unwrap());
+        exec_git(&path, &["reset", "-q", "--hard"]);
+        let result = revert_commit(path.clone(), change.clone(), false, None).ok().unwrap();
+        assert_eq!(result.status, CherryPickStatus::Applied);
+        assert_eq!(result.hash, resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap());
+        assert_eq!(std::fs::read_to_string(std::path::Path::new(&path).join("a.txt")).unwrap(), "a\n");
+        // the change is reverted already
+        assert_eq!(revert_commit(path.clone(), change.clone(), false, None).ok().unwrap().status, CherryPickStatus::Empty);
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        commit_file(&path, "b.txt", "b\n", "feature");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        commit_file(&path, "a.txt", "c\n", "main change");
+        exec_git(&path, &["merge", "-q", "--no-ff", "-m", "merge", "feature"]);
+        let merge = resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap();
+        assert!(revert_commit(path.clone(), merge.clone(), false, None).is_err());
+        let result = revert_commit(path.clone(), merge, false, Some(1)).ok().unwrap();
+        assert_eq!(result.status, CherryPickStatus::Applied);
+        assert!(!std::path::Path::new(&path).join("b.txt").exists());
+        let conflicting = commit_file(&path, "a.txt", "d\n", "conflicting");
+        commit_file(&path, "a.txt", "e\n", "later");
+        let result = revert_commit(path.clone(), conflicting, false, None).ok().unwrap();
+        assert_eq!(result.status, CherryPickStatus::Conflict);
+        assert_eq!(result.conflicts, vec!["a.txt".to_string()]);
     }
-}
 
-#[napi]
-fn get_branch_commit_count (path: String, branch: String) -> Result<i32, JsError> {
-    let output = get_command_output("git", &path, &["rev-list", "--count", &branch]);
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if stdout.trim().len() == 0 {
-                return Ok(0)
-            }
-            let count = stdout.trim().parse::<i32>().unwrap();
-            Ok(count)
-        },
-        Err(e) => {
-            let err =napiError::from(e);
-            Err(JsError::from(err))
-        }
+    #[test]
+    fn test_merge_branch() {
+        let path = init_test_repo("merge_branch");
+        commit_file(&path, "a.txt", "a\n", "first");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        let feature = commit_file(&path, "b.txt", "b\n", "feature");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let result = merge_branch(path.clone(), "feature".to_string(), None).ok().unwrap();
+        assert_eq!(result.status, MergeStatus::FastForward);
+        assert_eq!(result.hash, feature);
+        let result = merge_branch(path.clone(), "feature".to_string(), None).ok().unwrap();
+        assert_eq!(result.status, MergeStatus::AlreadyUpToDate);
+        assert_eq!(result.hash, "");
+        exec_git(&path, &["checkout", "-q", "feature"]);
+        commit_file(&path, "c.txt", "c\n", "more feature");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let result = merge_branch(path.clone(), "feature".to_string(), Some(MergeOptions { no_ff: Some(true), squash: None })).ok().unwrap();
+        assert_eq!(result.status, MergeStatus::MergeCommit);
+        assert_eq!(result.hash, resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap());
+        assert_eq!(resolve_ref(path.clone(), "HEAD^2".to_string()).ok().unwrap(), resolve_ref(path.clone(), "feature".to_string()).ok().unwrap());
+        exec_git(&path, &["checkout", "-q", "feature"]);
+        commit_file(&path, "d.txt", "d\n", "squashed feature");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let head = resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap();
+        let result = merge_branch(path.clone(), "feature".to_string(), Some(MergeOptions { no_ff: None, squash: Some(true) })).ok().unwrap();
+        assert_eq!(result.status, MergeStatus::Squashed);
+        assert_eq!(resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap(), head);
+        create_commit(path.clone(), "squash".to_string(), None).ok().unwrap();
+        exec_git(&path, &["checkout", "-q", "feature"]);
+        commit_file(&path, "a.txt", "feature\n", "feature change");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let head = commit_file(&path, "a.txt", "main\n", "main change");
+        let result = merge_branch(path.clone(), "feature".to_string(), None).ok().unwrap();
+        assert_eq!(result.status, MergeStatus::Conflict);
+        assert_eq!(result.conflicts, vec!["a.txt".to_string()]);
+        assert!(merge_abort(path.clone()).is_ok());
+        assert_eq!(resolve_ref(path.clone(), "HEAD".to_string()).ok().unwrap(), head);
+        assert!(is_commited(path.clone()).ok().unwrap());
+        // fast-forward onto a merge commit of another branch
+        exec_git(&path, &["checkout", "-q", "-b", "integration"]);
+        exec_git(&path, &["checkout", "-q", "-b", "topic"]);
+        commit_file(&path, "e.txt", "e\n", "topic");
+        exec_git(&path, &["checkout", "-q", "integration"]);
+        commit_file(&path, "f.txt", "f\n", "integration");
+        exec_git(&path, &["merge", "-q", "--no-ff", "-m", "merge topic", "topic"]);
+        let integration = resolve_ref(path.clone(), "integration".to_string()).ok().unwrap();
+        exec_git(&path, &["checkout", "-q", "main"]);
+        let result = merge_branch(path.clone(), "integration".to_string(), None).ok().unwrap();
+        assert_eq!(result.status, MergeStatus::FastForward);
+        assert_eq!(result.hash, integration);
+        assert!(merge_branch(path, "missing".to_string(), None).is_err());
     }
-}
 
+    #[test]
+    fn test_get_conflict_versions() {
+        let path = init_test_repo("conflict_versions");
+        commit_file(&path, "a.txt", "base\n", "first");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        commit_file(&path, "a.txt", "theirs\n", "feature change");
+        commit_file(&path, "b.txt", "theirs b\n", "feature add");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        commit_file(&path, "a.txt", "ours\n", "main change");
+        commit_file(&path, "b.txt", "ours b\n", "main add");
+        assert_eq!(merge_branch(path.clone(), "feature".to_string(), None).ok().unwrap().status, MergeStatus::Conflict);
+        let versions = get_conflict_versions(path.clone(), "a.txt".to_string()).ok().unwrap();
+        assert_eq!(versions.base, Some("base\n".to_string()));
+        assert_eq!(versions.ours, Some("ours\n".to_string()));
+        assert_eq!(versions.theirs, Some("theirs\n".to_string()));
+        // add/add conflict
+        let versions = get_conflict_versions(path.clone(), "b.txt".to_string()).ok().unwrap();
+        assert_eq!(versions.base, None);
+        assert_eq!(versions.ours, Some("ours b\n".to_string()));
+        assert_eq!(versions.theirs, Some("theirs b\n".to_string()));
+    }
 
-#[cfg(test)]
-mod tests {
+    #[test]
+    fn test_run_git() {
+        let path = init_test_repo("run_git");
+        let hash = commit_file(&path, "a.txt", "a\n", "first");
+        let output = run_git(path.clone(), vec!["log".to_string(), "-1".to_string(), "--format=%H %s".to_string()]).ok().unwrap();
+        assert_eq!(output.exit_code, 0);
+        assert_eq!(output.stdout, format!("{} first\n", hash));
+        assert_eq!(output.stderr, "");
+        // no shell, the quotes and the semicolon are passed to git as they are
+        let output = run_git(path.clone(), vec!["rev-parse".to_string(), "--verify".to_string(), "'main'; echo".to_string()]).ok().unwrap();
+        assert_ne!(output.exit_code, 0);
+        assert!(!output.stderr.is_empty());
+    }
 
-    use util::get_current_time;
+    #[test]
+    fn test_run_git_command() {
+        let path = init_test_repo("run_git_command");
+        commit_file(&path, "a.txt", "a\n", "first");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        // git reports the switch on the stderr of a successful command
+        let output = run_git_command(&path, &["checkout", "main"]).unwrap();
+        assert_eq!(output.exit_code, 0);
+        assert!(output.stderr.contains("Switched to branch 'main'"));
+        let output = run_git_command(&path, &["rev-parse", "--verify", "missing"]).unwrap();
+        assert_eq!(output.exit_code, 128);
+        assert!(!output.stderr.is_empty());
+    }
 
-    use super::*;
+    #[test]
+    fn test_get_file_word_diff() {
+        let path = init_test_repo("word_diff");
+        let first = commit_file(&path, "a.txt", "hello world\n\nfoo bar baz\nkeep\n", "first");
+        let second = commit_file(&path, "a.txt", "hello there world\n\nfoo baz\nkeep\nnew line\n", "second");
+        let hunks = get_file_word_diff(path, first, second, "a.txt".to_string(), None, None).ok().unwrap();
+        assert_eq!(hunks.len(), 1);
+        assert_eq!((hunks[0].old_start, hunks[0].old_lines, hunks[0].new_start, hunks[0].new_lines), (1, 4, 1, 5));
+        let lines = hunks[0].lines.iter().map(|segments| {
+            segments.iter().map(|segment| (segment.origin == DiffLineOrigin::Context, segment.origin == DiffLineOrigin::Addition, segment.content.as_str())).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], vec![(true, false, "hello "), (false, true, "there"), (true, false, " world")]);
+        assert!(lines[1].is_empty());
+        assert_eq!(lines[2], vec![(true, false, "foo"), (false, false, "bar"), (true, false, " baz")]);
+        assert_eq!(lines[3], vec![(true, false, "keep")]);
+        assert_eq!(lines[4], vec![(false, true, "new line")]);
+    }
 
     #[test]
-    fn test_get_commit_file_status() {    
-        let path = String::from(r"E:\workSpace\Rust\rust_test");
-        let hash = String::from("6a8a10948c80df189f79ff680df66d688b93bdd2");
-        let res = get_commit_file_status(path.to_string(), hash.to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-            }
-        }
+    fn test_get_contribute_stat_range() {
+        let path = init_test_repo("contribute_stat_range");
+        let first = commit_file_at(&path, "a.txt", "a\n", "first", "2024-01-01T10:00:00+00:00");
+        let second = commit_file_at(&path, "a.txt", "b\n", "second", "2024-01-02T10:00:00+00:00");
+        exec_git(&path, &["tag", "v1"]);
+        commit_file_at(&path, "a.txt", "c\n", "third", "2024-01-03T10:00:00+00:00");
+        exec_git(&path, &["tag", "v2"]);
+        commit_file_at(&path, "a.txt", "d\n", "fourth", "2024-01-04T10:00:00+00:00");
+        let count = |stat: BranchStatDailyContribute| stat.total_stat.commit_count.iter().sum::<i32>();
+        assert_eq!(count(get_contribute_stat(path.clone(), "main".to_string(), None, None).ok().unwrap()), 4);
+        assert_eq!(count(get_contribute_stat(path.clone(), "main".to_string(), None, Some("".to_string())).ok().unwrap()), 4);
+        // the start is counted
+        assert_eq!(count(get_contribute_stat(path.clone(), "v2".to_string(), None, Some(second)).ok().unwrap()), 2);
+        assert_eq!(count(get_contribute_stat(path.clone(), "v1".to_string(), None, None).ok().unwrap()), 2);
+        // the root commit has no parent to start after
+        assert_eq!(count(get_contribute_stat(path.clone(), "v2".to_string(), None, Some(first)).ok().unwrap()), 3);
+        let filter = CommitLogFilter { since: Some("2024-01-03T00:00:00+00:00".to_string()), ..Default::default() };
+        assert_eq!(count(get_contribute_stat(path, "main".to_string(), Some(filter), None).ok().unwrap()), 2);
     }
+
     #[test]
-    fn test_get_file_commit_status() {
-        let path = String::from(r"E:\workSpace\Rust\git-util-native");
-        let commit_hash1 = String::from("2ffc252bee9edcdfa27d0689e4c9f4f80f72b608^");
-        let commit_hash2 = String::from("2ffc252bee9edcdfa27d0689e4c9f4f80f72b608");
-        let file_path = String::from("src/structs.rs");
-        let res = get_file_between_commit_status(path.to_string(), commit_hash1.to_string(), file_path.to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-            }
-        }
+    fn test_build_commit_range() {
+        assert_eq!(build_commit_range("", ""), "HEAD");
+        assert_eq!(build_commit_range("", "main"), "main");
+        assert_eq!(build_commit_range("v1", ""), "v1^..HEAD");
+        assert_eq!(build_commit_range("v1", "main"), "v1^..main");
+        assert_eq!(build_diff_range("", ""), format!("{}..HEAD", EMPTY_TREE_HASH));
+        assert_eq!(build_diff_range("", "main"), format!("{}..main", EMPTY_TREE_HASH));
+        assert_eq!(build_diff_range("v1", ""), "v1..HEAD");
+        assert_eq!(build_diff_range("v1", "main"), "v1..main");
+        let path = init_test_repo("diff_range");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        commit_file(&path, "a.txt", "a\nb\n", "second");
+        let stat = |start: &str, end: &str| {
+            let stat = get_file_modify_stat_between_commit(path.clone(), start.to_string(), end.to_string(), "a.txt".to_string(), None).ok().unwrap();
+            (stat.addition, stat.deletion)
+        };
+        assert_eq!(stat("", ""), (2, 0));
+        assert_eq!(stat("", &first), (1, 0));
+        assert_eq!(stat(&first, ""), (1, 0));
+        assert_eq!(stat(&first, "main"), (1, 0));
+        let stat = get_diff_file_stat_between_commit(path.clone(), "".to_string(), first, "a.txt".to_string(), "a.txt".to_string(), None).ok().unwrap();
+        assert_eq!((stat.addition, stat.deletion), (1, 0));
     }
+
     #[test]
-    fn test_diff_file_context() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let commit_hash1 = String::from("fe2eff4^");
-        let commit_hash2 = String::from("fe2eff4");
-        let file_path = String::from("src/electron/workThreads/WorkPool.ts");
-        let res = diff_file_context(path.to_string(), commit_hash1.to_string(), commit_hash2.to_string(), file_path.to_string());
-        match res {
-            Ok(res) => {
-                println!("===============\n{:#?}\n=======================", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_three_dot_diff() {
+        let path = init_test_repo("three_dot_diff");
+        let base = commit_file(&path, "a.txt", "a\n", "base");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        commit_file(&path, "b.txt", "b\n", "feature");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        commit_file(&path, "c.txt", "c\nc\n", "main");
+        assert_eq!(get_merge_base(path.clone(), "main".to_string(), "feature".to_string()).ok().unwrap(), base);
+        // two-dot compares the tips, the file added on main is deleted on the way to feature
+        let paths = |files: Vec<FileStatus>| files.iter().map(|file| (file.path.to_string(), file.status == FileStatusType::Added)).collect::<Vec<_>>();
+        let two_dot = get_files_status_between_commit(path.clone(), "main".to_string(), "feature".to_string(), None, None, None).ok().unwrap();
+        assert_eq!(paths(two_dot), vec![("b.txt".to_string(), true), ("c.txt".to_string(), false)]);
+        let three_dot = get_files_status_between_commit(path.clone(), "main".to_string(), "feature".to_string(), None, Some(true), None).ok().unwrap();
+        assert_eq!(paths(three_dot), vec![("b.txt".to_string(), true)]);
+        let numstat = get_files_numstat_between_commit(path.clone(), "main".to_string(), "feature".to_string(), None).ok().unwrap();
+        assert_eq!(numstat.iter().map(|file| file.additions + file.deletions).sum::<i32>(), 3);
+        let numstat = get_files_numstat_between_commit(path.clone(), "main".to_string(), "feature".to_string(), Some(true)).ok().unwrap();
+        assert_eq!(numstat.len(), 1);
+        let stat = get_file_modify_stat_between_commit(path.clone(), "main".to_string(), "feature".to_string(), "c.txt".to_string(), None).ok().unwrap();
+        assert_eq!(stat.deletion, 2);
+        let stat = get_file_modify_stat_between_commit(path.clone(), "main".to_string(), "feature".to_string(), "c.txt".to_string(), Some(true)).ok().unwrap();
+        assert_eq!(stat.deletion, 0);
+        let contexts = get_files_diff_context(path.clone(), "main".to_string(), "feature".to_string(), None, Some(true), None).ok().unwrap();
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].context2, "b\n");
+        let context = diff_file_context(path.clone(), "main".to_string(), "feature".to_string(), "b.txt".to_string(), Some(true)).ok().unwrap();
+        assert_eq!(context.commit_hash1, base);
+        let hunks = get_file_diff_hunks(path.clone(), "main".to_string(), "feature".to_string(), "c.txt".to_string(), None, None).ok().unwrap();
+        assert_eq!(hunks.len(), 1);
+        assert!(get_file_diff_hunks(path.clone(), "main".to_string(), "feature".to_string(), "c.txt".to_string(), None, Some(true)).ok().unwrap().is_empty());
+        assert_eq!(get_file_word_diff(path.clone(), "main".to_string(), "feature".to_string(), "c.txt".to_string(), None, None).ok().unwrap().len(), 1);
+        assert!(get_file_word_diff(path.clone(), "main".to_string(), "feature".to_string(), "c.txt".to_string(), None, Some(true)).ok().unwrap().is_empty());
+        exec_git(&path, &["checkout", "-q", "--orphan", "orphan"]);
+        commit_file(&path, "d.txt", "d\n", "orphan");
+        assert!(get_merge_base(path.clone(), "main".to_string(), "orphan".to_string()).is_err());
+        assert!(get_merge_base(path.clone(), "main".to_string(), "missing".to_string()).is_err());
     }
+
     #[test]
-    fn test_get_file_content() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let commit_hash = String::from("fe2eff4");
-        let file_path = String::from("src/renderer/views/setting/index.vue");
-        let res = get_file_content(path.to_string(), commit_hash.to_string(), file_path.to_string());
-        match res {
-            Ok(res) => {
-                println!("===============\n{:#?}\n=======================", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_lfs_pointer() {
+        let pointer = "version https://git-lfs.github.com/spec/v1\noid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize 12345\n";
+        assert_eq!(parse_lfs_pointer(pointer), Some(LfsPointer { oid: "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393".to_string(), size: 12345 }));
+        assert_eq!(parse_lfs_pointer("version https://git-lfs.github.com/spec/v1\nsize 12345\n"), None);
+        assert_eq!(parse_lfs_pointer("plain text\n"), None);
+        let path = init_test_repo("lfs_pointer");
+        let first = commit_file(&path, "model.bin", "a\n", "first");
+        let second = commit_file(&path, "model.bin", pointer, "move to lfs");
+        assert_eq!(get_lfs_pointer(path.clone(), second.clone(), "model.bin".to_string()).ok().unwrap().unwrap().size, 12345);
+        assert_eq!(get_lfs_pointer(path.clone(), first.clone(), "model.bin".to_string()).ok().unwrap(), None);
+        let context = diff_file_context(path.clone(), first.clone(), second.clone(), "model.bin".to_string(), None).ok().unwrap();
+        assert_eq!(context.lfs_pointer1, None);
+        assert_eq!(context.lfs_pointer2.unwrap().size, 12345);
+        let contexts = get_files_diff_context(path, first, second, None, None, None).ok().unwrap();
+        assert!(contexts[0].lfs_pointer2.is_some());
     }
 
     #[test]
-    fn test_get_files_diff_context() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let commit1_hash = String::from("fe2eff4^");
-        let commit2_hash = String::from("fe2eff4");
-        let t1 = get_current_time();
-        let res = get_files_diff_context(path.to_string(), commit1_hash.to_string(), commit2_hash.to_string());
-        match res {
-            Ok(res) => {
-                let t2 = get_current_time();
-                println!("time: {}", t2 - t1);
-                // println!("===============\n{:#?}\n== =====================", res);
-                for file_diff in res {
-                    println!("====================================");
-                    println!("{}", file_diff.file_path);
-                    println!("{}", file_diff.file_status);
-                    println!("{}", file_diff.change_stat);
-                }
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_get_updates_since() {
+        let path = init_test_repo("updates_since");
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        let updates = get_updates_since(path.clone(), "main".to_string(), first.clone()).ok().unwrap();
+        assert_eq!(updates.head, first);
+        assert!(updates.commits.is_empty());
+        assert!(!updates.needs_full_refresh);
+        let second = commit_file(&path, "a.txt", "b\n", "second");
+        let third = commit_file(&path, "a.txt", "c\n", "third");
+        let updates = get_updates_since(path.clone(), "main".to_string(), first.clone()).ok().unwrap();
+        assert_eq!(updates.head, third);
+        assert_eq!(updates.commits.iter().map(|commit| commit.hash.to_string()).collect::<Vec<String>>(), vec![third.clone(), second.clone()]);
+        assert!(!updates.force_updated);
+        exec_git(&path, &["reset", "-q", "--hard", &first]);
+        let rewritten = commit_file(&path, "a.txt", "d\n", "rewritten");
+        let updates = get_updates_since(path.clone(), "main".to_string(), third).ok().unwrap();
+        assert_eq!(updates.head, rewritten);
+        assert!(updates.force_updated);
+        assert!(updates.needs_full_refresh);
+        assert!(updates.commits.is_empty());
+        let updates = get_updates_since(path.clone(), "main".to_string(), "0123456789012345678901234567890123456789".to_string()).ok().unwrap();
+        assert!(!updates.force_updated);
+        assert!(updates.needs_full_refresh);
+        assert!(get_updates_since(path.clone(), "main".to_string(), "".to_string()).ok().unwrap().needs_full_refresh);
+        assert!(get_updates_since(path, "missing".to_string(), first).is_err());
     }
 
     #[test]
-    fn test_is_repository() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let res = is_git_repository(path.to_string());
-        println!("{}", res);
+    fn test_get_repo_dir_list() {
+        let path = init_test_repo("repo_dir_list");
+        commit_file(&path, "README.md", "readme\n", "readme");
+        commit_file(&path, "src/lib.rs", "lib\n", "lib");
+        commit_file(&path, "src/util/mod.rs", "mod\n", "util");
+        let names = |files: &Vec<RepoFileInfo>| files.iter().map(|file| (file.name.to_string(), file.is_dir)).collect::<Vec<_>>();
+        let root = get_repo_dir_list(path.clone(), "main".to_string(), "".to_string()).ok().unwrap();
+        assert_eq!(names(&root), vec![("README.md".to_string(), false), ("src".to_string(), true)]);
+        assert_eq!(root[0].dir, "./");
+        assert_eq!(root[0].object_size, 7);
+        let src = get_repo_dir_list(path.clone(), "main".to_string(), "src".to_string()).ok().unwrap();
+        assert_eq!(names(&src), vec![("lib.rs".to_string(), false), ("util".to_string(), true)]);
+        assert!(src.iter().all(|file| file.dir == "src" && file.children.is_empty()));
+        let util = get_repo_dir_list(path.clone(), "main".to_string(), "src/util/".to_string()).ok().unwrap();
+        assert_eq!(names(&util), vec![("mod.rs".to_string(), false)]);
+        assert_eq!(util[0].dir, "src/util");
+        assert!(get_repo_dir_list(path, "main".to_string(), "missing".to_string()).ok().unwrap().is_empty());
     }
 
     #[test]
-    fn test_get_remote() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let res = get_remote(path.to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-                println!("ERROR");
+    fn test_file_tree_dir() {
+        let path = init_test_repo("file_tree_dir");
+        commit_file(&path, "a/b/c/deep.txt", "deep\n", "deep");
+        commit_file(&path, "a/b/side.txt", "side\n", "side");
+        commit_file(&path, "a/top.txt", "top\n", "top");
+        let tree = get_repo_file_list(path.clone(), "main".to_string()).ok().unwrap();
+        fn collect(nodes: &Vec<RepoFileInfo>, out: &mut Vec<(String, String)>) {
+            for node in nodes {
+                out.push((node.name.to_string(), node.dir.to_string()));
+                collect(&node.children, out);
             }
         }
+        let mut out = Vec::new();
+        collect(&tree, &mut out);
+        let expected = [("a", "./"), ("b", "a"), ("c", "a/b"), ("deep.txt", "a/b/c"), ("side.txt", "a/b"), ("top.txt", "a")];
+        assert_eq!(out, expected.iter().map(|(name, dir)| (name.to_string(), dir.to_string())).collect::<Vec<_>>());
     }
 
     #[test]
-    fn test_get_tag() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let res = get_tags(path.to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_file_list_unquoted_path() {
+        let path = init_test_repo("file_list_unquoted_path");
+        commit_file(&path, "中文 \"q\".txt", "text\n", "quoted");
+        let tree = get_repo_file_list(path.clone(), "main".to_string()).ok().unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].name, "中文 \"q\".txt");
+        assert_eq!(tree[0].object_size, 5);
+        let root = get_repo_dir_list(path, "main".to_string(), "".to_string()).ok().unwrap();
+        assert_eq!(root[0].name, "中文 \"q\".txt");
     }
-    
+
     #[test]
-    fn test_get_file_by_hash() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let res = get_file_by_hash(path.to_string(), "a54c9f80a58e9c23a41aee057f2d14d79cefbe6c".to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_file_list_object_kind() {
+        let path = init_test_repo("file_list_object_kind");
+        commit_file(&path, "src/main.rs", "fn main() {}\n", "main");
+        std::fs::write(std::path::Path::new(&path).join("run.sh"), "echo\n").unwrap();
+        exec_git(&path, &["add", "run.sh"]);
+        exec_git(&path, &["update-index", "--chmod=+x", "run.sh"]);
+        let target = exec_git(&path, &["hash-object", "-w", "src/main.rs"]);
+        exec_git(&path, &["update-index", "--add", "--cacheinfo", &format!("120000,{},link", target)]);
+        exec_git(&path, &["commit", "-q", "-m", "kinds"]);
+        let tree = get_repo_file_list(path.clone(), "main".to_string()).ok().unwrap();
+        let kinds = tree.iter().map(|file| (file.name.to_string(), file.object_mode.to_string(), file.object_kind, file.object_size)).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![
+            ("link".to_string(), "120000".to_string(), ObjectKind::Symlink, 13),
+            ("run.sh".to_string(), "100755".to_string(), ObjectKind::Executable, 5),
+            ("src".to_string(), "".to_string(), ObjectKind::Directory, 0),
+        ]);
+        assert_eq!(tree[2].children[0].object_kind, ObjectKind::File);
+        let root = get_repo_dir_list(path, "main".to_string(), "".to_string()).ok().unwrap();
+        assert_eq!(root[2].object_kind, ObjectKind::Directory);
+        assert_eq!(root[2].object_size, 0);
     }
 
     #[test]
-    fn test_get_commit_title() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let res = get_commit_log_format(path.to_string(), vec!["%s".to_string(), "%h".to_string()], "274b861".to_string(), "b4864c1".to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_file_exists_at() {
+        let path = init_test_repo("file_exists_at");
+        let first = commit_file(&path, "README.md", "readme\n", "readme");
+        commit_file(&path, "src/lib.rs", "lib\n", "lib");
+        assert!(file_exists_at(path.clone(), "main".to_string(), "src/lib.rs".to_string()));
+        assert!(!file_exists_at(path.clone(), first, "src/lib.rs".to_string()));
+        assert!(!file_exists_at(path.clone(), "main".to_string(), "src".to_string()));
+        assert!(!file_exists_at(path, "missing".to_string(), "README.md".to_string()));
     }
 
     #[test]
-    fn test_is_pushed() {
-        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
-        let res = is_pushed(path.to_string(), "3.3.4".to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_get_object_info() {
+        let path = init_test_repo("get_object_info");
+        let hash = commit_file(&path, "src/lib.rs", "lib\n", "lib");
+        assert_eq!(get_object_info(path.clone(), "main:src/lib.rs".to_string()).ok().unwrap(), ObjectInfo { object_type: ObjectType::Blob, size: 4 });
+        assert_eq!(get_object_info(path.clone(), "main:src".to_string()).ok().unwrap().object_type, ObjectType::Tree);
+        assert_eq!(get_object_info(path.clone(), hash).ok().unwrap().object_type, ObjectType::Commit);
+        exec_git(&path, &["tag", "-a", "v1", "-m", "v1"]);
+        assert_eq!(get_object_info(path.clone(), "v1".to_string()).ok().unwrap().object_type, ObjectType::Tag);
+        assert!(get_object_info(path.clone(), "main:missing.txt".to_string()).is_err());
+        assert!(get_object_info(path, "a b".to_string()).is_err());
     }
 
     #[test]
-    fn test_get_contribute_stat() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let res = get_contribute_stat(path.to_string(),"main".to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res.total_stat);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_max_output_bytes() {
+        let _globals = GlobalsGuard::new();
+        let path = init_test_repo("max_output_bytes");
+        commit_file(&path, "large.txt", &"0123456789\n".repeat(200_000), "large");
+        commit_file(&path, "small.txt", "small\n", "small");
+        // far above the output of the other tests running at the same time
+        set_max_output_bytes(1_000_000);
+        let err = get_command_output("git", &path, &["cat-file", "-p", "main:large.txt"]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+        assert!(get_command_output("git", &path, &["cat-file", "-s", "main:large.txt"]).is_ok());
+        assert_eq!(get_file_blob(&path, "main", "large.txt").unwrap_err().kind(), io::ErrorKind::OutOfMemory);
+        assert!(get_file_content(path.clone(), "main".to_string(), "large.txt".to_string()).is_err());
+        let mut batch = CatFileBatch::new(&path).unwrap();
+        let err = batch.read_blob("main", "large.txt").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+        // the reader is still in sync after the skipped blob
+        assert_eq!(batch.read_blob("main", "small.txt").unwrap(), b"small\n".to_vec());
+        assert!(get_files_diff_context(path.clone(), "".to_string(), "main".to_string(), None, None, None).is_err());
+        set_max_output_bytes(0);
+        assert_eq!(get_file_content(path, "main".to_string(), "large.txt".to_string()).ok().unwrap().len(), 2_200_000);
     }
 
     #[test]
-    fn test_get_commit_within_branches() {
-        let path = String::from(r"E:\workSpace\JavaScript\giter");
-        let hash = "274b8619bd9483f9e0622814987c253285584d0d";
-        let res = get_commit_within_branches(path.to_string(), hash.to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_files_diff_context_progress() {
+        let path = init_test_repo("files_diff_context_progress");
+        let first = commit_file(&path, "a.txt", "a\n", "a");
+        commit_file(&path, "b.txt", "b\n", "b");
+        commit_file(&path, "c.txt", "c\n", "c");
+        let mut progress = Vec::new();
+        let res = get_files_diff_context_with_progress(path, first, "main".to_string(), None, None, None, |p| progress.push(p)).ok().unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(progress, vec![
+            DiffProgress { current: 1, total: 2, file_path: "b.txt".to_string() },
+            DiffProgress { current: 2, total: 2, file_path: "c.txt".to_string() },
+        ]);
     }
 
     #[test]
-    fn test_get_status() {
-        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
-        let res = get_status(path.to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(e) => {
-                println!("ERROR");
-            }
-        }
+    fn test_get_head_commit() {
+        let path = init_test_repo("get_head_commit");
+        assert!(get_head_commit(path.clone()).is_err());
+        let first = commit_file(&path, "a.txt", "a\n", "first");
+        let second = commit_file(&path, "b.txt", "b\n", "second");
+        let head = get_head_commit(path.clone()).ok().unwrap();
+        assert_eq!(head.hash, second);
+        assert_eq!(head.short_hash, exec_git(&path, &["rev-parse", "--short", "HEAD"]));
+        assert_eq!(head.title, "second");
+        exec_git(&path, &["checkout", "-q", "--detach", &first]);
+        let head = get_head_commit(path.clone()).ok().unwrap();
+        assert_eq!(head.hash, first);
+        assert_eq!(head.title, "first");
+        exec_git(&path, &["commit", "-q", "--allow-empty", "-m", "odd \x1f subject \x1e"]);
+        let head = get_head_commit(path.clone()).ok().unwrap();
+        assert_eq!(head.title, "odd \x1f subject \x1e");
+        assert_eq!(head.parents, vec![first]);
     }
 
     #[test]
-    fn test_get_all_authors () {
-        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
-        let res = get_all_authors(path.to_string());
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(_) => {
-                println!("ERROR");
-            }
-        }
+    fn test_get_uncommitted_file_stats() {
+        let path = init_test_repo("uncommitted_file_stats");
+        commit_file(&path, "a.txt", "1\n2\n3\n", "a");
+        commit_file(&path, "b.txt", "b\n", "b");
+        let root = std::path::Path::new(&path);
+        std::fs::write(root.join("a.txt"), "1\n2\n3\n4\n").unwrap();
+        exec_git(&path, &["add", "a.txt"]);
+        std::fs::write(root.join("a.txt"), "1\n3\n4\n").unwrap();
+        std::fs::write(root.join("b.txt"), "changed\n").unwrap();
+        std::fs::write(root.join("new.txt"), "new\n").unwrap();
+        let stats = get_uncommitted_file_stats(path).ok().unwrap();
+        assert_eq!(stats, vec![
+            UncommittedFileStat { path: "a.txt".to_string(), staged_additions: 1, staged_deletions: 0, unstaged_additions: 0, unstaged_deletions: 1, untracked: false },
+            UncommittedFileStat { path: "b.txt".to_string(), staged_additions: 0, staged_deletions: 0, unstaged_additions: 1, unstaged_deletions: 1, untracked: false },
+            UncommittedFileStat { path: "new.txt".to_string(), untracked: true, ..Default::default() },
+        ]);
+    }
+
+    #[test]
+    fn test_set_log_encoding() {
+        let _globals = GlobalsGuard::new();
+        let path = init_test_repo("set_log_encoding");
+        commit_file(&path, "a.txt", "a\n", "utf8 中文");
+        // "中文" in GBK without an encoding header, git commit would convert the message as Latin-1
+        let tree = exec_git(&path, &["rev-parse", "HEAD^{tree}"]);
+        let parent = exec_git(&path, &["rev-parse", "HEAD"]);
+        let mut object = format!("tree {}\nparent {}\nauthor tester <tester@example.com> 1700000000 +0000\ncommitter tester <tester@example.com> 1700000000 +0000\n\n", tree, parent).into_bytes();
+        object.extend_from_slice(b"gbk \xd6\xd0\xce\xc4\n");
+        let object_path = std::env::temp_dir().join(format!("git-util-native-gbk-commit-{}", get_current_time()));
+        std::fs::write(&object_path, object).unwrap();
+        let hash = exec_git(&path, &["hash-object", "-t", "commit", "-w", object_path.to_str().unwrap()]);
+        exec_git(&path, &["update-ref", "refs/heads/main", &hash]);
+        let subjects = || get_commit_log_format(path.clone(), vec!["%s".to_string()], "".to_string(), "".to_string(), None).ok().unwrap()
+            .iter().map(|commit| commit.get("message").unwrap().to_string()).collect::<Vec<String>>();
+        assert_eq!(subjects()[0], "gbk \u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}");
+        assert!(set_log_encoding("not-an-encoding".to_string()).is_err());
+        set_log_encoding("GBK".to_string()).ok().unwrap();
+        let decoded = subjects();
+        set_log_encoding("".to_string()).ok().unwrap();
+        assert_eq!(decoded, vec!["gbk 中文".to_string(), "utf8 中文".to_string()]);
+        assert_eq!(decode_with_fallback(b"\xd6\xd0\x1f\xe4\xb8\xad\n", encoding_rs::Encoding::for_label(b"gbk")), "中\x1f中\n");
     }
+
     #[test]
-    fn test_get_branch_authors () {
-        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
-        let branch = "3.3.3".to_string();
-        let res = get_branch_authors(path.to_string(), branch);
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(_) => {
-                println!("ERROR");
-            }
-        }
+    fn test_get_range_summary() {
+        let path = init_test_repo("range_summary");
+        let root = commit_file(&path, "a.txt", "1\n2\n", "a");
+        exec_git(&path, &["checkout", "-q", "-b", "feature"]);
+        commit_file(&path, "a.txt", "1\n3\n4\n", "change a");
+        commit_file(&path, "b.txt", "b\n", "add b");
+        exec_git(&path, &["checkout", "-q", "main"]);
+        commit_file(&path, "c.txt", "c\n", "add c on main");
+        let summary = get_range_summary(path.clone(), "main".to_string(), "feature".to_string()).ok().unwrap();
+        assert_eq!(summary, RangeSummary { files_changed: 2, insertions: 3, deletions: 1 });
+        assert_eq!(get_range_summary(path.clone(), "main".to_string(), "main".to_string()).ok().unwrap(), RangeSummary::default());
+        let summary = get_range_summary(path.clone(), format!("{}^", root), root.to_string()).ok().unwrap();
+        assert_eq!(summary, RangeSummary { files_changed: 1, insertions: 2, deletions: 0 });
+        assert_eq!(get_range_summary(path.clone(), "".to_string(), root).ok().unwrap(), summary);
+        assert!(get_range_summary(path, "main".to_string(), "missing".to_string()).is_err());
     }
 
     #[test]
-    fn test_get_branch_commit_count() {
-        let path = String::from(r"E:\workSpace\Python_Project_File\wizvision3");
-        let branch = "3.3.4".to_string();
-        let res = get_branch_commit_count(path.to_string(), branch);
-        match res {
-            Ok(res) => {
-                println!("{:#?}", res);
-            },
-            Err(_) => {
-                println!("ERROR");
-            }
+    fn test_pathspec_filter() {
+        let path = init_test_repo("pathspec_filter");
+        let first = commit_file(&path, "README.md", "readme\n", "readme");
+        commit_file(&path, "src/lib.rs", "lib\n", "lib");
+        commit_file(&path, "src/util/mod.rs", "mod\n", "util");
+        commit_file(&path, "docs/guide.md", "guide\n", "guide");
+        let pathspec = |specs: &[&str]| Some(specs.iter().map(|spec| spec.to_string()).collect::<Vec<String>>());
+        let status = get_files_status_between_commit(path.clone(), first.to_string(), "HEAD".to_string(), None, None, pathspec(&["src/"])).ok().unwrap();
+        assert_eq!(status.iter().map(|file| file.path.as_str()).collect::<Vec<&str>>(), vec!["src/lib.rs", "src/util/mod.rs"]);
+        let status = get_files_status_between_commit(path.clone(), first.to_string(), "HEAD".to_string(), None, None, pathspec(&[])).ok().unwrap();
+        assert_eq!(status.len(), 3);
+        let contexts = get_files_diff_context(path.clone(), first.to_string(), "HEAD".to_string(), None, None, pathspec(&["*.md"])).ok().unwrap();
+        assert_eq!(contexts.iter().map(|file| file.file_path.as_str()).collect::<Vec<&str>>(), vec!["docs/guide.md"]);
+        let filter = CommitLogFilter { pathspec: pathspec(&["src/util", "docs/"]), ..Default::default() };
+        let commits = get_commit_log_format(path, vec!["%s".to_string()], "".to_string(), "".to_string(), Some(filter)).ok().unwrap();
+        assert_eq!(commits.iter().map(|commit| commit.get("message").unwrap().as_str()).collect::<Vec<&str>>(), vec!["guide", "util"]);
+    }
+
+    #[test]
+    fn test_stash_operations() {
+        let path = init_test_repo("stash_operations");
+        commit_file(&path, "a.txt", "a\n", "init");
+        let root = std::path::Path::new(&path);
+        assert!(stash_save(path.clone(), "".to_string(), false).ok().unwrap().is_none());
+        std::fs::write(root.join("a.txt"), "stashed\n").unwrap();
+        std::fs::write(root.join("new.txt"), "new\n").unwrap();
+        let entry = stash_save(path.clone(), "save a".to_string(), true).ok().unwrap().unwrap();
+        assert_eq!((entry.index, entry.message.as_str()), (0, "save a"));
+        assert!(!root.join("new.txt").exists());
+        let result = stash_apply(path.clone(), 0).ok().unwrap();
+        assert_eq!(result.status, StashApplyStatus::Applied);
+        assert_eq!(std::fs::read_to_string(root.join("a.txt")).unwrap(), "stashed\n");
+        assert_eq!(get_stash_list(path.clone()).ok().unwrap().len(), 1);
+        // the local changes of a.txt would be overwritten by the apply
+        assert!(stash_apply(path.clone(), 0).is_err());
+        exec_git(&path, &["checkout", "--", "a.txt"]);
+        std::fs::remove_file(root.join("new.txt")).unwrap();
+        commit_file(&path, "a.txt", "committed\n", "conflicting change");
+        let result = stash_pop(path.clone(), 0).ok().unwrap();
+        assert_eq!(result.status, StashApplyStatus::Conflict);
+        assert_eq!(result.conflicts, vec!["a.txt"]);
+        assert_eq!(get_stash_list(path.clone()).ok().unwrap().len(), 1);
+        exec_git(&path, &["reset", "-q", "--hard"]);
+        exec_git(&path, &["clean", "-q", "-f"]);
+        stash_drop(path.clone(), 0).ok().unwrap();
+        assert!(get_stash_list(path.clone()).ok().unwrap().is_empty());
+        assert!(stash_drop(path.clone(), 0).is_err());
+        std::fs::write(root.join("a.txt"), "popped\n").unwrap();
+        stash_save(path.clone(), "".to_string(), false).ok().unwrap().unwrap();
+        assert_eq!(stash_pop(path.clone(), 0).ok().unwrap().status, StashApplyStatus::Applied);
+        assert_eq!(std::fs::read_to_string(root.join("a.txt")).unwrap(), "popped\n");
+        assert!(get_stash_list(path).ok().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_repository() {
+        let path = init_test_repo("validate_repository");
+        commit_file(&path, "a.txt", "a\n", "first");
+        assert_eq!(validate_repository(path.clone()), RepositoryValidation::Ok);
+        let sub = std::path::Path::new(&path).join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+        assert_eq!(validate_repository(sub.to_str().unwrap().to_string()), RepositoryValidation::Ok);
+        assert_eq!(validate_repository(format!("{}/a.txt", path)), RepositoryValidation::NotAGitRepository);
+        assert_eq!(validate_repository(format!("{}/missing", path)), RepositoryValidation::PathDoesNotExist);
+        let outside = std::env::temp_dir().join(format!("git-util-native-validate-outside-{}", get_current_time()));
+        std::fs::create_dir_all(&outside).unwrap();
+        // the temp dir may be inside a repository on a developer machine
+        if get_command_output("git", outside.to_str().unwrap(), &["rev-parse", "--git-dir"]).map(|output| !output.status.success()).unwrap_or(false) {
+            assert_eq!(validate_repository(outside.to_str().unwrap().to_string()), RepositoryValidation::NotAGitRepository);
         }
     }
-}
\ No newline at end of file
+}
@@ -37,19 +37,83 @@ pub enum RepoStatus {
 
 #[napi(object)]
 #[derive(Clone)]
+/**
+ * When the repository is shallow, `is_shallow` is true and the commit is only the oldest fetched commit,
+ * not the real root of the branch
+ */
 pub struct BranchCreatedInfo {
     pub name: String,
     pub time: String,
     pub author: Author,
-    pub hash: String
+    pub hash: String,
+    pub is_shallow: bool
 }
 
 #[napi(object)]
 #[derive(Clone)]
+/**
+ * When HEAD is detached, `is_detached` is true and the name is the short hash of the checked out commit
+ */
 pub struct Branch {
     pub name: String,
     pub created: BranchCreatedInfo,
     pub authors: Vec<Author>,
+    pub commit_hash: String,
+    pub is_detached: bool,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A branch name from `git branch --all`, remote-tracking branches are named like `origin/main`
+ * and carry the name of their remote, which is empty for a local branch
+ */
+pub struct BranchRef {
+    pub name: String,
+    pub is_remote: bool,
+    pub remote_name: String,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A local or remote-tracking branch with its tip commit, the upstream is empty when none is configured
+ */
+pub struct BranchDetail {
+    pub name: String,
+    pub is_remote: bool,
+    pub tip_hash: String,
+    pub tip_subject: String,
+    pub tip_author: String,
+    pub tip_date: String,
+    pub upstream: String,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A tag of the repository, the tagger fields and the message are empty for a lightweight tag,
+ * the date is the tagger date of an annotated tag, or the commit date of a lightweight tag
+ */
+pub struct TagInfo {
+    pub name: String,
+    pub target_hash: String,
+    pub tagger_name: String,
+    pub tagger_email: String,
+    pub date: String,
+    pub message: String,
+    pub is_annotated: bool
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * How many commits a branch has that its upstream doesn't (ahead), and the reverse (behind)
+ */
+pub struct AheadBehind {
+    pub upstream: String,
+    pub ahead: i32,
+    pub behind: i32
 }
 
 #[napi(object)]
@@ -60,6 +124,14 @@ pub struct Remote {
     pub operate: Vec<String>
 }
 
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct RemoteDetail {
+    pub name: String,
+    pub fetch_url: String,
+    pub push_url: String
+}
+
 #[napi(object)]
 #[derive(Clone)]
 pub struct RepositoryFull {
@@ -111,19 +183,78 @@ pub struct BranchStatDailyContribute {
     pub authors_stat: Vec<AuthorStatDailyContribute>,
 }
 
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The totals of an author over the whole history of a branch
+ */
+pub struct AuthorTotals {
+    pub author: Author,
+    pub commit_count: i32,
+    pub insertions: i32,
+    pub deletions: i32,
+    pub files_changed: i32
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The changed lines of the files with an extension, the files without an extension are under "(none)".
+ * The binary files have no line changes, they are only counted in `binary_files`
+ */
+pub struct ExtensionChangeStat {
+    pub extension: String,
+    pub additions: i32,
+    pub deletions: i32,
+    pub files: i32,
+    pub binary_files: i32
+}
+
 #[napi(object)]
 #[derive(Clone)]
+/**
+ * The object_mode is the raw mode of git like "100644", object_kind is decoded from it.
+ * The object_size is 0 for the directories and the gitlinks, which have no size
+ */
 pub struct RepoFileInfo {
     pub name: String,
     pub dir: String,
     pub object_mode: String,
+    pub object_kind: ObjectKind,
     pub object_type: String,
     pub object_name: String,
-    pub object_size: String,
+    pub object_size: i64,
     pub is_dir: bool,
     pub children: Vec<RepoFileInfo>
 }
 
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum RepositoryValidation {
+    /** the path is a git repository, or a directory inside one */
+    Ok,
+    /** the path is a file, or a directory outside of any repository */
+    NotAGitRepository,
+    PathDoesNotExist,
+    /** git can't be spawned, see `has_git` */
+    GitNotInstalled,
+    /** the repository is owned by another user and not listed in safe.directory, see `set_safe_directory` */
+    DubiousOwnership,
+    /** the path or the .git directory can't be read by the current user */
+    PermissionDenied
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum ObjectKind {
+    Directory,
+    File,
+    Executable,
+    Symlink,
+    Gitlink,
+    GroupWritable
+}
+
 #[napi]
 #[derive(Debug, PartialEq)]
 pub enum FileStatusType {
@@ -150,6 +281,38 @@ impl Display for FileStatusType {
     }
 }
 
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum StashApplyStatus {
+    /** the changes of the stash are applied to the working tree */
+    Applied,
+    /** the apply stopped on conflicts, the stash is kept even by a pop */
+    Conflict
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The result of applying a stash, the conflicts are the conflicted paths when it stopped on conflicts
+ */
+pub struct StashApplyResult {
+    pub status: StashApplyStatus,
+    pub conflicts: Vec<String>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+/**
+ * The rename and copy detection of the diff, git's default rename detection is used when it's not given.
+ * `find_renames` and `find_copies` are the similarity thresholds in percent, mapped to `-M<n>%` and `-C<n>%`,
+ * `find_copies_harder` also looks for the copy sources in the unmodified files, it is slow on a large repository.
+ */
+pub struct RenameOptions {
+    pub find_renames: Option<i32>,
+    pub find_copies: Option<i32>,
+    pub find_copies_harder: Option<bool>
+}
+
 #[napi(object)]
 #[derive(Clone, Debug)]
 pub struct FileStatus {
@@ -158,6 +321,21 @@ pub struct FileStatus {
     pub message: String
 }
 
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The status and the changed lines of a file between two commits,
+ * `old_path` is the path before the rename or the copy, a binary file has no line counts
+ */
+pub struct FileNumstat {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub status: FileStatusType,
+    pub additions: i32,
+    pub deletions: i32,
+    pub is_binary: bool
+}
+
 #[napi(object)]
 #[derive(Clone, Debug)]
 pub struct FileStatusReport {
@@ -170,16 +348,134 @@ pub struct FileStatusReport {
 
 #[napi(object)]
 #[derive(Clone, Debug)]
+/**
+ * The metadata and the file status of a commit
+ */
+pub struct CommitDetail {
+    pub hash: String,
+    pub short_hash: String,
+    pub parents: Vec<String>,
+    pub title: String,
+    pub author: Author,
+    pub time: String,
+    pub committer: Author,
+    pub commit_time: String,
+    pub status: Vec<FileStatus>
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum ContentKind {
+    Utf8Text,
+    Utf16Text,
+    Binary
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The lfs_pointer1 and lfs_pointer2 are set when context1 or context2 is a Git LFS pointer
+ * instead of the content of the file
+ */
 pub struct FileDiffContext {
     pub commit_hash1: String,
     pub commit_hash2: String,
     pub file_path: String,
     pub change_stat: FileLineChangeStat,
+    pub lfs_pointer1: Option<LfsPointer>,
+    pub lfs_pointer2: Option<LfsPointer>,
     pub context1: String,
     pub context2: String,
     pub file_status: FileStatusType
 }
 
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum ObjectType {
+    Blob,
+    Tree,
+    Commit,
+    Tag
+}
+
+#[napi(object)]
+#[derive(Debug, PartialEq)]
+/**
+ * The type and the size in bytes of an object, the size of a tree or a commit is the size of its raw content
+ */
+pub struct ObjectInfo {
+    #[napi(js_name = "type")]
+    pub object_type: ObjectType,
+    pub size: i64
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq)]
+/**
+ * A Git LFS pointer file, the oid is the sha256 of the object and the size is in bytes,
+ * the object itself is not fetched
+ */
+pub struct LfsPointer {
+    pub oid: String,
+    pub size: i64
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The status of a file in the working tree, a file can be both staged and unstaged
+ * when it's changed again after being added to the index
+ */
+pub struct WorkingTreeFileStatus {
+    pub path: String,
+    pub status: FileStatusType,
+    pub message: String,
+    pub staged: bool,
+    pub unstaged: bool,
+    pub untracked: bool
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The line changes of the index against HEAD (staged), and of the working tree against the index (unstaged),
+ * the untracked files are not counted
+ */
+pub struct WorkingTreeStat {
+    pub staged_files: i32,
+    pub staged_insertions: i32,
+    pub staged_deletions: i32,
+    pub unstaged_files: i32,
+    pub unstaged_insertions: i32,
+    pub unstaged_deletions: i32
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default, PartialEq)]
+/**
+ * The totals of the changes between two commits from `git diff --shortstat`
+ */
+pub struct RangeSummary {
+    pub files_changed: i32,
+    pub insertions: i32,
+    pub deletions: i32
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default, PartialEq)]
+/**
+ * The line changes of a file in the index against HEAD (staged) and in the working tree against the index (unstaged),
+ * an untracked file has no line changes, a binary file has no line counts
+ */
+pub struct UncommittedFileStat {
+    pub path: String,
+    pub staged_additions: i32,
+    pub staged_deletions: i32,
+    pub unstaged_additions: i32,
+    pub unstaged_deletions: i32,
+    pub untracked: bool
+}
+
 #[napi(object)]
 #[derive(Clone, Debug)]
 pub struct FileLineChangeStat {
@@ -192,3 +488,426 @@ impl Display for FileLineChangeStat {
         write!(f, "Addition: {}, Deletion: {}", self.addition, self.deletion)
     }
 }
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The blame ownership of a file, used for bus-factor analysis
+ */
+pub struct FileBusFactor {
+    pub path: String,
+    pub top_author: Author,
+    pub top_author_percent: f64,
+    pub author_count: i32,
+    pub at_risk: bool
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct CommitSummary {
+    pub hash: String,
+    pub short_hash: String,
+    pub parents: Vec<String>,
+    pub title: String,
+    pub author: Author,
+    pub time: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The files diff introduced by a commit
+ */
+pub struct CommitPatch {
+    pub commit: CommitSummary,
+    pub files: Vec<FileDiffContext>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A page of the commit log
+ */
+pub struct CommitLogPage {
+    pub commits: Vec<HashMap<String, String>>,
+    pub has_more: bool
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+/**
+ * The filter of the commit log, every field is optional and an empty string means unbounded.
+ * `since` and `until` are passed to `git log --since/--until`, they filter by the committer date,
+ * and a date without an explicit offset is interpreted in the local timezone of the machine.
+ * `author` is passed to `git log --author`, it is a regex matched against the author name and email.
+ * `no_merges` skips the merge commits (`--no-merges`), `first_parent` only follows the first parent of the merge commits (`--first-parent`).
+ * `pathspec` only keeps the commits changing the files matching the pathspecs, e.g. "src/" or "*.rs", they are passed after "--".
+ */
+pub struct CommitLogFilter {
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub author: Option<String>,
+    pub no_merges: Option<bool>,
+    pub first_parent: Option<bool>,
+    pub pathspec: Option<Vec<String>>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * Whether a path is probably a vendored or a generated file
+ */
+pub struct PathClassification {
+    pub path: String,
+    pub vendored: bool,
+    pub generated: bool
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * The last change of a line, from git blame
+ */
+pub struct BlameLine {
+    pub line_number: i32,
+    pub content: String,
+    pub author: String,
+    pub email: String,
+    pub timestamp: String,
+    pub commit_hash: String
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum DiffLineOrigin {
+    Context,
+    Addition,
+    Deletion
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct DiffLine {
+    pub origin: DiffLineOrigin,
+    pub content: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A hunk of the unified diff, the header is the text after the second "@@"
+ */
+pub struct DiffHunk {
+    pub old_start: i32,
+    pub old_lines: i32,
+    pub new_start: i32,
+    pub new_lines: i32,
+    pub header: String,
+    pub lines: Vec<DiffLine>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A span of a line of the word diff, the added and the deleted words of a changed line are separate spans
+ */
+pub struct WordDiffSegment {
+    pub origin: DiffLineOrigin,
+    pub content: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A hunk of the word diff, every line is a list of spans, a line of only added or only deleted spans
+ * is an added or a deleted line
+ */
+pub struct WordDiffHunk {
+    pub old_start: i32,
+    pub old_lines: i32,
+    pub new_start: i32,
+    pub new_lines: i32,
+    pub header: String,
+    pub lines: Vec<Vec<WordDiffSegment>>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * An entry of `git stash list`, the index is the n of `stash@{n}`
+ */
+pub struct StashEntry {
+    pub index: i32,
+    pub branch: String,
+    pub message: String,
+    pub hash: String,
+    pub timestamp: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * An entry of `git reflog`, the selector is like `HEAD@{0}`,
+ * the action is like "commit" or "checkout" and the timestamp is the time of the ref update
+ */
+pub struct ReflogEntry {
+    pub hash: String,
+    pub selector: String,
+    pub action: String,
+    pub message: String,
+    pub timestamp: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A node of the commit graph, a merge commit has more than one parent and a root commit has none,
+ * the refs are the ref names pointing to the commit, as printed by `git log --decorate`
+ */
+pub struct CommitGraphNode {
+    pub hash: String,
+    pub parents: Vec<String>,
+    pub refs: Vec<String>,
+    pub decorations: CommitDecorations
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default, PartialEq)]
+/**
+ * The ref names pointing to a commit, `head` is true when HEAD points to the commit,
+ * the branches include the remote-tracking branches such as `origin/main`
+ */
+pub struct CommitDecorations {
+    pub branches: Vec<String>,
+    pub tags: Vec<String>,
+    pub head: bool
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum SubmoduleStatus {
+    /** "-", the submodule is not initialized */
+    Uninitialized,
+    /** "+", the checked out commit doesn't match the commit recorded in the superproject */
+    OutOfSync,
+    /** "U", the submodule has merge conflicts */
+    Conflict,
+    /** " ", the checked out commit matches the recorded commit */
+    Clean
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * A submodule of the repository, the url is from .gitmodules,
+ * the current_hash is the checked out commit, or the recorded commit if the submodule is not initialized
+ */
+pub struct SubmoduleInfo {
+    pub name: String,
+    pub path: String,
+    pub url: String,
+    pub current_hash: String,
+    pub status: SubmoduleStatus
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum SignatureStatus {
+    /** a good and valid signature */
+    Good,
+    /** a bad signature */
+    Bad,
+    /** the signature can't be fully verified, e.g. the key is missing, expired, revoked or of unknown validity */
+    Unknown,
+    /** the commit is not signed */
+    None
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The GPG or SSH signature of a commit, from the `%G?`, `%GS` and `%GK` placeholders,
+ * the signer and the key are empty if the commit is not signed
+ */
+pub struct CommitSignature {
+    pub signed: bool,
+    pub status: SignatureStatus,
+    pub signer: String,
+    pub key: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq)]
+/**
+ * The progress of reading the changed files of a diff, current is the 1-based index of the file being read
+ */
+pub struct DiffProgress {
+    pub current: i32,
+    pub total: i32,
+    pub file_path: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq)]
+/**
+ * A progress line of a git network command, e.g. "Receiving objects:  45% (450/1000)"
+ */
+pub struct TransferProgress {
+    pub stage: String,
+    pub percent: i32,
+    pub current: i32,
+    pub total: i32
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+/**
+ * The options of a clone, every field is optional.
+ * `depth` makes a shallow clone with this number of commits, `branch` clones only this branch (`--single-branch`)
+ * and `bare` clones without a working tree
+ */
+pub struct CloneOptions {
+    pub depth: Option<i32>,
+    pub branch: Option<String>,
+    pub bare: Option<bool>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+/**
+ * A path a staging operation failed on, with the error of git
+ */
+pub struct PathFailure {
+    pub path: String,
+    pub message: String
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+/**
+ * The options of a new commit, every field is optional.
+ * `amend` replaces the last commit, `allow_empty` allows a commit without any change
+ * and `author` overrides the author, in the "Name <email>" form
+ */
+pub struct CommitOptions {
+    pub amend: Option<bool>,
+    pub allow_empty: Option<bool>,
+    pub author: Option<String>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+/**
+ * The object counts of a repository as reported by `git count-objects -v`, the sizes are in KiB.
+ * `count` and `size_kb` are the loose objects, `garbage` counts the files in the object database
+ * that are neither valid loose objects nor packs
+ */
+pub struct RepoStats {
+    pub count: i32,
+    pub size_kb: i32,
+    pub in_pack: i32,
+    pub packs: i32,
+    pub size_pack_kb: i32,
+    pub prune_packable: i32,
+    pub garbage: i32,
+    pub size_garbage_kb: i32
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum CherryPickStatus {
+    /** the commit is picked or reverted onto HEAD */
+    Applied,
+    /** the pick or the revert stopped on conflicts, resolve them and commit, or abort it */
+    Conflict,
+    /** the changes are already applied, nothing is committed */
+    Empty
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The result of a cherry-pick or a revert, the hash of the new commit is empty unless it's applied,
+ * and the conflicts are the conflicted paths when it stopped on conflicts
+ */
+pub struct CherryPickResult {
+    pub status: CherryPickStatus,
+    pub hash: String,
+    pub conflicts: Vec<String>
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+/**
+ * The options of a merge, every field is optional.
+ * `no_ff` always creates a merge commit, `squash` stages the changes of the branch without committing them
+ */
+pub struct MergeOptions {
+    pub no_ff: Option<bool>,
+    pub squash: Option<bool>
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum MergeStatus {
+    /** HEAD is moved forward to the branch, no commit is created */
+    FastForward,
+    /** a merge commit is created */
+    MergeCommit,
+    /** the branch is already merged, nothing changes */
+    AlreadyUpToDate,
+    /** the changes of the branch are staged by `squash`, they are not committed */
+    Squashed,
+    /** the merge stopped on conflicts, resolve them and commit, or abort the merge */
+    Conflict
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The result of a merge, the hash is the new HEAD after a fast-forward or a merge commit and empty otherwise,
+ * the conflicts are the unmerged paths when the merge stopped on conflicts
+ */
+pub struct MergeResult {
+    pub status: MergeStatus,
+    pub hash: String,
+    pub conflicts: Vec<String>
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The three versions of a conflicted file, from the stages 1, 2 and 3 of the index,
+ * a side is null when the file doesn't exist on it, e.g. there's no base for an add/add conflict
+ */
+pub struct ConflictVersions {
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The raw output of a git command, the exit code is -1 when git is terminated by a signal
+ */
+pub struct GitOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32
+}
+
+#[napi(object)]
+#[derive(Debug)]
+/**
+ * The commits added to a branch since a commit seen before, the newest commit first.
+ * `force_updated` is true when the commit seen before is no longer an ancestor of the branch,
+ * and `needs_full_refresh` is true when the commits can't be listed incrementally, i.e. the branch is
+ * force updated or the commit seen before is unknown, the commits are empty then
+ */
+pub struct BranchUpdates {
+    pub head: String,
+    pub commits: Vec<CommitSummary>,
+    pub force_updated: bool,
+    pub needs_full_refresh: bool
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use napi::{bindgen_prelude::{AsyncTask, ToNapiValue}, threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode}, Env, Error as napiError, JsError, JsFunction, JsUnknown, NapiValue, Task};
+use napi_derive::napi;
+use crate::structs::{BranchStatDailyContribute, CloneOptions, CommitLogFilter, DiffProgress, FileDiffContext, RenameOptions, RepositoryFull, TransferProgress};
+
+/**
+ * The sync functions return a `JsError`, which can only be turned into a js value,
+ * so the js error is created and wrapped in a napi error to reject the promise with the same error
+ */
+fn into_task_result<T>(env: Env, output: Result<T, JsError>) -> napi::Result<T> {
+    match output {
+        Ok(output) => Ok(output),
+        Err(e) => {
+            let value = unsafe { JsUnknown::from_raw_unchecked(env.raw(), e.into_value(env.raw())) };
+            Err(napiError::from(value))
+        }
+    }
+}
+
+pub struct RepositoryInfoFullTask {
+    path: String,
+}
+
+#[napi]
+impl Task for RepositoryInfoFullTask {
+    type Output = Result<RepositoryFull, JsError>;
+    type JsValue = RepositoryFull;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::get_repository_info_full(self.path.to_string()))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * The async version of `get_repository_info_full`, the git commands run in the libuv thread pool
+ * @param path path to the repository
+ */
+fn get_repository_info_full_async (path: String) -> AsyncTask<RepositoryInfoFullTask> {
+    AsyncTask::new(RepositoryInfoFullTask { path })
+}
+
+pub struct FilesDiffContextTask {
+    repo: String,
+    commit_hash1: String,
+    commit_hash2: String,
+    rename_options: Option<RenameOptions>,
+    three_dot: Option<bool>,
+    pathspec: Option<Vec<String>>,
+    progress: Option<ProgressCallback<DiffProgress>>,
+}
+
+#[napi]
+impl Task for FilesDiffContextTask {
+    type Output = Result<Vec<FileDiffContext>, JsError>;
+    type JsValue = Vec<FileDiffContext>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::get_files_diff_context_with_progress(self.repo.to_string(), self.commit_hash1.to_string(), self.commit_hash2.to_string(), self.rename_options.clone(), self.three_dot, self.pathspec.clone(), |progress| report_progress(&self.progress, progress)))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * The async version of `get_files_diff_context`
+ * @param repo repo path
+ * @param commit_hash1 commit hash1
+ * @param commit_hash2 commit hash2
+ * @param rename_options the rename and copy detection, see `RenameOptions`
+ * @param three_dot compare the merge base of the two commits with the second commit (`a...b`) instead of the two commits (`a..b`)
+ * @param pathspec only the files matching the pathspecs, e.g. "src/" or "*.rs", the whole repository if it is not given or empty
+ * @param progress optional callback receiving the `DiffProgress` before every changed file is read
+ */
+fn get_files_diff_context_async (repo: String, commit_hash1: String, commit_hash2: String, rename_options: Option<RenameOptions>, three_dot: Option<bool>, pathspec: Option<Vec<String>>, progress: Option<JsFunction>) -> Result<AsyncTask<FilesDiffContextTask>, JsError> {
+    let progress = create_progress_callback(progress)?;
+    Ok(AsyncTask::new(FilesDiffContextTask { repo, commit_hash1, commit_hash2, rename_options, three_dot, pathspec, progress }))
+}
+
+pub struct ContributeStatTask {
+    path: String,
+    branch: String,
+    filter: Option<CommitLogFilter>,
+    start_ref: Option<String>,
+}
+
+#[napi]
+impl Task for ContributeStatTask {
+    type Output = Result<BranchStatDailyContribute, JsError>;
+    type JsValue = BranchStatDailyContribute;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::get_contribute_stat(self.path.to_string(), self.branch.to_string(), self.filter.clone(), self.start_ref.clone()))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * The async version of `get_contribute_stat`
+ * @param path path to the repository
+ * @param branch branch name
+ * @param filter optional filter of the counted commits, see `CommitLogFilter`
+ * @param start_ref optional first commit of the range, it is counted
+ */
+fn get_contribute_stat_async (path: String, branch: String, filter: Option<CommitLogFilter>, start_ref: Option<String>) -> AsyncTask<ContributeStatTask> {
+    AsyncTask::new(ContributeStatTask { path, branch, filter, start_ref })
+}
+
+pub struct CommitLogFormatTask {
+    path: String,
+    placeholders: Vec<String>,
+    start_commit: String,
+    end_commit: String,
+    filter: Option<CommitLogFilter>,
+}
+
+#[napi]
+impl Task for CommitLogFormatTask {
+    type Output = Result<Vec<HashMap<String, String>>, JsError>;
+    type JsValue = Vec<HashMap<String, String>>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::get_commit_log_format(self.path.to_string(), self.placeholders.clone(), self.start_commit.to_string(), self.end_commit.to_string(), self.filter.clone()))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * The async version of `get_commit_log_format`
+ * @param path path to the repository
+ * @param placeholders placeholders to get the commit log information
+ * @param start_commit start commit hash, it can be ""
+ * @param end_commit end commit hash, it can be ""
+ * @param filter optional filter of the commits, see `CommitLogFilter`
+ */
+fn get_commit_log_format_async (path: String, placeholders: Vec<String>, start_commit: String, end_commit: String, filter: Option<CommitLogFilter>) -> AsyncTask<CommitLogFormatTask> {
+    AsyncTask::new(CommitLogFormatTask { path, placeholders, start_commit, end_commit, filter })
+}
+
+/**
+ * The js callback receiving the progress of a long command, e.g. a network command, it is called from the libuv thread pool
+ */
+type ProgressCallback<T = TransferProgress> = ThreadsafeFunction<T, ErrorStrategy::Fatal>;
+
+fn create_progress_callback<T: ToNapiValue + 'static>(progress: Option<JsFunction>) -> Result<Option<ProgressCallback<T>>, JsError> {
+    match progress {
+        Some(progress) => {
+            let tsfn = progress
+                .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<T>| Ok(vec![ctx.value]))
+                .map_err(JsError::from)?;
+            Ok(Some(tsfn))
+        }
+        None => Ok(None),
+    }
+}
+
+fn report_progress<T: ToNapiValue + 'static>(callback: &Option<ProgressCallback<T>>, progress: T) {
+    if let Some(callback) = callback {
+        callback.call(progress, ThreadsafeFunctionCallMode::NonBlocking);
+    }
+}
+
+pub struct FetchTask {
+    path: String,
+    remote: String,
+    refspec: String,
+    prune: bool,
+    progress: Option<ProgressCallback>,
+}
+
+#[napi]
+impl Task for FetchTask {
+    type Output = Result<String, JsError>;
+    type JsValue = String;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::fetch_with_progress(&self.path, &self.remote, &self.refspec, self.prune, |progress| report_progress(&self.progress, progress)))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * Fetch from a remote, the git command runs in the libuv thread pool and the promise rejects with the stderr of git if it fails
+ * @param path path to the repository
+ * @param remote the remote to fetch, all the remotes are fetched if it is ""
+ * @param refspec the refspec to fetch, the configured refspecs of the remote are used if it is ""
+ * @param prune remove the remote-tracking branches which no longer exist on the remote (`--prune`)
+ * @param progress optional callback receiving the `TransferProgress` whenever the percent changes
+ * @return the messages of git without the progress, e.g. the updated refs and the warnings
+ */
+fn fetch (path: String, remote: String, refspec: String, prune: bool, progress: Option<JsFunction>) -> Result<AsyncTask<FetchTask>, JsError> {
+    let progress = create_progress_callback(progress)?;
+    Ok(AsyncTask::new(FetchTask { path, remote, refspec, prune, progress }))
+}
+
+pub struct PullTask {
+    path: String,
+    remote: String,
+    branch: String,
+    progress: Option<ProgressCallback>,
+}
+
+#[napi]
+impl Task for PullTask {
+    type Output = Result<String, JsError>;
+    type JsValue = String;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::pull_with_progress(&self.path, &self.remote, &self.branch, |progress| report_progress(&self.progress, progress)))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * Pull a branch of a remote into the current branch, the git command runs in the libuv thread pool.
+ * The promise rejects with "authentication failed", "no upstream branch" or "local changes would be overwritten" errors, see `CustomerGitError`
+ * @param path path to the repository
+ * @param remote the remote to pull from, the upstream of the current branch is used if it is ""
+ * @param branch the branch of the remote, it is only used with a remote
+ * @param progress optional callback receiving the `TransferProgress` whenever the percent changes
+ * @return the messages of git without the progress, e.g. the summary of the merge and the warnings
+ */
+fn pull (path: String, remote: String, branch: String, progress: Option<JsFunction>) -> Result<AsyncTask<PullTask>, JsError> {
+    let progress = create_progress_callback(progress)?;
+    Ok(AsyncTask::new(PullTask { path, remote, branch, progress }))
+}
+
+pub struct PushTask {
+    path: String,
+    remote: String,
+    branch: String,
+    force: bool,
+    set_upstream: bool,
+    progress: Option<ProgressCallback>,
+}
+
+#[napi]
+impl Task for PushTask {
+    type Output = Result<String, JsError>;
+    type JsValue = String;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::push_with_progress(&self.path, &self.remote, &self.branch, self.force, self.set_upstream, |progress| report_progress(&self.progress, progress)))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * Push a branch to a remote, the git command runs in the libuv thread pool.
+ * The promise rejects with "authentication failed", "the push is rejected" or "no upstream branch" errors, see `CustomerGitError`
+ * @param path path to the repository
+ * @param remote the remote to push to, the upstream of the current branch is used if it is ""
+ * @param branch the branch to push, it is only used with a remote
+ * @param force overwrite the remote branch even if it is not a fast-forward (`--force`)
+ * @param set_upstream set the pushed branch as the upstream of the local branch (`--set-upstream`)
+ * @param progress optional callback receiving the `TransferProgress` whenever the percent changes
+ * @return the messages of git without the progress, e.g. the updated refs and the messages of the remote
+ */
+fn push (path: String, remote: String, branch: String, force: bool, set_upstream: bool, progress: Option<JsFunction>) -> Result<AsyncTask<PushTask>, JsError> {
+    let progress = create_progress_callback(progress)?;
+    Ok(AsyncTask::new(PushTask { path, remote, branch, force, set_upstream, progress }))
+}
+
+pub struct CloneTask {
+    url: String,
+    target_dir: String,
+    options: Option<CloneOptions>,
+    progress: Option<ProgressCallback>,
+}
+
+#[napi]
+impl Task for CloneTask {
+    type Output = Result<String, JsError>;
+    type JsValue = String;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::clone_with_progress(&self.url, &self.target_dir, &self.options, |progress| report_progress(&self.progress, progress)))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        into_task_result(env, output)
+    }
+}
+
+#[napi]
+/**
+ * Clone a repository, the git command runs in the libuv thread pool.
+ * The promise rejects with the output of git, which also tells if a partial clone is left in the target directory
+ * @param url the url or the path of the repository
+ * @param target_dir the directory of the new repository, a relative path is resolved against the current directory
+ * @param options optional depth, branch and bare, see `CloneOptions`
+ * @param progress optional callback receiving the `TransferProgress` whenever the percent changes
+ * @return the absolute path of the new repository
+ */
+fn clone (url: String, target_dir: String, options: Option<CloneOptions>, progress: Option<JsFunction>) -> Result<AsyncTask<CloneTask>, JsError> {
+    let progress = create_progress_callback(progress)?;
+    Ok(AsyncTask::new(CloneTask { url, target_dir, options, progress }))
+}
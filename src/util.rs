@@ -1,3 +1,5 @@
+use encoding_rs::Encoding;
+use regex::{Regex, RegexSet};
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -7,6 +9,11 @@ pub fn get_basename(path: &str) -> Option<String> {
     path.file_name().and_then(|f| f.to_str()).map(String::from)
 }
 
+pub fn get_extension(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    path.extension().and_then(|f| f.to_str()).map(String::from)
+}
+
 pub fn get_directory_path(path: &str) -> Option<String> {
     let path = Path::new(path);
     path.parent().and_then(|f| f.to_str()).map(String::from)
@@ -31,4 +38,227 @@ pub fn build_commit_range(start: &str, end: &str) -> String {
         format!("{}^..{}", start, end)
     };
     return commit_range;
-}
\ No newline at end of file
+}
+
+// the hash of the empty tree, used to diff against when a commit has no parent
+pub static EMPTY_TREE_HASH: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/**
+ * Build the range of `git diff` with the same empty semantics as `build_commit_range`:
+ * an empty end is HEAD and an empty start is before the root, i.e. the empty tree.
+ * Unlike the commit range the start is the old side of the diff, its own changes are not included
+ */
+pub fn build_diff_range(start: &str, end: &str) -> String {
+    let start = if start.is_empty() { EMPTY_TREE_HASH } else { start };
+    let end = if end.is_empty() { "HEAD" } else { end };
+    format!("{}..{}", start, end)
+}
+/**
+ * Unquote a path quoted by git in the C style, e.g. "\344\270\255 \"q\".txt",
+ * git quotes the paths with special characters even with -z in some formats like `%(path)` of ls-tree.
+ * A path without the surrounding quotes is returned as it is
+ */
+pub fn unquote_git_path(path: &str) -> String {
+    if path.len() < 2 || !path.starts_with('"') || !path.ends_with('"') {
+        return path.to_string();
+    }
+    let inner = &path.as_bytes()[1..path.len() - 1];
+    let mut bytes = Vec::<u8>::with_capacity(inner.len());
+    let mut i = 0;
+    while i < inner.len() {
+        if inner[i] != b'\\' || i + 1 >= inner.len() {
+            bytes.push(inner[i]);
+            i += 1;
+            continue;
+        }
+        let escaped = inner[i + 1];
+        i += 2;
+        match escaped {
+            b'0'..=b'7' => {
+                // three octal digits of a raw byte
+                let mut value = (escaped - b'0') as u32;
+                let mut digits = 1;
+                while digits < 3 && i < inner.len() && (b'0'..=b'7').contains(&inner[i]) {
+                    value = value * 8 + (inner[i] - b'0') as u32;
+                    i += 1;
+                    digits += 1;
+                }
+                bytes.push(value as u8);
+            }
+            b'a' => bytes.push(0x07),
+            b'b' => bytes.push(0x08),
+            b'f' => bytes.push(0x0c),
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'v' => bytes.push(0x0b),
+            other => bytes.push(other),
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+// The heuristics of the vendored and generated files, add a pattern here to extend the rule set
+static VENDORED_PATTERNS: &[&str] = &[
+    r"(^|/)node_modules/",
+    r"(^|/)bower_components/",
+    r"(^|/)vendor/",
+    r"(^|/)third_party/",
+    r"(^|/)Pods/",
+];
+static GENERATED_PATTERNS: &[&str] = &[
+    r"(^|/)dist/",
+    r"\.min\.(js|css)$",
+    r"\.(js|css)\.map$",
+    r"(^|/)package-lock\.json$",
+    r"(^|/)yarn\.lock$",
+    r"(^|/)pnpm-lock\.yaml$",
+    r"(^|/)Cargo\.lock$",
+    r"(^|/)go\.sum$",
+    r"\.pb\.go$",
+    r"_pb2\.py$",
+];
+
+pub fn is_vendored_path(path: &str) -> bool {
+    static VENDORED_RE: std::sync::OnceLock<RegexSet> = std::sync::OnceLock::new();
+    VENDORED_RE.get_or_init(|| RegexSet::new(VENDORED_PATTERNS).unwrap()).is_match(path)
+}
+
+pub fn is_generated_path(path: &str) -> bool {
+    static GENERATED_RE: std::sync::OnceLock<RegexSet> = std::sync::OnceLock::new();
+    GENERATED_RE.get_or_init(|| RegexSet::new(GENERATED_PATTERNS).unwrap()).is_match(path)
+}
+
+/**
+ * Convert a gitattributes glob to a regex, a pattern without "/" matches the basename in any directory
+ */
+pub fn glob_to_regex(glob: &str) -> String {
+    let anchored = glob.trim_end_matches('/').contains('/');
+    let glob = glob.trim_start_matches('/');
+    let mut regex = String::from(if anchored { "^" } else { "(^|/)" });
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if glob.ends_with('/') {
+        regex.push_str(".*");
+    }
+    regex + "(/|$)"
+}
+
+/**
+ * Parse the linguist-vendored and linguist-generated markers of a .gitattributes file,
+ * return (path regex, attribute name, value) in the order of the file
+ */
+pub fn parse_linguist_attributes(gitattributes: &str) -> Vec<(Regex, String, bool)> {
+    let mut attributes = Vec::new();
+    for line in gitattributes.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_ascii_whitespace();
+        let Some(pattern) = parts.next() else {
+            continue;
+        };
+        let Ok(regex) = Regex::new(&glob_to_regex(pattern)) else {
+            continue;
+        };
+        for attr in parts {
+            let (name, value) = if let Some(name) = attr.strip_prefix('-') {
+                (name, false)
+            } else if let Some((name, value)) = attr.split_once('=') {
+                (name, value != "false")
+            } else {
+                (attr, true)
+            };
+            if name == "linguist-vendored" || name == "linguist-generated" {
+                attributes.push((regex.clone(), name.to_string(), value));
+            }
+        }
+    }
+    attributes
+}
+
+/**
+ * Guess the byte order of a UTF-16 text from the BOM, or from the NUL bytes of the ASCII characters
+ * when there is no BOM, return Some(true) for little endian and Some(false) for big endian
+ */
+pub fn detect_utf16(content: &[u8]) -> Option<bool> {
+    if content.starts_with(&[0xFF, 0xFE]) {
+        return Some(true)
+    }
+    if content.starts_with(&[0xFE, 0xFF]) {
+        return Some(false)
+    }
+    let sample = &content[..content.len().min(8000)];
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None
+    }
+    let mut even_nul = 0;
+    let mut odd_nul = 0;
+    for (i, c) in sample.iter().take(pairs * 2).enumerate() {
+        if *c == 0 {
+            if i % 2 == 0 {
+                even_nul += 1;
+            } else {
+                odd_nul += 1;
+            }
+        }
+    }
+    // the text is mostly ASCII, so one byte of most of the code units is NUL, and the other never is
+    if even_nul == 0 && odd_nul * 10 >= pairs * 7 {
+        Some(true)
+    } else if odd_nul == 0 && even_nul * 10 >= pairs * 7 {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/**
+ * Decode the text content of a file, UTF-16 is converted by `detect_utf16`,
+ * and anything else is read as UTF-8 with the invalid bytes replaced
+ */
+pub fn decode_text(content: &[u8]) -> String {
+    let Some(little_endian) = detect_utf16(content) else {
+        return String::from_utf8_lossy(content).to_string()
+    };
+    let units = content.chunks_exact(2).map(|pair| {
+        if little_endian {
+            u16::from_le_bytes([pair[0], pair[1]])
+        } else {
+            u16::from_be_bytes([pair[0], pair[1]])
+        }
+    }).collect::<Vec<u16>>();
+    let text = String::from_utf16_lossy(&units);
+    text.strip_prefix('\u{FEFF}').map(|t| t.to_string()).unwrap_or(text)
+}
+
+/**
+ * Decode the output of git log, the messages of the commits without an encoding header are printed as they are stored,
+ * so the pieces between the newlines and the separators which are not valid UTF-8 are decoded with the fallback encoding.
+ * The separators are ASCII and never a part of a multi-byte character in the legacy encodings like GBK or Shift_JIS
+ */
+pub fn decode_with_fallback(content: &[u8], fallback: Option<&'static Encoding>) -> String {
+    let Some(fallback) = fallback else {
+        return String::from_utf8_lossy(content).to_string()
+    };
+    let mut text = String::with_capacity(content.len());
+    for piece in content.split_inclusive(|byte| matches!(byte, b'\n' | b'\0' | 0x1e | 0x1f)) {
+        match std::str::from_utf8(piece) {
+            Ok(piece) => text.push_str(piece),
+            Err(_) => text.push_str(&fallback.decode_without_bom_handling(piece).0),
+        }
+    }
+    text
+}